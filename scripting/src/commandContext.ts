@@ -27,6 +27,15 @@ export interface CommandContext {
    */
   args: string[];
 
+  /**
+   * Arguments parsed using the arguments defined on the command, keyed
+   * by argument name. Omitted optional arguments are null.
+   *
+   * Users are provided as their lowercase username without the @ prefix,
+   * integers as numbers and choices / the rest of the message as strings
+   */
+  arguments: Record<string, string | number | null>;
+
   /**
    * Get the target user of the command within the context of a command
    * only available within command scripts
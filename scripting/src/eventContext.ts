@@ -131,6 +131,11 @@ interface ChatInputData {
    * Cheered bits amount if the message contained a bits cheer
    */
   cheer: number | null;
+  /**
+   * Named capture groups from the matched chat pattern
+   * (Only present for regex chat pattern triggers)
+   */
+  captures: Record<string, string>;
 }
 
 interface Cheermote {
@@ -219,6 +224,119 @@ interface ShoutoutReceiveInputData {
   viewerCount: number;
 }
 
+interface HypeTrainInputData {
+  /**
+   * Phase of the hype train
+   */
+  phase: "Begin" | "Progress" | "End";
+  /**
+   * Current level of the hype train
+   */
+  level: number;
+  /**
+   * Total points contributed to the hype train
+   */
+  total: number;
+  /**
+   * Points contributed towards the current level
+   */
+  progress: number;
+  /**
+   * Points required to reach the next level
+   */
+  goal: number;
+}
+
+interface PollChoice {
+  /**
+   * ID of the choice
+   */
+  id: string;
+  /**
+   * Title of the choice
+   */
+  title: string;
+  /**
+   * Total votes for the choice
+   */
+  votes: number;
+}
+
+interface PollInputData {
+  /**
+   * ID of the poll
+   */
+  pollId: string;
+  /**
+   * Phase of the poll
+   */
+  phase: "Begin" | "Progress" | "End";
+  /**
+   * Question displayed for the poll
+   */
+  title: string;
+  /**
+   * Choices for the poll and their votes
+   */
+  choices: PollChoice[];
+  /**
+   * Title of the choice with the most votes, this is the
+   * current leader until the poll has ended
+   */
+  winningChoice: string | null;
+  /**
+   * Votes for the winning choice
+   */
+  winningVotes: number;
+}
+
+interface PredictionOutcome {
+  /**
+   * ID of the outcome
+   */
+  id: string;
+  /**
+   * Title of the outcome
+   */
+  title: string;
+  /**
+   * Number of users that predicted the outcome
+   */
+  users: number;
+  /**
+   * Channel points spent predicting the outcome
+   */
+  channelPoints: number;
+}
+
+interface PredictionInputData {
+  /**
+   * ID of the prediction
+   */
+  predictionId: string;
+  /**
+   * Phase of the prediction
+   */
+  phase: "Begin" | "Progress" | "Lock" | "End";
+  /**
+   * Question displayed for the prediction
+   */
+  title: string;
+  /**
+   * Outcomes for the prediction and their predictions
+   */
+  outcomes: PredictionOutcome[];
+  /**
+   * Title of the winning outcome, only present once the
+   * prediction has been resolved
+   */
+  winningOutcome: string | null;
+  /**
+   * Channel points spent on the winning outcome
+   */
+  winningPoints: number;
+}
+
 type EventInputData =
   | RedeemInputData
   | BitsInputData
@@ -229,7 +347,10 @@ type EventInputData =
   | ChatInputData
   | RaidInputData
   | AdBreakBeginInputData
-  | ShoutoutReceiveInputData;
+  | ShoutoutReceiveInputData
+  | HypeTrainInputData
+  | PollInputData
+  | PredictionInputData;
 
 export type EventContext = EventData & EventInputData;
 
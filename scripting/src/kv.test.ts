@@ -6,6 +6,11 @@ import {
   getArray,
   setObject,
   getObject,
+  increment,
+  decrement,
+  compareAndSet,
+  updateObject,
+  createCounter,
   createScopedCounter,
 } from "./kv";
 
@@ -18,6 +23,8 @@ describe("KV Operations", () => {
           op_kv_get: jest.fn(),
           op_kv_set: jest.fn(),
           op_kv_remove: jest.fn(),
+          op_kv_increment: jest.fn(),
+          op_kv_compare_and_set: jest.fn().mockResolvedValue(true),
         },
       },
     } as never;
@@ -63,6 +70,7 @@ describe("KV Operations", () => {
         "Text",
         "testKey",
         "testValue",
+        undefined,
       );
     });
 
@@ -87,6 +95,7 @@ describe("KV Operations", () => {
         "Array",
         "testKey",
         array,
+        undefined,
       );
     });
 
@@ -135,6 +144,7 @@ describe("KV Operations", () => {
         "Object",
         "testKey",
         JSON.stringify(obj),
+        undefined,
       );
     });
 
@@ -200,7 +210,77 @@ describe("KV Operations", () => {
         JSON.stringify({}),
       );
       await counter.set("scopeC", 10);
-      expect(Deno.core.ops.op_kv_set).toHaveBeenCalled();
+      expect(Deno.core.ops.op_kv_compare_and_set).toHaveBeenCalledWith(
+        "Object",
+        "counterKey",
+        JSON.stringify({}),
+        JSON.stringify({ scopeC: 10 }),
+        undefined,
+      );
+    });
+  });
+
+  describe("increment", () => {
+    it("should call op_kv_increment with the correct arguments", async () => {
+      (Deno.core.ops.op_kv_increment as jest.Mock).mockResolvedValueOnce(6);
+      const value = await increment("testKey", 5);
+      expect(value).toBe(6);
+      expect(Deno.core.ops.op_kv_increment).toHaveBeenCalledWith("testKey", 5);
+    });
+
+    it("should decrement using a negative amount", async () => {
+      await decrement("testKey");
+      expect(Deno.core.ops.op_kv_increment).toHaveBeenCalledWith("testKey", -1);
+    });
+
+    it("should throw an error if amount is not an integer", async () => {
+      await expect(() => increment("testKey", 1.5)).toThrow(
+        new Error("amount must be an integer"),
+      );
+    });
+  });
+
+  describe("compareAndSet", () => {
+    it("should call op_kv_compare_and_set with the correct arguments", async () => {
+      const stored = await compareAndSet("testKey", null, "testValue");
+      expect(stored).toBe(true);
+      expect(Deno.core.ops.op_kv_compare_and_set).toHaveBeenCalledWith(
+        "Text",
+        "testKey",
+        null,
+        "testValue",
+        undefined,
+      );
+    });
+  });
+
+  describe("updateObject", () => {
+    it("should retry when the value was changed while updating", async () => {
+      (Deno.core.ops.op_kv_get as jest.Mock)
+        .mockResolvedValueOnce(JSON.stringify({ a: 1 }))
+        .mockResolvedValueOnce(JSON.stringify({ a: 2 }));
+      (Deno.core.ops.op_kv_compare_and_set as jest.Mock).mockResolvedValueOnce(
+        false,
+      );
+
+      const value = await updateObject<{ a: number }>(
+        "testKey",
+        { a: 0 },
+        (value) => ({ a: value.a + 1 }),
+      );
+      expect(value).toEqual({ a: 3 });
+      expect(Deno.core.ops.op_kv_compare_and_set).toHaveBeenCalledTimes(2);
+    });
+  });
+
+  describe("createCounter", () => {
+    it("should increase the counter atomically", async () => {
+      const counter = createCounter("counterKey");
+      await counter.increase(2);
+      expect(Deno.core.ops.op_kv_increment).toHaveBeenCalledWith(
+        "counterKey",
+        2,
+      );
     });
   });
 });
@@ -1,15 +1,56 @@
+/**
+ * Type of value stored within the KV store
+ */
+export type KeyValueType = "Text" | "Number" | "Object" | "Array";
+
+export interface KeyValueOptions {
+  /**
+   * Time in milliseconds the value should be stored for, once expired
+   * the value is treated as if it was never stored. Values are stored
+   * forever when not specified
+   */
+  ttl?: number;
+}
+
+/**
+ * Entry within the KV store
+ */
+export interface KeyValueEntry {
+  /**
+   * Key the value is stored under
+   */
+  key: string;
+  /**
+   * Type of the stored value
+   */
+  type: KeyValueType;
+  /**
+   * Raw stored value, JSON for objects and arrays
+   */
+  value: string;
+  /**
+   * ISO date time the value expires at, null if the value never expires
+   */
+  expires_at: string | null;
+}
+
 /**
  * Store a string value within the KV store
  *
  * @param key The key to store the value under
  * @param value The string value to store
+ * @param options Additional options such as the time to live
  * @returns Promise resolved when the value is stored
  */
-export function setText(key: string, value: string): Promise<void> {
+export function setText(
+  key: string,
+  value: string,
+  options?: KeyValueOptions,
+): Promise<void> {
   if (typeof key !== "string") throw new Error("key must be a string");
   if (typeof value !== "string") throw new Error("value must be a string");
 
-  return Deno.core.ops.op_kv_set("Text", key, value);
+  return Deno.core.ops.op_kv_set("Text", key, value, options?.ttl);
 }
 
 /**
@@ -65,13 +106,18 @@ export function remove(key: string): Promise<void> {
  *
  * @param key The key to store the value under
  * @param value The number value to store
+ * @param options Additional options such as the time to live
  * @returns Promise resolved when the value is stored
  */
-export function setNumber(key: string, value: number): Promise<void> {
+export function setNumber(
+  key: string,
+  value: number,
+  options?: KeyValueOptions,
+): Promise<void> {
   if (typeof key !== "string") throw new Error("key must be a string");
   if (typeof value !== "number") throw new Error("value must be a number");
 
-  return Deno.core.ops.op_kv_set("Number", key, value);
+  return Deno.core.ops.op_kv_set("Number", key, value, options?.ttl);
 }
 
 /**
@@ -116,13 +162,18 @@ export async function getNumber(
  *
  * @param key The key to store the value under
  * @param value The array value to store
+ * @param options Additional options such as the time to live
  * @returns Promise resolved when the value is stored
  */
-export function setArray<T>(key: string, value: T[]): Promise<void> {
+export function setArray<T>(
+  key: string,
+  value: T[],
+  options?: KeyValueOptions,
+): Promise<void> {
   if (typeof key !== "string") throw new Error("key must be a string");
   if (!Array.isArray(value)) throw new Error("value must be an array");
 
-  return Deno.core.ops.op_kv_set("Array", key, value);
+  return Deno.core.ops.op_kv_set("Array", key, value, options?.ttl);
 }
 
 /**
@@ -164,13 +215,23 @@ export async function getArray<T>(
  *
  * @param key The key to store the value under
  * @param value The object value to store
+ * @param options Additional options such as the time to live
  * @returns Promise resolved when the value is stored
  */
-export function setObject<T>(key: string, value: T): Promise<void> {
+export function setObject<T>(
+  key: string,
+  value: T,
+  options?: KeyValueOptions,
+): Promise<void> {
   if (typeof key !== "string") throw new Error("key must be a string");
   if (typeof value !== "object") throw new Error("value must be a object");
 
-  return Deno.core.ops.op_kv_set("Object", key, JSON.stringify(value));
+  return Deno.core.ops.op_kv_set(
+    "Object",
+    key,
+    JSON.stringify(value),
+    options?.ttl,
+  );
 }
 
 /**
@@ -207,6 +268,140 @@ export async function getObject<T>(
   return JSON.parse(value);
 }
 
+/**
+ * Atomically increase a number value within the KV store, missing
+ * values are treated as zero
+ *
+ * Unlike reading and then storing the value this is safe to use from
+ * multiple scripts running at the same time
+ *
+ * @param key The key the value is under
+ * @param amount Amount to increase by (Default: 1)
+ * @returns Promise resolved to the new value
+ */
+export function increment(key: string, amount: number = 1): Promise<number> {
+  if (typeof key !== "string") throw new Error("key must be a string");
+  if (!Number.isInteger(amount)) throw new Error("amount must be an integer");
+
+  return Deno.core.ops.op_kv_increment(key, amount);
+}
+
+/**
+ * Atomically decrease a number value within the KV store, missing
+ * values are treated as zero
+ *
+ * @param key The key the value is under
+ * @param amount Amount to decrease by (Default: 1)
+ * @returns Promise resolved to the new value
+ */
+export function decrement(key: string, amount: number = 1): Promise<number> {
+  return increment(key, -amount);
+}
+
+/**
+ * Atomically replace a value within the KV store only if the currently
+ * stored value matches the expected value
+ *
+ * @param key The key the value is under
+ * @param expected The expected raw stored value, null to only store the value if nothing is stored
+ * @param value The raw value to store
+ * @param type The type of value being stored (Default: "Text")
+ * @param options Additional options such as the time to live
+ * @returns Promise resolved to whether the value was stored
+ */
+export function compareAndSet(
+  key: string,
+  expected: string | null,
+  value: string,
+  type: KeyValueType = "Text",
+  options?: KeyValueOptions,
+): Promise<boolean> {
+  if (typeof key !== "string") throw new Error("key must be a string");
+  if (expected !== null && typeof expected !== "string")
+    throw new Error("expected must be a string or null");
+  if (typeof value !== "string") throw new Error("value must be a string");
+
+  return Deno.core.ops.op_kv_compare_and_set(
+    type,
+    key,
+    expected,
+    value,
+    options?.ttl,
+  );
+}
+
+// Maximum number of attempts to update an object before giving up
+const MAX_UPDATE_ATTEMPTS = 100;
+
+/**
+ * Atomically update an object value within the KV store, the update
+ * is retried with the latest value if the value was changed by another
+ * script while updating
+ *
+ * @param key The key the value is under
+ * @param defaultValue Value to update when nothing is stored
+ * @param update Function creating the updated value from the current value
+ * @returns Promise resolved to the updated value
+ */
+export async function updateObject<T>(
+  key: string,
+  defaultValue: T,
+  update: Transform<T>,
+): Promise<T> {
+  if (typeof key !== "string") throw new Error("key must be a string");
+
+  for (let attempt = 0; attempt < MAX_UPDATE_ATTEMPTS; attempt++) {
+    const current: string | null = await Deno.core.ops.op_kv_get(key);
+    const value: T = current === null ? defaultValue : JSON.parse(current);
+    const updated = update(value);
+
+    const stored = await compareAndSet(
+      key,
+      current,
+      JSON.stringify(updated),
+      "Object",
+    );
+    if (stored) return updated;
+  }
+
+  throw new Error("failed to update value, too many concurrent updates");
+}
+
+/**
+ * List all values in the KV store whose key starts with the provided prefix
+ *
+ * @param prefix The key prefix (i.e "leaderboard.")
+ * @returns Promise resolved to the matching entries ordered by key
+ */
+export function list(prefix: string): Promise<KeyValueEntry[]> {
+  if (typeof prefix !== "string") throw new Error("prefix must be a string");
+  return Deno.core.ops.op_kv_list(prefix);
+}
+
+/**
+ * Remove all values in the KV store whose key starts with the provided prefix
+ *
+ * @param prefix The key prefix (i.e "leaderboard.")
+ * @returns Promise resolved to the number of removed values
+ */
+export function removePrefix(prefix: string): Promise<number> {
+  if (typeof prefix !== "string") throw new Error("prefix must be a string");
+  return Deno.core.ops.op_kv_remove_prefix(prefix);
+}
+
+/**
+ * Create a key within the namespace of the current event or command,
+ * keys within the namespace are not shared with other events and commands
+ * and are always accessible regardless of the script permissions
+ *
+ * @param key The key within the namespace
+ * @returns The full key (i.e "event.{id}.{key}")
+ */
+export function localKey(key: string): string {
+  if (typeof key !== "string") throw new Error("key must be a string");
+  return Deno.core.ops.op_kv_namespace(api.internal.getContext()) + key;
+}
+
 type Transform<T> = (value: T) => T;
 
 export interface Counter {
@@ -251,18 +446,11 @@ export interface Counter {
 export function createCounter(key: string): Counter {
   if (typeof key !== "string") throw new Error("key must be a string");
 
-  const update = async (action: Transform<number>) => {
-    const value = await getNumber(key, 0);
-    const updated = action(value);
-    await setNumber(key, updated);
-    return updated;
-  };
-
   return {
     get: () => getNumber(key, 0),
     set: (value: number) => setNumber(key, value),
-    increase: (amount?: number) => update((value) => value + (amount ?? 1)),
-    decrease: (amount?: number) => update((value) => value - (amount ?? 1)),
+    increase: (amount?: number) => increment(key, amount ?? 1),
+    decrease: (amount?: number) => decrement(key, amount ?? 1),
   };
 }
 
@@ -341,11 +529,11 @@ export function createScopedCounter(key: string): ScopedCounter {
    * @returns Promise resolved to the new value
    */
   const update = async (scope: string, action: Transform<number>) => {
-    const objectValue = await getObject<ScopedCounterObject>(key, {});
-    const value = objectValue[scope] ?? 0;
-    const updated = action(value);
-    objectValue[scope] = updated;
-    await setObject(key, objectValue);
+    let updated = 0;
+    await updateObject<ScopedCounterObject>(key, {}, (objectValue) => {
+      updated = action(objectValue[scope] ?? 0);
+      return { ...objectValue, [scope]: updated };
+    });
     return updated;
   };
 
@@ -358,9 +546,7 @@ export function createScopedCounter(key: string): ScopedCounter {
     set: async (scope: string, value: number) => {
       if (typeof scope !== "string") throw new Error("scope must be a string");
       if (typeof value !== "number") throw new Error("value must be a number");
-      const objectValue = await getObject<ScopedCounterObject>(key, {});
-      objectValue[scope] = value;
-      return setObject(key, objectValue);
+      await update(scope, () => value);
     },
     increase: (scope: string, amount?: number) =>
       update(scope, (value) => value + (amount ?? 1)),
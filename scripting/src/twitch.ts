@@ -112,6 +112,160 @@ export async function sendChatAnnouncement(
   if (!response.ok) throw new TwitchError(response);
 }
 
+/**
+ * Create a twitch poll on the channel
+ *
+ * @param title Question displayed for the poll
+ * @param choices Choices viewers can vote for (2-5 choices)
+ * @param duration Duration of the poll in seconds (15-1800 seconds)
+ * @returns Promise resolved with the ID of the created poll
+ */
+export async function createPoll(
+  title: string,
+  choices: string[],
+  duration: number = 60,
+): Promise<string> {
+  return Deno.core.ops.op_twitch_create_poll(title, choices, duration);
+}
+
+/**
+ * Create a twitch prediction on the channel
+ *
+ * @param title Question displayed for the prediction
+ * @param outcomes Outcomes viewers can predict (2-10 outcomes)
+ * @param duration Duration in seconds that predictions are accepted for (30-1800 seconds)
+ * @returns Promise resolved with the ID of the created prediction
+ */
+export async function createPrediction(
+  title: string,
+  outcomes: string[],
+  duration: number = 60,
+): Promise<string> {
+  return Deno.core.ops.op_twitch_create_prediction(title, outcomes, duration);
+}
+
+/**
+ * Status to update a channel point redemption to, "CANCELED" refunds the
+ * points to the viewer
+ */
+export type RedemptionStatus = "FULFILLED" | "CANCELED";
+
+/**
+ * Update the status of a channel point redemption
+ *
+ * Twitch only allows updating redemptions for rewards that were
+ * created by VTFTK
+ *
+ * @param rewardId ID of the reward that was redeemed
+ * @param redemptionId ID of the redemption to update
+ * @param status New status for the redemption
+ * @returns Promise resolved when the status is updated
+ */
+export async function updateRedemptionStatus(
+  rewardId: string,
+  redemptionId: string,
+  status: RedemptionStatus,
+): Promise<void> {
+  return Deno.core.ops.op_twitch_update_redemption_status(
+    rewardId,
+    redemptionId,
+    status,
+  );
+}
+
+export interface CreateRewardOptions {
+  /**
+   * Title of the reward
+   */
+  title: string;
+  /**
+   * Cost of the reward in channel points
+   */
+  cost: number;
+  /**
+   * Prompt shown to the viewer when redeeming
+   */
+  prompt?: string;
+  /**
+   * Global cooldown in seconds, 0 disables the cooldown
+   */
+  cooldown?: number;
+  /**
+   * Whether the reward is enabled (Defaults to true)
+   */
+  enabled?: boolean;
+}
+
+export interface UpdateRewardOptions {
+  /**
+   * New title of the reward
+   */
+  title?: string;
+  /**
+   * New cost of the reward in channel points
+   */
+  cost?: number;
+  /**
+   * New prompt shown to the viewer when redeeming
+   */
+  prompt?: string;
+  /**
+   * New global cooldown in seconds, 0 disables the cooldown
+   */
+  cooldown?: number;
+  /**
+   * Whether the reward is enabled, disabled rewards are hidden from viewers
+   */
+  enabled?: boolean;
+  /**
+   * Whether the reward is paused, paused rewards are visible but cannot be redeemed
+   */
+  paused?: boolean;
+}
+
+/**
+ * Create a custom channel point reward owned by VTFTK
+ *
+ * @param options Details for the reward
+ * @returns Promise resolved with the ID of the created reward
+ */
+export async function createReward(
+  options: CreateRewardOptions,
+): Promise<string> {
+  return Deno.core.ops.op_twitch_create_reward(options);
+}
+
+/**
+ * Update a custom channel point reward, only the provided options
+ * are changed
+ *
+ * Twitch only allows updating rewards that were created by VTFTK
+ *
+ * @param rewardId ID of the reward to update
+ * @param options Changes to make to the reward
+ * @returns Promise resolved when the reward is updated
+ */
+export async function updateReward(
+  rewardId: string,
+  options: UpdateRewardOptions,
+): Promise<void> {
+  return Deno.core.ops.op_twitch_update_reward(rewardId, options);
+}
+
+/**
+ * Pause or unpause a custom channel point reward
+ *
+ * @param rewardId ID of the reward to update
+ * @param paused Whether the reward should be paused
+ * @returns Promise resolved when the reward is updated
+ */
+export async function setRewardPaused(
+  rewardId: string,
+  paused: boolean,
+): Promise<void> {
+  return updateReward(rewardId, { paused });
+}
+
 export type TwitchUserId = string;
 
 export type TwitchUsername = string;
@@ -13,7 +13,11 @@ use crate::{
         },
         DbPool,
     },
-    export::{self, ExportedCommandModel},
+    events::{
+        arguments::validate_command_arguments,
+        counter::{self, Counter},
+    },
+    export::{self, ExportedCommandModel, ImportScriptPermissions},
 };
 use anyhow::Context;
 use tauri::{async_runtime::spawn_blocking, AppHandle, State};
@@ -54,6 +58,8 @@ pub async fn create_command(
     db: State<'_, DbPool>,
 ) -> CmdResult<CommandWithAliases> {
     let db = db.inner();
+    validate_command_arguments(&create.config.arguments)?;
+
     let command = CommandModel::create(db, create).await?;
     let aliases = CommandAliasModel::get_aliases(db, command.id).await?;
 
@@ -68,6 +74,10 @@ pub async fn update_command(
     db: State<'_, DbPool>,
 ) -> CmdResult<CommandWithAliases> {
     let db = db.inner();
+    if let Some(config) = &update.config {
+        validate_command_arguments(&config.arguments)?;
+    }
+
     let mut command = CommandModel::get_by_id(db, command_id)
         .await?
         .context("command not found")?;
@@ -191,3 +201,36 @@ pub async fn import_commands(
     export::import_commands(db, commands).await?;
     Ok(())
 }
+
+/// Get the permissions requested by the scripts of commands
+/// before they are imported
+#[tauri::command]
+pub fn get_import_commands_permissions(
+    commands: Vec<ExportedCommandModel>,
+) -> CmdResult<Vec<ImportScriptPermissions>> {
+    Ok(export::command_script_permissions(&commands))
+}
+
+/// Get all counters and their current values
+#[tauri::command]
+pub async fn get_counters(db: State<'_, DbPool>) -> CmdResult<Vec<Counter>> {
+    let db = db.inner();
+    let counters = counter::all_counters(db).await?;
+    Ok(counters)
+}
+
+/// Set the value of a counter
+#[tauri::command]
+pub async fn set_counter(name: String, value: i64, db: State<'_, DbPool>) -> CmdResult<()> {
+    let db = db.inner();
+    counter::set_counter(db, &name, value).await?;
+    Ok(())
+}
+
+/// Delete a counter
+#[tauri::command]
+pub async fn delete_counter(name: String, db: State<'_, DbPool>) -> CmdResult<()> {
+    let db = db.inner();
+    counter::delete_counter(db, &name).await?;
+    Ok(())
+}
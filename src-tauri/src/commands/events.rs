@@ -9,21 +9,26 @@ use crate::{
             event_execution::EventExecutionModel,
             event_log::EventLogsModel,
             events::{CreateEvent, EventModel, EventTrigger, EventTriggerType, UpdateEvent},
+            timer_state::TimerStateModel,
             shared::{ExecutionsQuery, LogsQuery, UpdateOrdering},
         },
         DbPool,
     },
     events::{
-        matching::EventData,
-        outcome::produce_outcome_message,
-        scheduler::{SchedulerHandle, SchedulerQueueEvent},
+        matching::{is_event_conditions_met, ConditionInput, EventData},
+        outcome::execute_outcomes,
+        pause::{PauseSnapshot, ProcessingPause},
+        queue::{EventQueue, EventQueueSnapshot},
+        scheduler::{EventSchedule, SchedulerHandle, SchedulerQueueEvent},
+        validation::validate_event_config,
     },
-    export::{self, ExportedEventModel},
+    export::{self, ExportedEventModel, ImportScriptPermissions},
     overlay::OverlayMessageSender,
     script::runtime::ScriptExecutorHandle,
     twitch::manager::Twitch,
 };
-use anyhow::Context;
+use anyhow::{bail, Context};
+use log::error;
 use tauri::{async_runtime::spawn_blocking, AppHandle, State};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::reveal_item_in_dir;
@@ -53,6 +58,8 @@ pub async fn create_event(
     scheduler: State<'_, SchedulerHandle>,
 ) -> CmdResult<EventModel> {
     let db = db.inner();
+    validate_event_config(&create.config)?;
+
     let event = EventModel::create(db, create).await?;
 
     // Update the event scheduler
@@ -71,13 +78,27 @@ pub async fn update_event(
     scheduler: State<'_, SchedulerHandle>,
 ) -> CmdResult<EventModel> {
     let db = db.inner();
+    if let Some(config) = &update.config {
+        validate_event_config(config)?;
+    }
+
     let mut event = EventModel::get_by_id(db, event_id)
         .await?
         .context("event not found")?;
+    let previous_trigger = event.config.trigger.clone();
     event.update(db, update).await?;
 
+    let was_timer = matches!(previous_trigger, EventTrigger::Timer { .. });
+    let is_timer = matches!(event.config.trigger, EventTrigger::Timer { .. });
+
+    // Clear the stored next run when the timer changed so the new timer settings
+    // apply, unchanged timers keep their next run (i.e when only renamed)
+    if was_timer && previous_trigger != event.config.trigger {
+        TimerStateModel::delete_by_event_id(db, event.id).await?;
+    }
+
     // Update the event scheduler
-    if let EventTrigger::Timer { .. } = event.config.trigger {
+    if was_timer || is_timer {
         update_scheduler_events(db, scheduler.inner()).await;
     }
 
@@ -112,21 +133,53 @@ pub async fn delete_event(
 pub async fn update_scheduler_events(db: &DbPool, scheduler: &SchedulerHandle) {
     if let Ok(events) = EventModel::get_by_trigger_type(db, EventTriggerType::Timer).await {
         // Map into scheduler events
-        let scheduled = events
+        let mut scheduled: Vec<SchedulerQueueEvent> = events
             .into_iter()
             .filter_map(|event| {
-                let interval = match &event.config.trigger {
-                    EventTrigger::Timer { interval, .. } => *interval,
+                let (schedule, missed_runs) = match &event.config.trigger {
+                    EventTrigger::Timer {
+                        interval,
+                        schedule,
+                        days,
+                        jitter,
+                        missed_runs,
+                        ..
+                    } => (
+                        EventSchedule::from_timer(*interval, schedule.as_ref(), days, *jitter),
+                        *missed_runs,
+                    ),
                     _ => return None,
                 };
 
+                let schedule = match schedule {
+                    Ok(value) => value,
+                    Err(err) => {
+                        error!("invalid schedule for timer event {}: {err:?}", event.id);
+                        return None;
+                    }
+                };
+
                 Some(SchedulerQueueEvent {
                     event_id: event.id,
-                    interval_seconds: interval,
+                    schedule,
+                    live_only: event.config.live_only,
+                    missed_runs,
+                    state: None,
                 })
             })
             .collect();
 
+        // Load the stored state for each timer
+        for event in &mut scheduled {
+            event.state = match TimerStateModel::get_by_event_id(db, event.event_id).await {
+                Ok(value) => value,
+                Err(err) => {
+                    error!("failed to load timer state: {err:?}");
+                    None
+                }
+            };
+        }
+
         _ = scheduler.update_events(scheduled).await;
     }
 }
@@ -145,12 +198,25 @@ pub async fn test_event_by_id(
         .await?
         .context("unknown event")?;
 
-    if let Some(msg) =
-        produce_outcome_message(db, &twitch, &script_handle, event, &event_data).await?
-    {
-        _ = event_sender.send(msg);
+    // Test data must meet the conditions the event would be triggered with
+    if !is_event_conditions_met(
+        &event.config.conditions,
+        &ConditionInput::new(&event_data.input_data),
+    ) {
+        bail!("test data does not meet the event conditions");
     }
 
+    let (_picks, result) = execute_outcomes(
+        db,
+        &twitch,
+        &script_handle,
+        &event_sender,
+        event,
+        &event_data,
+    )
+    .await;
+    result?;
+
     Ok(())
 }
 
@@ -255,3 +321,56 @@ pub async fn import_events(
     export::import_events(db, events).await?;
     Ok(())
 }
+
+/// Get the permissions requested by the scripts of events
+/// before they are imported
+#[tauri::command]
+pub fn get_import_events_permissions(
+    events: Vec<ExportedEventModel>,
+) -> CmdResult<Vec<ImportScriptPermissions>> {
+    Ok(export::event_script_permissions(&events))
+}
+
+/// Get the current state of the event queue
+#[tauri::command]
+pub fn get_event_queue(event_queue: State<'_, EventQueue>) -> CmdResult<EventQueueSnapshot> {
+    Ok(event_queue.snapshot())
+}
+
+/// Skip an event in the event queue, stops the event if it is
+/// currently playing
+#[tauri::command]
+pub fn skip_queued_event(event_queue: State<'_, EventQueue>, id: Uuid) -> CmdResult<bool> {
+    Ok(event_queue.skip(id))
+}
+
+/// Clear the pending events from a specific queue or all
+/// queues when no queue name is provided
+#[tauri::command]
+pub fn clear_event_queue(
+    event_queue: State<'_, EventQueue>,
+    name: Option<String>,
+) -> CmdResult<()> {
+    event_queue.clear(name.as_deref());
+    Ok(())
+}
+
+/// Pause or resume playing events from the event queue
+#[tauri::command]
+pub fn set_event_queue_paused(event_queue: State<'_, EventQueue>, paused: bool) -> CmdResult<()> {
+    event_queue.set_paused(paused);
+    Ok(())
+}
+
+/// Get the current state of the event and command processing pause
+#[tauri::command]
+pub fn get_processing_paused(pause: State<'_, ProcessingPause>) -> CmdResult<PauseSnapshot> {
+    Ok(pause.snapshot())
+}
+
+/// Pause or resume all event and command processing
+#[tauri::command]
+pub fn set_processing_paused(pause: State<'_, ProcessingPause>, paused: bool) -> CmdResult<()> {
+    pause.set_paused(paused);
+    Ok(())
+}
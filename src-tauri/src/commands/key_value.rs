@@ -0,0 +1,47 @@
+//! # Key Value
+//!
+//! Commands for browsing and editing the key value store from the frontend
+
+use super::CmdResult;
+use crate::database::{
+    DbPool,
+    entity::key_value::{CreateKeyValue, KeyValueModel},
+};
+use tauri::State;
+
+/// Get all key values, optionally only those whose key starts with `prefix`
+#[tauri::command]
+pub async fn get_key_values(
+    prefix: Option<String>,
+    db: State<'_, DbPool>,
+) -> CmdResult<Vec<KeyValueModel>> {
+    let db = db.inner();
+    let prefix = prefix.unwrap_or_default();
+    let key_values = KeyValueModel::get_by_key_prefix(db, &prefix).await?;
+    Ok(key_values)
+}
+
+/// Create or replace a key value
+#[tauri::command]
+pub async fn set_key_value(create: CreateKeyValue, db: State<'_, DbPool>) -> CmdResult<()> {
+    let db = db.inner();
+    KeyValueModel::create(db, create).await?;
+    Ok(())
+}
+
+/// Delete a key value by key
+#[tauri::command]
+pub async fn delete_key_value(key: String, db: State<'_, DbPool>) -> CmdResult<()> {
+    let db = db.inner();
+    KeyValueModel::delete_by_key(db, &key).await?;
+    Ok(())
+}
+
+/// Delete all key values whose key starts with `prefix`, returns
+/// the number of deleted key values
+#[tauri::command]
+pub async fn delete_key_values_by_prefix(prefix: String, db: State<'_, DbPool>) -> CmdResult<u64> {
+    let db = db.inner();
+    let deleted = KeyValueModel::delete_by_key_prefix(db, &prefix).await?;
+    Ok(deleted)
+}
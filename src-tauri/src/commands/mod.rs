@@ -6,6 +6,7 @@ pub mod commands;
 pub mod data;
 pub mod events;
 pub mod items;
+pub mod key_value;
 pub mod sounds;
 pub mod test;
 pub mod twitch;
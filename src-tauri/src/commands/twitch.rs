@@ -3,7 +3,8 @@ use crate::database::entity::secrets::SecretsModel;
 use crate::database::entity::TWITCH_SECRET_KEY;
 use crate::database::DbPool;
 use crate::http::ServerPort;
-use crate::twitch::manager::Twitch;
+use crate::twitch::limits::{validate_create_reward, validate_reward_id, validate_update_reward};
+use crate::twitch::manager::{CreateReward, Twitch, UpdateReward};
 use anyhow::Context;
 use reqwest::Url;
 use std::sync::Arc;
@@ -29,6 +30,54 @@ pub async fn refresh_redeems_list(twitch: State<'_, Twitch>) -> CmdResult<()> {
     Ok(())
 }
 
+/// Creates a new custom channel point reward owned by VTFTK
+#[tauri::command]
+pub async fn create_redeem(
+    twitch: State<'_, Twitch>,
+    create: CreateReward,
+) -> CmdResult<CustomReward> {
+    validate_create_reward(&create)?;
+
+    Ok(twitch
+        .create_reward(create)
+        .await
+        .context("failed to create redeem")?)
+}
+
+/// Updates a custom channel point reward, only rewards created by
+/// VTFTK can be updated
+#[tauri::command]
+pub async fn update_redeem(
+    twitch: State<'_, Twitch>,
+    reward_id: String,
+    update: UpdateReward,
+) -> CmdResult<()> {
+    validate_reward_id(&reward_id)?;
+    validate_update_reward(&update)?;
+
+    twitch
+        .update_reward(&reward_id, update)
+        .await
+        .context("failed to update redeem")?;
+    Ok(())
+}
+
+/// Pauses or unpauses a custom channel point reward
+#[tauri::command]
+pub async fn set_redeem_paused(
+    twitch: State<'_, Twitch>,
+    reward_id: String,
+    paused: bool,
+) -> CmdResult<()> {
+    validate_reward_id(&reward_id)?;
+
+    twitch
+        .set_reward_paused(&reward_id, paused)
+        .await
+        .context("failed to update redeem")?;
+    Ok(())
+}
+
 /// Obtain a URL for use logging into twitch using OAuth2
 #[tauri::command]
 pub fn get_twitch_oauth_uri(
@@ -65,6 +65,78 @@ pub struct MainConfig {
     pub auto_updating: bool,
     /// Port for the HTTP server
     http_port: u16,
+    /// How events that arrive while processing is paused are handled
+    pub pause_mode: PauseMode,
+    /// Prefixes that can be used to trigger commands (i.e "!" and "?"), commands
+    /// starting with one of these prefixes can be triggered using any of them
+    pub command_prefixes: Vec<String>,
+    /// Whether commands can be triggered from anywhere in a chat message
+    /// rather than only the start of the message
+    pub mid_message_commands: bool,
+    /// Whether to reply with a "did you mean" suggestion when a prefixed
+    /// chat message is close to but does not match a command
+    pub command_suggestions: bool,
+    /// Settings for the runtimes scripts are run on, only read when the
+    /// app starts so changes are applied on restart
+    pub script_runtime: ScriptRuntimeConfig,
+}
+
+/// Settings for the pool of runtimes that scripts are run on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptRuntimeConfig {
+    /// Number of threads scripts are spread across, scripts on different
+    /// threads can run at the same time without delaying each other
+    ///
+    /// Each thread has its own runtimes, with more than one thread scripts
+    /// using the shared runtime no longer share globals with every script
+    pub pool_size: usize,
+    /// How runtimes are shared between scripts
+    pub isolation: ScriptIsolation,
+    /// Maximum heap size for each runtime in megabytes
+    pub memory_limit_mb: usize,
+    /// Default maximum time a script can take to complete including time
+    /// spent waiting on async operations (Milliseconds)
+    pub wall_clock_limit: u32,
+    /// Default maximum total time a script can spend running JS code,
+    /// measured by the worker across every part of the execution (Milliseconds)
+    pub cpu_time_limit: u32,
+}
+
+impl Default for ScriptRuntimeConfig {
+    fn default() -> Self {
+        Self {
+            pool_size: 1,
+            isolation: ScriptIsolation::default(),
+            memory_limit_mb: 128,
+            wall_clock_limit: 120_000,
+            cpu_time_limit: 5_000,
+        }
+    }
+}
+
+/// Determines how runtimes are shared between scripts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ScriptIsolation {
+    /// All scripts on a thread share one runtime (And its globals)
+    #[default]
+    Shared,
+    /// Each script has its own runtime, globals are kept between executions
+    /// of the same script as it is always run on the same thread
+    PerScript,
+    /// Every execution runs on a fresh runtime
+    PerExecution,
+}
+
+/// Determines what happens to events that arrive while
+/// event and command processing is paused
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum PauseMode {
+    /// Events are discarded
+    #[default]
+    Drop,
+    /// Events are kept and run once processing is resumed
+    Buffer,
 }
 
 pub fn default_http_port() -> u16 {
@@ -83,6 +155,11 @@ impl Default for MainConfig {
             clean_chat_history_days: 1,
             auto_updating: true,
             http_port: default_http_port(),
+            pause_mode: PauseMode::default(),
+            command_prefixes: vec!["!".to_string()],
+            mid_message_commands: false,
+            command_suggestions: false,
+            script_runtime: ScriptRuntimeConfig::default(),
         }
     }
 }
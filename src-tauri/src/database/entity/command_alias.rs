@@ -79,6 +79,27 @@ impl CommandAliasModel {
         query.execute(db).await?;
         Ok(())
     }
+    /// Get all enabled commands along with their aliases
+    pub async fn get_enabled_with_aliases(db: &DbPool) -> DbResult<Vec<CommandWithAliases>> {
+        let commands = CommandModel::get_enabled(db).await?;
+        let aliases: Vec<CommandAliasModel> =
+            sqlx::query_as(r#"SELECT * FROM "command_alias" ORDER BY "order" ASC"#)
+                .fetch_all(db)
+                .await?;
+
+        let mut aliases = aliases
+            .into_iter()
+            .map(|alias| (alias.command_id, alias.alias))
+            .into_group_map();
+
+        Ok(commands
+            .into_iter()
+            .map(|command| CommandWithAliases {
+                aliases: aliases.remove(&command.id).unwrap_or_default(),
+                command,
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -108,6 +129,13 @@ mod test {
                     },
                     cooldown: Default::default(),
                     require_role: MinimumRequireRole::None,
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
                 },
                 aliases: vec![],
             },
@@ -139,6 +167,13 @@ mod test {
                     },
                     cooldown: Default::default(),
                     require_role: MinimumRequireRole::None,
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
                 },
                 aliases: vec![],
             },
@@ -190,6 +225,13 @@ mod test {
                     },
                     cooldown: Default::default(),
                     require_role: MinimumRequireRole::None,
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
                 },
                 aliases: vec![],
             },
@@ -217,4 +259,51 @@ mod test {
 
         assert_eq!(aliases, vec!["!test3".to_string(), "!test4".to_string()]);
     }
+
+    /// Tests that only enabled commands are loaded along with their aliases
+    #[tokio::test]
+    async fn test_get_enabled_with_aliases() {
+        let db = mock_database().await;
+
+        let mut ids = Vec::new();
+        for (enabled, command, aliases) in [
+            (true, "!test", vec!["!test1".to_string()]),
+            (false, "!disabled", vec!["!disabled1".to_string()]),
+        ] {
+            let command = CommandModel::create(
+                &db,
+                CreateCommand {
+                    enabled,
+                    name: "test".to_string(),
+                    command: command.to_string(),
+                    config: CommandConfig {
+                        outcome: CommandOutcome::Template {
+                            message: "test".to_string(),
+                        },
+                        cooldown: Default::default(),
+                        require_role: MinimumRequireRole::None,
+                        live_only: Default::default(),
+                        usage_limits: Default::default(),
+                        usage_limit_message: Default::default(),
+                        cooldown_message: Default::default(),
+                        require_role_message: Default::default(),
+                        arguments: Default::default(),
+                        match_mode: Default::default(),
+                    },
+                    aliases,
+                },
+            )
+            .await
+            .unwrap();
+            ids.push(command.id);
+        }
+
+        let commands = CommandAliasModel::get_enabled_with_aliases(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command.id, ids[0]);
+        assert_eq!(commands[0].aliases, vec!["!test1".to_string()]);
+    }
 }
@@ -48,12 +48,15 @@ impl CommandExecutionModel {
         let metadata_value =
             serde_json::to_value(&create.metadata).map_err(|err| DbErr::Encode(err.into()))?;
 
+        let user_id = create.metadata.user.as_ref().map(|user| user.id.as_str());
+
         sqlx::query(
-            r#"INSERT INTO "command_executions" ("id", "command_id", "metadata", "created_at")
-            VALUES (?, ?, ?, ?)"#,
+            r#"INSERT INTO "command_executions" ("id", "command_id", "user_id", "metadata", "created_at")
+            VALUES (?, ?, ?, ?, ?)"#,
         )
         .bind(id)
         .bind(create.command_id)
+        .bind(user_id)
         .bind(metadata_value)
         .bind(create.created_at)
         .execute(db)
@@ -81,6 +84,46 @@ impl CommandExecutionModel {
         .await
     }
 
+    /// Find the most recent execution of a command triggered by a specific user
+    pub async fn last_by_user(
+        db: &DbPool,
+        command_id: Uuid,
+        user_id: &str,
+    ) -> DbResult<Option<CommandExecutionModel>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "command_executions"
+            WHERE "command_id" = ? AND "user_id" = ?
+            ORDER BY "created_at" DESC
+            LIMIT 1"#,
+        )
+        .bind(command_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Count the executions of a command since the provided `start_date`,
+    /// optionally only counting executions triggered by a specific user
+    pub async fn count_since(
+        db: &DbPool,
+        command_id: Uuid,
+        start_date: DateTime<Utc>,
+        user_id: Option<&str>,
+    ) -> DbResult<u32> {
+        let result: (u32,) = sqlx::query_as(
+            r#"SELECT COUNT(*) FROM "command_executions"
+            WHERE "command_id" = ? AND "created_at" >= ?
+                AND (? IS NULL OR "user_id" = ?)"#,
+        )
+        .bind(command_id)
+        .bind(start_date)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+        Ok(result.0)
+    }
+
     /// Query the executions for a specific command
     pub async fn query(
         db: &DbPool,
@@ -168,6 +211,21 @@ impl CommandExecutionModel {
 
 #[cfg(test)]
 mod test {
+    use chrono::{TimeDelta, Utc};
+
+    use super::{CommandExecutionMetadata, CommandExecutionModel, CreateCommandExecution};
+    use crate::{
+        database::{
+            entity::{
+                commands::{CommandConfig, CommandModel, CommandOutcome, CreateCommand},
+                shared::MinimumRequireRole,
+            },
+            mock_database,
+        },
+        events::TwitchEventUser,
+    };
+    use twitch_api::types::{DisplayName, UserId, UserName};
+
     #[tokio::test]
     async fn test_create() {}
 
@@ -212,4 +270,151 @@ mod test {
 
     #[tokio::test]
     async fn test_estimate_size_empty() {}
+
+    /// Tests counting executions within a window, both for all users
+    /// and for a specific user
+    #[tokio::test]
+    async fn test_count_since() {
+        let db = mock_database().await;
+        let command = CommandModel::create(
+            &db,
+            CreateCommand {
+                enabled: true,
+                name: "test".to_string(),
+                command: "!test".to_string(),
+                config: CommandConfig {
+                    outcome: CommandOutcome::Template {
+                        message: "test".to_string(),
+                    },
+                    cooldown: Default::default(),
+                    require_role: MinimumRequireRole::None,
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
+                },
+                aliases: vec![],
+            },
+        )
+        .await
+        .unwrap();
+
+        let now = Utc::now();
+        let user = |id: &'static str| TwitchEventUser {
+            id: UserId::from_static(id),
+            name: UserName::from_static(id),
+            display_name: DisplayName::from_static(id),
+        };
+
+        // Executions by two users, one of which is outside the window
+        let executions = [
+            (user("1"), now - TimeDelta::hours(2)),
+            (user("1"), now),
+            (user("1"), now),
+            (user("2"), now),
+        ];
+
+        for (user, created_at) in executions {
+            CommandExecutionModel::create(
+                &db,
+                CreateCommandExecution {
+                    command_id: command.id,
+                    metadata: CommandExecutionMetadata {
+                        user: Some(user),
+                        data: Default::default(),
+                    },
+                    created_at,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let start_date = now - TimeDelta::hours(1);
+
+        let total = CommandExecutionModel::count_since(&db, command.id, start_date, None)
+            .await
+            .unwrap();
+        assert_eq!(total, 3);
+
+        let user_total = CommandExecutionModel::count_since(&db, command.id, start_date, Some("1"))
+            .await
+            .unwrap();
+        assert_eq!(user_total, 2);
+    }
+
+    /// Tests finding the most recent execution from a specific user
+    #[tokio::test]
+    async fn test_last_by_user() {
+        let db = mock_database().await;
+        let command = CommandModel::create(
+            &db,
+            CreateCommand {
+                enabled: true,
+                name: "test".to_string(),
+                command: "!test".to_string(),
+                config: CommandConfig {
+                    outcome: CommandOutcome::Template {
+                        message: "test".to_string(),
+                    },
+                    cooldown: Default::default(),
+                    require_role: MinimumRequireRole::None,
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
+                },
+                aliases: vec![],
+            },
+        )
+        .await
+        .unwrap();
+
+        let now = Utc::now();
+        let user = |id: &'static str| TwitchEventUser {
+            id: UserId::from_static(id),
+            name: UserName::from_static(id),
+            display_name: DisplayName::from_static(id),
+        };
+
+        // Older execution from the target user followed by executions from others
+        let executions = [
+            (user("1"), now - TimeDelta::hours(1)),
+            (user("2"), now),
+            (user("3"), now),
+        ];
+
+        for (user, created_at) in executions {
+            CommandExecutionModel::create(
+                &db,
+                CreateCommandExecution {
+                    command_id: command.id,
+                    metadata: CommandExecutionMetadata {
+                        user: Some(user),
+                        data: Default::default(),
+                    },
+                    created_at,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let last = CommandExecutionModel::last_by_user(&db, command.id, "1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(last.created_at, now - TimeDelta::hours(1));
+
+        let last = CommandExecutionModel::last_by_user(&db, command.id, "4")
+            .await
+            .unwrap();
+        assert!(last.is_none());
+    }
 }
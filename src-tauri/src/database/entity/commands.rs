@@ -1,6 +1,6 @@
 use super::{
     command_alias::CommandAliasModel,
-    shared::{MinimumRequireRole, UpdateOrdering},
+    shared::{MinimumRequireRole, ScriptLimits, ScriptPermissions, UpdateOrdering, UsageLimit},
 };
 use crate::database::{DbErr, DbPool, DbResult};
 use chrono::{DateTime, Utc};
@@ -36,13 +36,111 @@ pub struct CommandConfig {
     pub cooldown: CommandCooldown,
     /// Minimum required role to trigger the command
     pub require_role: MinimumRequireRole,
+    /// Whether the command should only trigger while the stream is live
+    #[serde(default)]
+    pub live_only: bool,
+    /// Limits on the number of times the command can be used
+    #[serde(default)]
+    pub usage_limits: Vec<UsageLimit>,
+    /// Chat message to reply with when a user reaches a usage limit
+    #[serde(default)]
+    pub usage_limit_message: Option<String>,
+    /// Chat message to reply with when skipped due to an active cooldown
+    #[serde(default)]
+    pub cooldown_message: Option<String>,
+    /// Chat message to reply with when skipped due to a missing role
+    #[serde(default)]
+    pub require_role_message: Option<String>,
+    /// Typed arguments the command accepts, when present the arguments
+    /// are validated before the command runs
+    #[serde(default)]
+    pub arguments: Vec<CommandArgument>,
+    /// How the command and its aliases are matched against chat messages
+    #[serde(default)]
+    pub match_mode: CommandMatchMode,
+}
+
+/// How a command trigger is compared against chat messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommandMatchMode {
+    /// Trigger must match ignoring case
+    #[default]
+    CaseInsensitive,
+    /// Trigger must match exactly
+    CaseSensitive,
+    /// Trigger can contain small typos (Ignores case)
+    Fuzzy,
+}
+
+/// Typed argument accepted by a command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandArgument {
+    /// Name of the argument, used as the key for the parsed value
+    pub name: String,
+    /// Whether the argument can be omitted
+    #[serde(default)]
+    pub optional: bool,
+    /// Type of value the argument accepts
+    #[serde(flatten)]
+    pub ty: CommandArgumentType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CommandArgumentType {
+    /// Twitch username, optionally prefixed with @
+    User,
+    /// Whole number within an optional range (Inclusive)
+    Integer {
+        #[serde(default)]
+        min: Option<i64>,
+        #[serde(default)]
+        max: Option<i64>,
+    },
+    /// One value from a list of choices (Case insensitive)
+    Choice { choices: Vec<String> },
+    /// Remainder of the message
+    Rest,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum CommandOutcome {
-    Template { message: String },
-    Script { script: String },
+    Template {
+        message: String,
+    },
+    Script {
+        script: String,
+        /// Permissions the script requests, None for unrestricted access which
+        /// is only given to local scripts, imported scripts are always restricted
+        #[serde(default)]
+        permissions: Option<ScriptPermissions>,
+        /// Execution limits for the script
+        #[serde(default)]
+        limits: ScriptLimits,
+    },
+    /// Views or modifies a named counter based on the command arguments
+    Counter {
+        /// Name of the counter
+        counter: String,
+        /// Chat message to reply with, `$(name)` and `$(value)` are
+        /// replaced with the counter name and its value
+        #[serde(default)]
+        message: String,
+        /// Role required to increase the counter
+        #[serde(default = "default_counter_role")]
+        increment_role: MinimumRequireRole,
+        /// Role required to decrease the counter
+        #[serde(default = "default_counter_role")]
+        decrement_role: MinimumRequireRole,
+        /// Role required to reset or set the counter
+        #[serde(default = "default_counter_role")]
+        reset_role: MinimumRequireRole,
+    },
+}
+
+fn default_counter_role() -> MinimumRequireRole {
+    MinimumRequireRole::Mod
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +218,15 @@ impl CommandModel {
         Ok(model)
     }
 
+    /// Find all enabled commands
+    pub async fn get_enabled(db: &DbPool) -> DbResult<Vec<CommandModel>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "commands" WHERE "enabled" = TRUE ORDER BY "order" ASC, "created_at" DESC"#,
+        )
+        .fetch_all(db)
+        .await
+    }
+
     /// Find commands by the actual command trigger word
     /// and only commands that are enabled
     pub async fn get_by_command(db: &DbPool, command: &str) -> DbResult<Vec<CommandModel>> {
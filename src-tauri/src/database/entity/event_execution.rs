@@ -45,12 +45,15 @@ impl EventExecutionModel {
         let metadata_value =
             serde_json::to_value(&create.metadata).map_err(|err| DbErr::Encode(err.into()))?;
 
+        let user_id = create.metadata.user.as_ref().map(|user| user.id.as_str());
+
         sqlx::query(
-            r#"INSERT INTO "event_executions" ("id", "event_id", "metadata", "created_at")
-            VALUES (?, ?, ?, ?)"#,
+            r#"INSERT INTO "event_executions" ("id", "event_id", "user_id", "metadata", "created_at")
+            VALUES (?, ?, ?, ?, ?)"#,
         )
         .bind(id)
         .bind(create.event_id)
+        .bind(user_id)
         .bind(metadata_value)
         .bind(create.created_at)
         .execute(db)
@@ -78,6 +81,46 @@ impl EventExecutionModel {
         .await
     }
 
+    /// Find the most recent execution of a event triggered by a specific user
+    pub async fn last_by_user(
+        db: &DbPool,
+        event_id: Uuid,
+        user_id: &str,
+    ) -> DbResult<Option<EventExecutionModel>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "event_executions"
+            WHERE "event_id" = ? AND "user_id" = ?
+            ORDER BY "created_at" DESC
+            LIMIT 1"#,
+        )
+        .bind(event_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Count the executions of a event since the provided `start_date`,
+    /// optionally only counting executions triggered by a specific user
+    pub async fn count_since(
+        db: &DbPool,
+        event_id: Uuid,
+        start_date: DateTime<Utc>,
+        user_id: Option<&str>,
+    ) -> DbResult<u32> {
+        let result: (u32,) = sqlx::query_as(
+            r#"SELECT COUNT(*) FROM "event_executions"
+            WHERE "event_id" = ? AND "created_at" >= ?
+                AND (? IS NULL OR "user_id" = ?)"#,
+        )
+        .bind(event_id)
+        .bind(start_date)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_one(db)
+        .await?;
+        Ok(result.0)
+    }
+
     /// Query the executions for a specific event
     pub async fn query(
         db: &DbPool,
@@ -165,6 +208,18 @@ impl EventExecutionModel {
 
 #[cfg(test)]
 mod test {
+    use chrono::{TimeDelta, Utc};
+    use twitch_api::types::{DisplayName, UserId, UserName};
+
+    use super::{CreateEventExecution, EventExecutionMetadata, EventExecutionModel};
+    use crate::{
+        database::{
+            entity::events::{CreateEvent, EventConfig, EventModel, EventTrigger},
+            mock_database,
+        },
+        events::TwitchEventUser,
+    };
+
     #[tokio::test]
     async fn test_create() {}
 
@@ -209,4 +264,67 @@ mod test {
 
     #[tokio::test]
     async fn test_estimate_size_empty() {}
+
+    /// Tests counting executions within a window, both for all users
+    /// and for a specific user
+    #[tokio::test]
+    async fn test_count_since() {
+        let db = mock_database().await;
+        let event = EventModel::create(
+            &db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Event".to_string(),
+                config: EventConfig {
+                    trigger: EventTrigger::Follow,
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+        let now = Utc::now();
+        let user = |id: &'static str| TwitchEventUser {
+            id: UserId::from_static(id),
+            name: UserName::from_static(id),
+            display_name: DisplayName::from_static(id),
+        };
+
+        // Executions by two users, one of which is outside the window
+        let executions = [
+            (user("1"), now - TimeDelta::hours(2)),
+            (user("1"), now),
+            (user("1"), now),
+            (user("2"), now),
+        ];
+
+        for (user, created_at) in executions {
+            EventExecutionModel::create(
+                &db,
+                CreateEventExecution {
+                    event_id: event.id,
+                    metadata: EventExecutionMetadata {
+                        user: Some(user),
+                        data: Default::default(),
+                    },
+                    created_at,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let start_date = now - TimeDelta::hours(1);
+
+        let total = EventExecutionModel::count_since(&db, event.id, start_date, None)
+            .await
+            .unwrap();
+        assert_eq!(total, 3);
+
+        let user_total = EventExecutionModel::count_since(&db, event.id, start_date, Some("1"))
+            .await
+            .unwrap();
+        assert_eq!(user_total, 2);
+    }
 }
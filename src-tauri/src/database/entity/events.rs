@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
@@ -7,7 +7,9 @@ use uuid::Uuid;
 
 use crate::database::{DbErr, DbPool, DbResult};
 
-use super::shared::{MinMax, MinimumRequireRole, UpdateOrdering};
+use super::shared::{
+    MinMax, MinimumRequireRole, ScriptLimits, ScriptPermissions, UpdateOrdering, UsageLimit,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct EventModel {
@@ -29,14 +31,76 @@ pub struct EventModel {
 pub struct EventConfig {
     /// Input that should trigger the event
     pub trigger: EventTrigger,
-    /// Outcome the event should trigger
-    pub outcome: EventOutcome,
+    /// Ordered list of outcomes the event should trigger, configs from
+    /// before outcome chains stored a single "outcome" instead
+    #[serde(alias = "outcome", deserialize_with = "deserialize_outcomes")]
+    pub outcomes: Vec<EventOutcomeStep>,
+    /// How the outcome steps should be executed
+    #[serde(default)]
+    pub outcome_mode: EventOutcomeMode,
     /// Cooldown between each trigger of the even
     pub cooldown: EventCooldown,
     /// Minimum required role to trigger the event
     pub require_role: MinimumRequireRole,
     /// Delay before executing the outcome
     pub outcome_delay: u32,
+    /// Conditions the event input data must meet for the event to trigger
+    #[serde(default)]
+    pub conditions: Vec<EventCondition>,
+    /// Whether the event should only trigger while the stream is live
+    #[serde(default)]
+    pub live_only: bool,
+    /// Queue the event plays in, when not set the event runs immediately
+    /// alongside any other events
+    #[serde(default)]
+    pub queue: Option<EventQueueConfig>,
+    /// Limits on the number of times the event can be triggered
+    #[serde(default)]
+    pub usage_limits: Vec<UsageLimit>,
+    /// Chat message to reply with when a user reaches a usage limit
+    #[serde(default)]
+    pub usage_limit_message: Option<String>,
+    /// Chat message to reply with when skipped due to an active cooldown
+    #[serde(default)]
+    pub cooldown_message: Option<String>,
+    /// Chat message to reply with when skipped due to a missing role
+    #[serde(default)]
+    pub require_role_message: Option<String>,
+}
+
+/// Follow triggered config without any outcomes, tests override the
+/// fields they need using struct update syntax
+#[cfg(test)]
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            trigger: EventTrigger::Follow,
+            outcomes: Default::default(),
+            outcome_mode: Default::default(),
+            cooldown: Default::default(),
+            require_role: Default::default(),
+            outcome_delay: Default::default(),
+            conditions: Default::default(),
+            live_only: Default::default(),
+            queue: Default::default(),
+            usage_limits: Default::default(),
+            usage_limit_message: Default::default(),
+            cooldown_message: Default::default(),
+            require_role_message: Default::default(),
+        }
+    }
+}
+
+/// Configuration for playing an event through a queue, queued events
+/// wait for the previous event in the queue to finish before running
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventQueueConfig {
+    /// Name of a shared queue, events using the same name play one after
+    /// the other. When not set the event is only queued against itself
+    pub name: Option<String>,
+    /// Gap in milliseconds to wait after the event before the next queued event
+    pub gap: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,15 +136,27 @@ pub enum EventTriggerType {
     Timer,
     AdBreakBegin,
     ShoutoutReceive,
+    HypeTrain,
+    Poll,
+    Prediction,
+    StreamOnline,
+    StreamOffline,
+    ChatPattern,
+    FirstMessage,
+    ReturningViewer,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EventTrigger {
     /// Redeem was triggered
     Redeem {
         /// ID of the reward required
         reward_id: String,
+        /// Mark the redemption as fulfilled when the outcomes succeed and
+        /// refund it when they fail
+        #[serde(default)]
+        update_redemption: bool,
     },
     /// Command was sent
     Command {
@@ -104,15 +180,31 @@ pub enum EventTrigger {
         min_raiders: u32,
     },
 
-    /// Run the event automatically on a fixed interval timer
+    /// Run the event automatically on a fixed interval timer or schedule
     Timer {
-        /// Interval in seconds to run
+        /// Interval in seconds to run (Used when no schedule is set)
         interval: u64,
 
         /// Minimum chat messages that must have been received between each interval
         /// for the timer to trigger to prevent spamming when nobody is chatting
         #[serde(default)]
         min_chat_messages: u32,
+
+        /// Schedule to run on instead of the fixed interval
+        #[serde(default)]
+        schedule: Option<TimerSchedule>,
+
+        /// Days of the week the timer is allowed to run on, empty allows every day
+        #[serde(default)]
+        days: Vec<Weekday>,
+
+        /// Maximum random delay in seconds added to each run
+        #[serde(default)]
+        jitter: u64,
+
+        /// How runs that were missed while the app was closed are handled
+        #[serde(default)]
+        missed_runs: MissedRunPolicy,
     },
 
     /// Ad break started
@@ -123,6 +215,141 @@ pub enum EventTrigger {
         /// Minimum viewers required
         min_viewers: u32,
     },
+
+    /// Hype train began, progressed or ended
+    HypeTrain {
+        /// Phase of the hype train to trigger on
+        phase: HypeTrainPhase,
+        /// Minimum hype train level required
+        #[serde(default)]
+        min_level: u32,
+    },
+
+    /// Poll began, progressed or ended
+    Poll {
+        /// Phase of the poll to trigger on
+        phase: PollPhase,
+    },
+
+    /// Prediction began, progressed, locked or ended
+    Prediction {
+        /// Phase of the prediction to trigger on
+        phase: PredictionPhase,
+    },
+
+    /// Stream went live
+    StreamOnline,
+
+    /// Stream went offline
+    StreamOffline,
+
+    /// Chat message matched a pattern
+    ChatPattern {
+        /// Pattern to match chat messages against
+        pattern: String,
+        /// How the pattern should be matched
+        #[serde(default)]
+        match_type: ChatPatternMatchType,
+        /// Whether matching should be case sensitive (Does not apply to emote matching)
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+
+    /// User sent their first chat message
+    FirstMessage {
+        /// Whether this is the first message of the stream session or ever
+        #[serde(default)]
+        scope: FirstMessageScope,
+    },
+
+    /// User chatted after not being seen for a number of days
+    ReturningViewer {
+        /// Minimum number of days since the user was last seen
+        days: u32,
+    },
+}
+
+/// Scope of a first message trigger
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstMessageScope {
+    /// First message from the user during the current stream session
+    #[default]
+    Session,
+    /// First message the user has ever sent
+    Ever,
+}
+
+/// Calendar based schedule for a timer, evaluated in local time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TimerSchedule {
+    /// Run whenever a cron expression matches
+    /// (minute hour day-of-month month day-of-week)
+    Cron { expression: String },
+    /// Run at specific times of the day (i.e "09:30")
+    TimesOfDay { times: Vec<NaiveTime> },
+}
+
+/// How a timer handles runs that were missed while the app was closed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissedRunPolicy {
+    /// Ignore missed runs and continue with the next scheduled run
+    #[default]
+    Skip,
+    /// Run once if any runs were missed
+    RunOnce,
+    /// Run once for every missed run (Limited to prevent flooding)
+    CatchUp,
+}
+
+/// How a chat pattern is matched against chat messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChatPatternMatchType {
+    /// Pattern appears anywhere in the message
+    #[default]
+    Substring,
+    /// Message matches a glob pattern (i.e "*bonk*")
+    Glob,
+    /// Message matches a regex pattern, named capture groups are
+    /// provided to the outcome
+    Regex,
+    /// Message contains an emote with the pattern as its ID
+    Emote,
+}
+
+/// Phase of a hype train
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HypeTrainPhase {
+    /// Hype train has started
+    Begin,
+    /// Hype train has received a contribution
+    Progress,
+    /// Hype train has ended
+    End,
+}
+
+/// Phase of a poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PollPhase {
+    /// Poll has started
+    Begin,
+    /// Poll has received votes
+    Progress,
+    /// Poll has ended
+    End,
+}
+
+/// Phase of a prediction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PredictionPhase {
+    /// Prediction has started
+    Begin,
+    /// Prediction has received predictions
+    Progress,
+    /// Prediction has been locked and no longer accepts predictions
+    Lock,
+    /// Prediction has been resolved or canceled
+    End,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -219,6 +446,13 @@ pub struct EventOutcomeSendChat {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventOutcomeScript {
     pub script: String,
+    /// Permissions the script requests, None for unrestricted access which
+    /// is only given to local scripts, imported scripts are always restricted
+    #[serde(default)]
+    pub permissions: Option<ScriptPermissions>,
+    /// Execution limits for the script
+    #[serde(default)]
+    pub limits: ScriptLimits,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -227,6 +461,61 @@ pub struct EventOutcomeChannelEmotes {
     pub amount: ThrowableAmountData,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventOutcomeCreatePoll {
+    /// Question displayed for the poll
+    pub title: String,
+    /// Choices viewers can vote for
+    pub choices: Vec<String>,
+    /// Duration of the poll in seconds
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventOutcomeCreatePrediction {
+    /// Question displayed for the prediction
+    pub title: String,
+    /// Outcomes viewers can predict
+    pub outcomes: Vec<String>,
+    /// Duration in seconds that predictions are accepted for
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventOutcomeToggleReward {
+    /// ID of the reward to change
+    pub reward_id: String,
+    /// Change to make to the reward
+    pub action: ToggleRewardAction,
+}
+
+/// Change to make to a channel point reward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToggleRewardAction {
+    /// Enable the reward, showing it to viewers
+    Enable,
+    /// Disable the reward, hiding it from viewers
+    Disable,
+    /// Pause the reward, viewers can see but not redeem it
+    Pause,
+    /// Unpause the reward
+    Unpause,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventOutcomeRandom {
+    /// Outcomes to randomly pick from
+    pub outcomes: Vec<WeightedEventOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedEventOutcome {
+    /// Weight of the outcome relative to the other outcomes
+    pub weight: u32,
+    /// Outcome to execute when picked
+    pub outcome: EventOutcome,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EventOutcome {
@@ -244,6 +533,86 @@ pub enum EventOutcome {
     Script(EventOutcomeScript),
     /// Throw the emotes of a specific channel
     ChannelEmotes(EventOutcomeChannelEmotes),
+    /// Pick one outcome at random from a weighted list
+    Random(EventOutcomeRandom),
+    /// Create a Twitch poll
+    CreatePoll(EventOutcomeCreatePoll),
+    /// Create a Twitch prediction
+    CreatePrediction(EventOutcomeCreatePrediction),
+    /// Enable, disable, pause or unpause a channel point reward
+    ToggleReward(EventOutcomeToggleReward),
+}
+
+/// Single step within the chain of event outcomes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventOutcomeStep {
+    /// Delay in milliseconds before executing the step
+    #[serde(default)]
+    pub delay: u32,
+    /// Outcome to execute
+    pub outcome: EventOutcome,
+}
+
+/// Outcome steps in either the current chain format or the single
+/// outcome format from before outcome chains
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EventOutcomesFormat {
+    Steps(Vec<EventOutcomeStep>),
+    Single(EventOutcome),
+}
+
+/// Deserializes the outcome steps of an event, wrapping a single legacy
+/// outcome into a chain containing just that outcome
+fn deserialize_outcomes<'de, D>(deserializer: D) -> Result<Vec<EventOutcomeStep>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match EventOutcomesFormat::deserialize(deserializer)? {
+        EventOutcomesFormat::Steps(steps) => steps,
+        EventOutcomesFormat::Single(outcome) => vec![EventOutcomeStep { delay: 0, outcome }],
+    })
+}
+
+/// Determines how the outcome steps of an event are executed
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum EventOutcomeMode {
+    /// Steps are executed one after the other, each step waits for
+    /// the previous step to complete before its delay starts
+    #[default]
+    Sequential,
+    /// Steps are all started at the same time, each step delay is
+    /// relative to the start of the execution
+    Parallel,
+}
+
+/// Condition checked against the input data of an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventCondition {
+    /// Name of the input data field to check (i.e "bits", "tier", "userInput"),
+    /// nested fields use a dot separated path (i.e "captures.amount", "choices.0.votes")
+    pub field: String,
+    /// Check to perform on the field value
+    pub check: EventConditionCheck,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EventConditionCheck {
+    /// Value must equal the provided value
+    Equals { value: serde_json::Value },
+    /// Value must not equal the provided value
+    NotEquals { value: serde_json::Value },
+    /// Value must be greater than the provided number
+    GreaterThan { value: f64 },
+    /// Value must be less than the provided number
+    LessThan { value: f64 },
+    /// Value must be within the range (inclusive)
+    Between { range: MinMax<f64> },
+    /// Value must contain the provided text (case-insensitive)
+    Contains { value: String },
+    /// Value must match the provided regex pattern
+    Matches { pattern: String },
 }
 
 #[derive(Debug, Deserialize)]
@@ -405,6 +774,49 @@ impl EventModel {
 
 #[cfg(test)]
 mod test {
+    use super::{EventConfig, EventOutcome};
+
+    /// Tests that configs using the single "outcome" field from before
+    /// outcome chains are loaded as a single step chain
+    #[test]
+    fn test_deserialize_legacy_outcome() {
+        let config: EventConfig = serde_json::from_value(serde_json::json!({
+            "trigger": { "type": "Redeem", "reward_id": "reward" },
+            "outcome": { "type": "SendChatMessage", "template": "Hello" },
+            "cooldown": { "enabled": false, "duration": 0, "per_user": false },
+            "require_role": "None",
+            "outcome_delay": 0
+        }))
+        .unwrap();
+
+        assert_eq!(config.outcomes.len(), 1);
+        assert_eq!(config.outcomes[0].delay, 0);
+        assert!(matches!(
+            &config.outcomes[0].outcome,
+            EventOutcome::SendChatMessage(data) if data.template == "Hello"
+        ));
+    }
+
+    /// Tests loading configs using the "outcomes" chain
+    #[test]
+    fn test_deserialize_outcomes() {
+        let config: EventConfig = serde_json::from_value(serde_json::json!({
+            "trigger": { "type": "Redeem", "reward_id": "reward" },
+            "outcomes": [
+                { "outcome": { "type": "SendChatMessage", "template": "First" } },
+                { "delay": 500, "outcome": { "type": "SendChatMessage", "template": "Second" } }
+            ],
+            "outcome_mode": "Parallel",
+            "cooldown": { "enabled": false, "duration": 0, "per_user": false },
+            "require_role": "None",
+            "outcome_delay": 0
+        }))
+        .unwrap();
+
+        assert_eq!(config.outcomes.len(), 2);
+        assert_eq!(config.outcomes[0].delay, 0);
+        assert_eq!(config.outcomes[1].delay, 500);
+    }
 
     #[tokio::test]
     async fn test_create() {}
@@ -1,7 +1,9 @@
 use crate::database::{DbPool, DbResult};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use strum::{Display, EnumString};
+use uuid::Uuid;
 
 #[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
 pub struct KeyValueModel {
@@ -11,6 +13,8 @@ pub struct KeyValueModel {
     #[sqlx(rename = "type")]
     pub ty: KeyValueType,
     pub value: String,
+    /// Time the key value expires at, expired key values are treated as missing
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 /// Key value type
@@ -32,23 +36,51 @@ pub struct CreateKeyValue {
     pub value: String,
     #[serde(rename = "type")]
     pub ty: KeyValueType,
+    /// Time the key value should expire at, None to never expire
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Namespace owned by a specific event or command, keys within the
+/// namespace are prefixed with the namespace followed by a "."
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyValueNamespace {
+    Event(Uuid),
+    Command(Uuid),
+}
+
+impl KeyValueNamespace {
+    /// Prefix of all keys within the namespace (i.e "event.{id}.")
+    pub fn prefix(&self) -> String {
+        match self {
+            KeyValueNamespace::Event(event_id) => format!("event.{event_id}."),
+            KeyValueNamespace::Command(command_id) => format!("command.{command_id}."),
+        }
+    }
+
+    /// Creates the full key for `key` within the namespace
+    pub fn key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix())
+    }
 }
 
 impl KeyValueModel {
-    /// Create a new sound
+    /// Create a new key value, replacing any existing value
     pub async fn create(db: &DbPool, create: CreateKeyValue) -> DbResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO "key_value" ("key", "value", "type")
-            VALUES (?, ?, ?)
+            INSERT INTO "key_value" ("key", "value", "type", "expires_at")
+            VALUES (?, ?, ?, ?)
             ON CONFLICT("key") DO UPDATE SET
                 "value" = excluded."value",
-                "type" = excluded."type"
+                "type" = excluded."type",
+                "expires_at" = excluded."expires_at"
         "#,
         )
         .bind(create.key)
         .bind(create.value)
         .bind(create.ty)
+        .bind(create.expires_at)
         .execute(db)
         .await?;
 
@@ -57,10 +89,126 @@ impl KeyValueModel {
 
     /// Find a specific key value by key
     pub async fn get_by_key(db: &DbPool, key: &str) -> DbResult<Option<Self>> {
-        sqlx::query_as(r#"SELECT * FROM "key_value" WHERE "key" = ?"#)
-            .bind(key)
-            .fetch_optional(db)
-            .await
+        sqlx::query_as(
+            r#"SELECT * FROM "key_value"
+            WHERE "key" = ? AND ("expires_at" IS NULL OR "expires_at" > ?)"#,
+        )
+        .bind(key)
+        .bind(Utc::now())
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Find all key values whose key starts with the provided prefix
+    pub async fn get_by_key_prefix(db: &DbPool, prefix: &str) -> DbResult<Vec<Self>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "key_value"
+            WHERE SUBSTR("key", 1, LENGTH(?)) = ? AND ("expires_at" IS NULL OR "expires_at" > ?)
+            ORDER BY "key" ASC"#,
+        )
+        .bind(prefix)
+        .bind(prefix)
+        .bind(Utc::now())
+        .fetch_all(db)
+        .await
+    }
+
+    /// Find all key values within the `namespace`
+    pub async fn get_by_namespace(
+        db: &DbPool,
+        namespace: KeyValueNamespace,
+    ) -> DbResult<Vec<Self>> {
+        Self::get_by_key_prefix(db, &namespace.prefix()).await
+    }
+
+    /// Atomically adds `amount` to a number key value, creating the key value
+    /// with `amount` if it does not exist or has expired. Values that are not
+    /// numbers are treated as zero.
+    ///
+    /// Returns the new value
+    pub async fn increment(db: &DbPool, key: &str, amount: i64) -> DbResult<i64> {
+        let now = Utc::now();
+        let result: (String,) = sqlx::query_as(
+            r#"
+            INSERT INTO "key_value" ("key", "value", "type")
+            VALUES (?, CAST(? AS TEXT), ?)
+            ON CONFLICT("key") DO UPDATE SET
+                "value" = CASE
+                    WHEN "expires_at" IS NOT NULL AND "expires_at" <= ? THEN excluded."value"
+                    ELSE CAST(CAST("value" AS INTEGER) + excluded."value" AS TEXT)
+                END,
+                "type" = excluded."type",
+                "expires_at" = CASE
+                    WHEN "expires_at" IS NOT NULL AND "expires_at" <= ? THEN NULL
+                    ELSE "expires_at"
+                END
+            RETURNING "value"
+        "#,
+        )
+        .bind(key)
+        .bind(amount)
+        .bind(KeyValueType::Number)
+        .bind(now)
+        .bind(now)
+        .fetch_one(db)
+        .await?;
+
+        Ok(result.0.parse().unwrap_or_default())
+    }
+
+    /// Atomically replaces a key value only when its current value matches
+    /// `expected`. When `expected` is None the key value is only created if
+    /// it does not exist or has expired.
+    ///
+    /// Returns whether the key value was replaced
+    pub async fn compare_and_set(
+        db: &DbPool,
+        expected: Option<&str>,
+        create: CreateKeyValue,
+    ) -> DbResult<bool> {
+        let now = Utc::now();
+        let result = match expected {
+            Some(expected) => {
+                sqlx::query(
+                    r#"
+                    UPDATE "key_value" SET "value" = ?, "type" = ?, "expires_at" = ?
+                    WHERE "key" = ? AND "value" = ?
+                        AND ("expires_at" IS NULL OR "expires_at" > ?)
+                "#,
+                )
+                .bind(create.value)
+                .bind(create.ty)
+                .bind(create.expires_at)
+                .bind(create.key)
+                .bind(expected)
+                .bind(now)
+                .execute(db)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO "key_value" ("key", "value", "type", "expires_at")
+                    VALUES (?, ?, ?, ?)
+                    ON CONFLICT("key") DO UPDATE SET
+                        "value" = excluded."value",
+                        "type" = excluded."type",
+                        "expires_at" = excluded."expires_at"
+                    WHERE "key_value"."expires_at" IS NOT NULL
+                        AND "key_value"."expires_at" <= ?
+                "#,
+                )
+                .bind(create.key)
+                .bind(create.value)
+                .bind(create.ty)
+                .bind(create.expires_at)
+                .bind(now)
+                .execute(db)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() > 0)
     }
 
     /// Find a specific key value by key
@@ -72,15 +220,63 @@ impl KeyValueModel {
 
         Ok(())
     }
+
+    /// Deletes all key values whose key starts with the provided prefix
+    ///
+    /// Returns the number of deleted key values
+    pub async fn delete_by_key_prefix(db: &DbPool, prefix: &str) -> DbResult<u64> {
+        let result =
+            sqlx::query(r#"DELETE FROM "key_value" WHERE SUBSTR("key", 1, LENGTH(?)) = ?"#)
+                .bind(prefix)
+                .bind(prefix)
+                .execute(db)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes all key values within the `namespace`
+    pub async fn delete_by_namespace(db: &DbPool, namespace: KeyValueNamespace) -> DbResult<u64> {
+        Self::delete_by_key_prefix(db, &namespace.prefix()).await
+    }
+
+    /// Deletes all expired key values
+    pub async fn delete_expired(db: &DbPool) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM "key_value" WHERE "expires_at" <= ?"#)
+            .bind(Utc::now())
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use chrono::{TimeDelta, Utc};
+    use uuid::Uuid;
+
     use crate::database::{
-        entity::key_value::{CreateKeyValue, KeyValueModel, KeyValueType},
+        entity::key_value::{CreateKeyValue, KeyValueModel, KeyValueNamespace, KeyValueType},
         mock_database,
     };
 
+    fn text(key: &str, value: &str) -> CreateKeyValue {
+        CreateKeyValue {
+            key: key.to_string(),
+            value: value.to_string(),
+            ty: KeyValueType::Text,
+            expires_at: None,
+        }
+    }
+
+    fn expired(key: &str, value: &str) -> CreateKeyValue {
+        CreateKeyValue {
+            expires_at: Some(Utc::now() - TimeDelta::seconds(1)),
+            ..text(key, value)
+        }
+    }
+
     #[tokio::test]
     async fn test_create() {
         let db = mock_database().await;
@@ -91,6 +287,7 @@ mod test {
                 key: "test".to_string(),
                 value: "test_value".to_string(),
                 ty: KeyValueType::Text,
+                expires_at: None,
             },
         )
         .await
@@ -115,6 +312,7 @@ mod test {
                 key: "test".to_string(),
                 value: "test_value".to_string(),
                 ty: KeyValueType::Text,
+                expires_at: None,
             },
         )
         .await
@@ -136,6 +334,7 @@ mod test {
                 key: "test".to_string(),
                 value: "test_value_2".to_string(),
                 ty: KeyValueType::Text,
+                expires_at: None,
             },
         )
         .await
@@ -168,6 +367,7 @@ mod test {
                 key: "test".to_string(),
                 value: "test_value".to_string(),
                 ty: KeyValueType::Text,
+                expires_at: None,
             },
         )
         .await
@@ -192,6 +392,7 @@ mod test {
                 key: "test".to_string(),
                 value: "test_value".to_string(),
                 ty: KeyValueType::Text,
+                expires_at: None,
             },
         )
         .await
@@ -206,4 +407,254 @@ mod test {
         let model = KeyValueModel::get_by_key(&db, "test").await.unwrap();
         assert!(model.is_none());
     }
+
+    #[tokio::test]
+    async fn test_increment() {
+        let db = mock_database().await;
+
+        // Missing key is created with the amount
+        let value = KeyValueModel::increment(&db, "test", 1).await.unwrap();
+        assert_eq!(value, 1);
+
+        let value = KeyValueModel::increment(&db, "test", 5).await.unwrap();
+        assert_eq!(value, 6);
+
+        let value = KeyValueModel::increment(&db, "test", -2).await.unwrap();
+        assert_eq!(value, 4);
+
+        let model = KeyValueModel::get_by_key(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(model.value, "4".to_string());
+        assert!(matches!(model.ty, KeyValueType::Number));
+    }
+
+    #[tokio::test]
+    async fn test_increment_non_number() {
+        let db = mock_database().await;
+
+        KeyValueModel::create(
+            &db,
+            CreateKeyValue {
+                key: "test".to_string(),
+                value: "test_value".to_string(),
+                ty: KeyValueType::Text,
+                expires_at: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let value = KeyValueModel::increment(&db, "test", 1).await.unwrap();
+        assert_eq!(value, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_by_key_prefix() {
+        let db = mock_database().await;
+
+        for key in ["prefix.b", "prefix.a", "other", "prefix_c"] {
+            KeyValueModel::create(
+                &db,
+                CreateKeyValue {
+                    key: key.to_string(),
+                    value: "test_value".to_string(),
+                    ty: KeyValueType::Text,
+                    expires_at: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let keys: Vec<String> = KeyValueModel::get_by_key_prefix(&db, "prefix.")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|model| model.key)
+            .collect();
+
+        assert_eq!(keys, vec!["prefix.a".to_string(), "prefix.b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_get_expired() {
+        let db = mock_database().await;
+
+        KeyValueModel::create(&db, expired("test", "test_value"))
+            .await
+            .unwrap();
+
+        let model = KeyValueModel::get_by_key(&db, "test").await.unwrap();
+        assert!(model.is_none());
+
+        let models = KeyValueModel::get_by_key_prefix(&db, "").await.unwrap();
+        assert!(models.is_empty());
+
+        // Key values that have not expired yet are still present
+        KeyValueModel::create(
+            &db,
+            CreateKeyValue {
+                expires_at: Some(Utc::now() + TimeDelta::minutes(1)),
+                ..text("test", "test_value")
+            },
+        )
+        .await
+        .unwrap();
+
+        let model = KeyValueModel::get_by_key(&db, "test").await.unwrap();
+        assert!(model.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_increment_expired() {
+        let db = mock_database().await;
+
+        KeyValueModel::create(&db, expired("test", "10"))
+            .await
+            .unwrap();
+
+        // Expired value is replaced rather than added to
+        let value = KeyValueModel::increment(&db, "test", 1).await.unwrap();
+        assert_eq!(value, 1);
+
+        let model = KeyValueModel::get_by_key(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(model.expires_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set() {
+        let db = mock_database().await;
+
+        // Missing key is only created when nothing is expected
+        let replaced = KeyValueModel::compare_and_set(&db, Some("a"), text("test", "b"))
+            .await
+            .unwrap();
+        assert!(!replaced);
+
+        let replaced = KeyValueModel::compare_and_set(&db, None, text("test", "a"))
+            .await
+            .unwrap();
+        assert!(replaced);
+
+        // Existing key is not replaced when nothing is expected
+        let replaced = KeyValueModel::compare_and_set(&db, None, text("test", "b"))
+            .await
+            .unwrap();
+        assert!(!replaced);
+
+        let replaced = KeyValueModel::compare_and_set(&db, Some("b"), text("test", "c"))
+            .await
+            .unwrap();
+        assert!(!replaced);
+
+        let replaced = KeyValueModel::compare_and_set(&db, Some("a"), text("test", "c"))
+            .await
+            .unwrap();
+        assert!(replaced);
+
+        let model = KeyValueModel::get_by_key(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(model.value, "c".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set_expired() {
+        let db = mock_database().await;
+
+        KeyValueModel::create(&db, expired("test", "a"))
+            .await
+            .unwrap();
+
+        // Expired values are treated as missing
+        let replaced = KeyValueModel::compare_and_set(&db, Some("a"), text("test", "b"))
+            .await
+            .unwrap();
+        assert!(!replaced);
+
+        let replaced = KeyValueModel::compare_and_set(&db, None, text("test", "b"))
+            .await
+            .unwrap();
+        assert!(replaced);
+
+        let model = KeyValueModel::get_by_key(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(model.value, "b".to_string());
+        assert!(model.expires_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_key_prefix() {
+        let db = mock_database().await;
+
+        for key in ["prefix.a", "prefix.b", "other"] {
+            KeyValueModel::create(&db, text(key, "test_value"))
+                .await
+                .unwrap();
+        }
+
+        let deleted = KeyValueModel::delete_by_key_prefix(&db, "prefix.")
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+
+        let keys: Vec<String> = KeyValueModel::get_by_key_prefix(&db, "")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|model| model.key)
+            .collect();
+        assert_eq!(keys, vec!["other".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_namespace() {
+        let db = mock_database().await;
+
+        let event_id = Uuid::new_v4();
+        let namespace = KeyValueNamespace::Event(event_id);
+        assert_eq!(namespace.key("points"), format!("event.{event_id}.points"));
+
+        KeyValueModel::create(&db, text(&namespace.key("points"), "1"))
+            .await
+            .unwrap();
+        KeyValueModel::create(
+            &db,
+            text(&KeyValueNamespace::Command(event_id).key("points"), "2"),
+        )
+        .await
+        .unwrap();
+
+        let models = KeyValueModel::get_by_namespace(&db, namespace)
+            .await
+            .unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].value, "1".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired() {
+        let db = mock_database().await;
+
+        KeyValueModel::create(&db, expired("expired", "test_value"))
+            .await
+            .unwrap();
+        KeyValueModel::create(&db, text("test", "test_value"))
+            .await
+            .unwrap();
+
+        KeyValueModel::delete_expired(&db).await.unwrap();
+
+        let deleted = KeyValueModel::delete_by_key_prefix(&db, "").await.unwrap();
+        assert_eq!(deleted, 1);
+    }
 }
@@ -13,6 +13,8 @@ pub mod model_data;
 pub mod secrets;
 pub mod shared;
 pub mod sounds;
+pub mod timer_state;
+pub mod viewers;
 
 pub const TWITCH_SECRET_KEY: &str = "__TWITCH_SECRET__";
 pub const VT_SECRET_KEY: &str = "__VT_STUDIO_SECRET__";
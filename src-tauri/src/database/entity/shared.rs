@@ -12,6 +12,62 @@ pub enum MinimumRequireRole {
     Broadcaster,
 }
 
+/// Limit on the number of times an event or command can
+/// be used within a window of time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageLimit {
+    /// Maximum number of uses allowed within the window
+    pub max_uses: u32,
+    /// Whether the limit applies to each user individually
+    /// rather than to all users combined
+    #[serde(default)]
+    pub per_user: bool,
+    /// Window of time the uses are counted within
+    pub window: UsageLimitWindow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum UsageLimitWindow {
+    /// Uses since the current stream started, while offline uses since the
+    /// most recent stream started are counted so the limit still applies
+    /// between streams (i.e when testing or running events while offline)
+    Stream,
+    /// Uses within a rolling duration (Milliseconds)
+    Duration { duration: u32 },
+}
+
+/// Permissions a script requests, local scripts without declared
+/// permissions have unrestricted access while imported scripts without
+/// declared permissions are given the default (no access)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptPermissions {
+    /// Hosts the script can send HTTP requests to, a leading `*.`
+    /// allows any subdomain (i.e "*.example.com")
+    pub http_hosts: Vec<String>,
+    /// Whether the script can access Twitch credentials and APIs
+    pub twitch: bool,
+    /// Key value namespaces the script can access in addition to its
+    /// own, a namespace covers keys prefixed with "{namespace}." and
+    /// "*" covers all keys
+    pub kv_namespaces: Vec<String>,
+    /// Whether the script can emit overlay messages
+    pub overlay: bool,
+}
+
+/// Execution limits for a script, limits that are not set use the
+/// defaults from the script runtime config
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Maximum time the script can take to complete including time spent
+    /// waiting on async operations (Milliseconds)
+    pub wall_clock_limit: Option<u32>,
+    /// Maximum total time the script can spend running JS code (Milliseconds)
+    pub cpu_time_limit: Option<u32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MinMax<T> {
     /// Minimum value
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::database::{DbPool, DbResult};
+
+/// Persisted scheduler state for a timer event, allows timers to
+/// resume from their previous schedule after a restart
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimerStateModel {
+    /// ID of the timer event
+    pub event_id: Uuid,
+    /// Last time the timer ran
+    pub last_run: Option<DateTime<Utc>>,
+    /// Next time the timer is scheduled to run
+    pub next_run: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct SaveTimerState {
+    pub event_id: Uuid,
+    /// Time the timer ran, [None] keeps the previous last run
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: DateTime<Utc>,
+}
+
+impl TimerStateModel {
+    /// Save the state of a timer, creating the state if it does not exist
+    pub async fn save(db: &DbPool, save: SaveTimerState) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO "timer_states" ("event_id", "last_run", "next_run")
+            VALUES (?, ?, ?)
+            ON CONFLICT("event_id") DO UPDATE SET
+                "last_run" = COALESCE(excluded."last_run", "last_run"),
+                "next_run" = excluded."next_run"
+        "#,
+        )
+        .bind(save.event_id)
+        .bind(save.last_run)
+        .bind(save.next_run)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the state of a specific timer
+    pub async fn get_by_event_id(db: &DbPool, event_id: Uuid) -> DbResult<Option<Self>> {
+        sqlx::query_as(r#"SELECT * FROM "timer_states" WHERE "event_id" = ?"#)
+            .bind(event_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete the state of a specific timer
+    pub async fn delete_by_event_id(db: &DbPool, event_id: Uuid) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM "timer_states" WHERE "event_id" = ?"#)
+            .bind(event_id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Days, Utc};
+
+    use crate::database::{
+        DbPool,
+        entity::{
+            events::{CreateEvent, EventConfig, EventModel, EventTrigger},
+            timer_state::{SaveTimerState, TimerStateModel},
+        },
+        mock_database,
+    };
+
+    async fn create_timer_event(db: &DbPool) -> EventModel {
+        EventModel::create(
+            db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Timer".to_string(),
+                config: EventConfig {
+                    trigger: EventTrigger::Timer {
+                        interval: 60,
+                        min_chat_messages: Default::default(),
+                        schedule: Default::default(),
+                        days: Default::default(),
+                        jitter: Default::default(),
+                        missed_runs: Default::default(),
+                    },
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Tests saving a new timer state
+    #[tokio::test]
+    async fn test_save_new() {
+        let db = mock_database().await;
+        let event = create_timer_event(&db).await;
+        let next_run = Utc::now();
+
+        TimerStateModel::save(
+            &db,
+            SaveTimerState {
+                event_id: event.id,
+                last_run: None,
+                next_run,
+            },
+        )
+        .await
+        .unwrap();
+
+        let state = TimerStateModel::get_by_event_id(&db, event.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(state.last_run, None);
+        assert_eq!(state.next_run, next_run);
+    }
+
+    /// Tests that saving without a last run keeps the previous last run
+    #[tokio::test]
+    async fn test_save_keeps_last_run() {
+        let db = mock_database().await;
+        let event = create_timer_event(&db).await;
+        let last_run = Utc::now();
+        let next_run = last_run.checked_add_days(Days::new(1)).unwrap();
+
+        TimerStateModel::save(
+            &db,
+            SaveTimerState {
+                event_id: event.id,
+                last_run: Some(last_run),
+                next_run: last_run,
+            },
+        )
+        .await
+        .unwrap();
+
+        TimerStateModel::save(
+            &db,
+            SaveTimerState {
+                event_id: event.id,
+                last_run: None,
+                next_run,
+            },
+        )
+        .await
+        .unwrap();
+
+        let state = TimerStateModel::get_by_event_id(&db, event.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(state.last_run, Some(last_run));
+        assert_eq!(state.next_run, next_run);
+    }
+
+    /// Tests that deleting the event deletes the timer state
+    #[tokio::test]
+    async fn test_delete_event_cascade() {
+        let db = mock_database().await;
+        let event = create_timer_event(&db).await;
+        let event_id = event.id;
+
+        TimerStateModel::save(
+            &db,
+            SaveTimerState {
+                event_id,
+                last_run: None,
+                next_run: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+
+        event.delete(&db).await.unwrap();
+
+        let state = TimerStateModel::get_by_event_id(&db, event_id)
+            .await
+            .unwrap();
+        assert!(state.is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::database::{DbPool, DbResult};
+
+/// Viewer that has chatted in the channel, unlike chat history
+/// viewers are never cleaned up
+#[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
+pub struct ViewerModel {
+    /// ID of the twitch user
+    pub user_id: String,
+    /// Total number of chat messages sent by the viewer
+    pub message_count: u32,
+    /// When the viewer sent their first chat message
+    pub first_seen_at: DateTime<Utc>,
+    /// When the viewer last sent a chat message
+    pub last_seen_at: DateTime<Utc>,
+    /// When the viewer sent the chat message before their last
+    /// message, None when they have only been seen once
+    pub previous_seen_at: Option<DateTime<Utc>>,
+}
+
+impl ViewerModel {
+    /// Get a viewer by their twitch user ID
+    pub async fn get_by_user_id(db: &DbPool, user_id: &str) -> DbResult<Option<ViewerModel>> {
+        sqlx::query_as(r#"SELECT * FROM "viewers" WHERE "user_id" = ?"#)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Records a chat message from the viewer at `seen_at`, creating the
+    /// viewer if they have not been seen before
+    ///
+    /// Returns the viewer as it was before the message was recorded, None for
+    /// new viewers. Recorded and returned in a single statement so concurrent
+    /// messages from the same viewer never see the same previous state
+    pub async fn record_message(
+        db: &DbPool,
+        user_id: &str,
+        seen_at: DateTime<Utc>,
+    ) -> DbResult<Option<ViewerModel>> {
+        let viewer: ViewerModel = sqlx::query_as(
+            r#"INSERT INTO "viewers" ("user_id", "message_count", "first_seen_at", "last_seen_at")
+            VALUES (?, 1, ?, ?)
+            ON CONFLICT("user_id") DO UPDATE SET
+                "message_count" = "message_count" + 1,
+                "previous_seen_at" = "last_seen_at",
+                "last_seen_at" = excluded."last_seen_at"
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(seen_at)
+        .bind(seen_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok(viewer.before_last_message())
+    }
+
+    /// Gets the viewer as it was before their last message, None
+    /// when the last message was their first
+    fn before_last_message(self) -> Option<ViewerModel> {
+        let last_seen_at = self.previous_seen_at?;
+
+        Some(ViewerModel {
+            user_id: self.user_id,
+            message_count: self.message_count.saturating_sub(1),
+            first_seen_at: self.first_seen_at,
+            last_seen_at,
+            previous_seen_at: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Days, Utc};
+
+    use crate::database::{entity::viewers::ViewerModel, mock_database};
+
+    #[tokio::test]
+    async fn test_record_message_new_viewer() {
+        let db = mock_database().await;
+        let seen_at = Utc::now();
+
+        let previous = ViewerModel::record_message(&db, "test", seen_at)
+            .await
+            .unwrap();
+        assert!(previous.is_none());
+
+        let viewer = ViewerModel::get_by_user_id(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(viewer.message_count, 1);
+        assert_eq!(viewer.first_seen_at, seen_at);
+        assert_eq!(viewer.last_seen_at, seen_at);
+    }
+
+    #[tokio::test]
+    async fn test_record_message_existing_viewer() {
+        let db = mock_database().await;
+        let first_time = Utc::now();
+        let second_time = first_time.checked_add_days(Days::new(5)).unwrap();
+
+        ViewerModel::record_message(&db, "test", first_time)
+            .await
+            .unwrap();
+        let previous = ViewerModel::record_message(&db, "test", second_time)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(previous.message_count, 1);
+        assert_eq!(previous.first_seen_at, first_time);
+        assert_eq!(previous.last_seen_at, first_time);
+
+        let viewer = ViewerModel::get_by_user_id(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(viewer.message_count, 2);
+        assert_eq!(viewer.first_seen_at, first_time);
+        assert_eq!(viewer.last_seen_at, second_time);
+        assert_eq!(viewer.previous_seen_at, Some(first_time));
+    }
+
+    /// Tests that only one of many concurrent messages from a
+    /// new viewer is treated as their first message
+    #[tokio::test]
+    async fn test_record_message_concurrent() {
+        let db = mock_database().await;
+        let seen_at = Utc::now();
+
+        let results = futures::future::join_all(
+            (0..10).map(|_| ViewerModel::record_message(&db, "test", seen_at)),
+        )
+        .await;
+
+        let first_messages = results
+            .into_iter()
+            .map(|result| result.unwrap())
+            .filter(Option::is_none)
+            .count();
+        assert_eq!(first_messages, 1);
+
+        let viewer = ViewerModel::get_by_user_id(&db, "test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(viewer.message_count, 10);
+    }
+
+    #[tokio::test]
+    async fn test_get_by_user_id_unknown() {
+        let db = mock_database().await;
+        let viewer = ViewerModel::get_by_user_id(&db, "test").await.unwrap();
+        assert!(viewer.is_none());
+    }
+}
@@ -22,6 +22,12 @@ const MIGRATIONS: &[(&str, &str)]= &[
     ("m20250124_082703_create_chat_history_table", include_str!("sql/m20250124_082703_create_chat_history_table.sql")),
     ("m20250209_101257_create_command_aliases_table", include_str!("sql/m20250209_101257_create_command_aliases_table.sql")),
     ("m20250216_140137_create_secrets_table", include_str!("sql/m20250216_140137_create_secrets_table.sql")),
+    ("m20251017_090000_event_outcome_chains", include_str!("sql/m20251017_090000_event_outcome_chains.sql")),
+    ("m20251017_100000_create_viewers_table", include_str!("sql/m20251017_100000_create_viewers_table.sql")),
+    ("m20251017_110000_create_timer_states_table", include_str!("sql/m20251017_110000_create_timer_states_table.sql")),
+    ("m20251017_120000_add_execution_user_ids", include_str!("sql/m20251017_120000_add_execution_user_ids.sql")),
+    ("m20251017_130000_add_key_value_expiry", include_str!("sql/m20251017_130000_add_key_value_expiry.sql")),
+    ("m20251017_140000_add_viewer_previous_seen_at", include_str!("sql/m20251017_140000_add_viewer_previous_seen_at.sql")),
 ];
 
 #[derive(FromRow)]
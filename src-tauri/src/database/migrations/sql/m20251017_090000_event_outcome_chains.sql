@@ -0,0 +1,8 @@
+-- Migrate the single "outcome" of each event into the first step of the "outcomes" chain
+UPDATE "events"
+SET "config" = JSON_SET(
+    JSON_REMOVE("config", '$.outcome'),
+    '$.outcomes', JSON_ARRAY(JSON_OBJECT('delay', 0, 'outcome', JSON(JSON_EXTRACT("config", '$.outcome')))),
+    '$.outcome_mode', 'Sequential'
+)
+WHERE JSON_TYPE("config", '$.outcome') = 'object';
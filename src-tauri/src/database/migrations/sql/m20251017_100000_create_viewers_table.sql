@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS "viewers" (
+	"user_id"	VARCHAR NOT NULL PRIMARY KEY,
+	"message_count"	INTEGER NOT NULL DEFAULT 0,
+	"first_seen_at"	datetime_text NOT NULL,
+	"last_seen_at"	datetime_text NOT NULL
+);
+
+-- Seed viewers from the existing chat history
+INSERT OR IGNORE INTO "viewers" ("user_id", "message_count", "first_seen_at", "last_seen_at")
+SELECT "user_id", COUNT(*), MIN("created_at"), MAX("created_at")
+FROM "chat_history"
+GROUP BY "user_id";
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "timer_states" (
+	"event_id"	uuid_text NOT NULL PRIMARY KEY,
+	"last_run"	datetime_text NULL,
+	"next_run"	datetime_text NOT NULL,
+
+	FOREIGN KEY ("event_id")
+        REFERENCES "events" ("id")
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,14 @@
+-- Store the ID of the user who triggered each execution as its own column
+ALTER TABLE "event_executions" ADD COLUMN "user_id" TEXT NULL;
+ALTER TABLE "command_executions" ADD COLUMN "user_id" TEXT NULL;
+
+-- Migrate existing user IDs from the execution metadata
+UPDATE "event_executions" SET "user_id" = JSON_EXTRACT("metadata", '$.user.id');
+UPDATE "command_executions" SET "user_id" = JSON_EXTRACT("metadata", '$.user.id');
+
+-- Index for finding executions from a specific user
+CREATE INDEX "idx-event-executions-user" 
+ON "event_executions" ("event_id", "user_id", "created_at");
+
+CREATE INDEX "idx-command-executions-user" 
+ON "command_executions" ("command_id", "user_id", "created_at");
@@ -0,0 +1,5 @@
+-- Optional time the key value expires at, expired key values are treated as missing
+ALTER TABLE "key_value" ADD COLUMN "expires_at" datetime_text NULL;
+
+-- Index for cleaning up expired key values
+CREATE INDEX "idx-key-value-expires-at" ON "key_value" ("expires_at");
@@ -0,0 +1,3 @@
+-- Time the viewer sent the message before their last message, allows recording a
+-- message and reading the previous state of the viewer in a single statement
+ALTER TABLE "viewers" ADD COLUMN "previous_seen_at" datetime_text NULL;
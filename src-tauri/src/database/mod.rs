@@ -4,7 +4,7 @@ use chrono::{Days, Utc};
 use entity::{
     app_data::AppDataModel, chat_history::ChatHistoryModel,
     command_execution::CommandExecutionModel, command_log::CommandLogsModel,
-    event_execution::EventExecutionModel, event_log::EventLogsModel,
+    event_execution::EventExecutionModel, event_log::EventLogsModel, key_value::KeyValueModel,
 };
 use log::error;
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
@@ -95,5 +95,8 @@ pub async fn clean_old_data(db: DbPool) -> anyhow::Result<()> {
         ChatHistoryModel::delete_before(&db, clean_date).await?;
     }
 
+    // Clean expired key values
+    KeyValueModel::delete_expired(&db).await?;
+
     Ok(())
 }
@@ -0,0 +1,365 @@
+//! # Arguments
+//!
+//! Validation and parsing of typed command arguments, parsed values are
+//! provided to command scripts by argument name
+
+use std::collections::HashSet;
+
+use anyhow::bail;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::database::entity::commands::{CommandArgument, CommandArgumentType};
+
+/// Parsed argument values by argument name
+pub type CommandArguments = Map<String, Value>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CommandArgumentError {
+    #[error("missing {0}")]
+    Missing(String),
+    #[error("{0} must be a username")]
+    InvalidUser(String),
+    #[error("{0} must be a whole number")]
+    InvalidInteger(String),
+    #[error("{name} must be {range}")]
+    OutOfRange { name: String, range: String },
+    #[error("{name} must be one of {choices}")]
+    InvalidChoice { name: String, choices: String },
+}
+
+/// Validates a command arguments `schema` before it is saved, ensuring
+/// every argument can actually be provided by a user
+pub fn validate_command_arguments(schema: &[CommandArgument]) -> anyhow::Result<()> {
+    let mut names = HashSet::new();
+    let mut seen_optional = false;
+
+    for (index, argument) in schema.iter().enumerate() {
+        let name = &argument.name;
+
+        if name.trim().is_empty() {
+            bail!("argument names cannot be empty");
+        }
+
+        if !names.insert(name.as_str()) {
+            bail!("duplicate argument \"{name}\"");
+        }
+
+        if argument.optional {
+            seen_optional = true;
+        } else if seen_optional {
+            bail!("required argument \"{name}\" cannot come after an optional argument");
+        }
+
+        match &argument.ty {
+            CommandArgumentType::Rest if index + 1 != schema.len() => {
+                bail!("rest argument \"{name}\" must be the last argument");
+            }
+            CommandArgumentType::Integer {
+                min: Some(min),
+                max: Some(max),
+            } if min > max => {
+                bail!("argument \"{name}\" minimum cannot be greater than its maximum");
+            }
+            CommandArgumentType::Choice { choices } if choices.is_empty() => {
+                bail!("argument \"{name}\" must have at least one choice");
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the raw command `args` using the command arguments `schema`.
+///
+/// Omitted optional arguments are provided as null, extra arguments
+/// beyond the schema are ignored
+pub fn parse_command_arguments(
+    schema: &[CommandArgument],
+    args: &[String],
+) -> Result<CommandArguments, CommandArgumentError> {
+    let mut values = CommandArguments::new();
+    let mut args = args.iter();
+
+    for argument in schema {
+        let value = match &argument.ty {
+            CommandArgumentType::Rest => {
+                let rest = args.by_ref().map(String::as_str).collect::<Vec<_>>();
+                (!rest.is_empty()).then(|| rest.join(" "))
+            }
+            _ => args.next().cloned(),
+        };
+
+        let Some(value) = value else {
+            if !argument.optional {
+                return Err(CommandArgumentError::Missing(argument.name.clone()));
+            }
+
+            values.insert(argument.name.clone(), Value::Null);
+            continue;
+        };
+
+        let value = parse_argument_value(argument, value)?;
+        values.insert(argument.name.clone(), value);
+    }
+
+    Ok(values)
+}
+
+/// Parses a single argument value
+fn parse_argument_value(
+    argument: &CommandArgument,
+    value: String,
+) -> Result<Value, CommandArgumentError> {
+    let name = &argument.name;
+
+    match &argument.ty {
+        CommandArgumentType::User => {
+            let username = value.strip_prefix('@').unwrap_or(&value);
+            if !is_valid_username(username) {
+                return Err(CommandArgumentError::InvalidUser(name.clone()));
+            }
+
+            Ok(Value::String(username.to_lowercase()))
+        }
+        CommandArgumentType::Integer { min, max } => {
+            let number: i64 = value
+                .parse()
+                .map_err(|_| CommandArgumentError::InvalidInteger(name.clone()))?;
+
+            if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                return Err(CommandArgumentError::OutOfRange {
+                    name: name.clone(),
+                    range: format_range(*min, *max),
+                });
+            }
+
+            Ok(Value::from(number))
+        }
+        CommandArgumentType::Choice { choices } => choices
+            .iter()
+            .find(|choice| choice.eq_ignore_ascii_case(&value))
+            .map(|choice| Value::String(choice.clone()))
+            .ok_or_else(|| CommandArgumentError::InvalidChoice {
+                name: name.clone(),
+                choices: choices.join(", "),
+            }),
+        CommandArgumentType::Rest => Ok(Value::String(value)),
+    }
+}
+
+/// Checks that a username only contains characters twitch allows
+fn is_valid_username(username: &str) -> bool {
+    (1..=25).contains(&username.len())
+        && username
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+fn format_range(min: Option<i64>, max: Option<i64>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("between {min} and {max}"),
+        (Some(min), None) => format!("at least {min}"),
+        (None, Some(max)) => format!("at most {max}"),
+        (None, None) => "a whole number".to_string(),
+    }
+}
+
+/// Creates a usage string for a command from its arguments schema
+/// (i.e "!give <user> <amount> [message...]")
+pub fn command_usage(command: &str, schema: &[CommandArgument]) -> String {
+    let mut usage = command.to_string();
+
+    for argument in schema {
+        let name = match &argument.ty {
+            CommandArgumentType::Choice { choices } => choices.join("|"),
+            CommandArgumentType::Rest => format!("{}...", argument.name),
+            _ => argument.name.clone(),
+        };
+
+        usage.push(' ');
+        if argument.optional {
+            usage.push_str(&format!("[{name}]"));
+        } else {
+            usage.push_str(&format!("<{name}>"));
+        }
+    }
+
+    usage
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{Value, json};
+
+    use super::{
+        CommandArgumentError, command_usage, parse_command_arguments, validate_command_arguments,
+    };
+    use crate::database::entity::commands::{CommandArgument, CommandArgumentType};
+
+    fn argument(name: &str, optional: bool, ty: CommandArgumentType) -> CommandArgument {
+        CommandArgument {
+            name: name.to_string(),
+            optional,
+            ty,
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn give_schema() -> Vec<CommandArgument> {
+        vec![
+            argument("user", false, CommandArgumentType::User),
+            argument(
+                "amount",
+                false,
+                CommandArgumentType::Integer {
+                    min: Some(1),
+                    max: Some(100),
+                },
+            ),
+            argument(
+                "item",
+                false,
+                CommandArgumentType::Choice {
+                    choices: vec!["Sword".to_string(), "Shield".to_string()],
+                },
+            ),
+            argument("message", true, CommandArgumentType::Rest),
+        ]
+    }
+
+    /// Tests parsing valid arguments
+    #[test]
+    fn test_parse_arguments() {
+        let schema = give_schema();
+
+        let values = parse_command_arguments(
+            &schema,
+            &args(&["@Jacobtread", "5", "sword", "have", "fun"]),
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(values),
+            json!({
+                "user": "jacobtread",
+                "amount": 5,
+                "item": "Sword",
+                "message": "have fun"
+            })
+        );
+
+        // Optional arguments are null when omitted
+        let values =
+            parse_command_arguments(&schema, &args(&["jacobtread", "5", "shield"])).unwrap();
+        assert_eq!(values.get("message"), Some(&Value::Null));
+    }
+
+    /// Tests that invalid arguments are reported
+    #[test]
+    fn test_parse_arguments_invalid() {
+        let schema = give_schema();
+
+        let cases = [
+            (
+                args(&["jacobtread"]),
+                CommandArgumentError::Missing("amount".to_string()),
+            ),
+            (
+                args(&["@", "5", "sword"]),
+                CommandArgumentError::InvalidUser("user".to_string()),
+            ),
+            (
+                args(&["jacobtread", "five", "sword"]),
+                CommandArgumentError::InvalidInteger("amount".to_string()),
+            ),
+            (
+                args(&["jacobtread", "101", "sword"]),
+                CommandArgumentError::OutOfRange {
+                    name: "amount".to_string(),
+                    range: "between 1 and 100".to_string(),
+                },
+            ),
+            (
+                args(&["jacobtread", "5", "bow"]),
+                CommandArgumentError::InvalidChoice {
+                    name: "item".to_string(),
+                    choices: "Sword, Shield".to_string(),
+                },
+            ),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(
+                parse_command_arguments(&schema, &args),
+                Err(expected),
+                "args: {args:?}"
+            );
+        }
+    }
+
+    /// Tests creating a usage string from a schema
+    #[test]
+    fn test_command_usage() {
+        assert_eq!(
+            command_usage("!give", &give_schema()),
+            "!give <user> <amount> <Sword|Shield> [message...]"
+        );
+    }
+
+    /// Tests that valid schemas are accepted
+    #[test]
+    fn test_validate_arguments() {
+        validate_command_arguments(&give_schema()).unwrap();
+        validate_command_arguments(&[]).unwrap();
+    }
+
+    /// Tests that schemas which cannot be satisfied are rejected
+    #[test]
+    fn test_validate_arguments_invalid() {
+        let cases = [
+            // Rest argument that is not last
+            vec![
+                argument("message", false, CommandArgumentType::Rest),
+                argument("user", false, CommandArgumentType::User),
+            ],
+            // Required argument after an optional argument
+            vec![
+                argument("user", true, CommandArgumentType::User),
+                argument("message", false, CommandArgumentType::Rest),
+            ],
+            // Minimum greater than maximum
+            vec![argument(
+                "amount",
+                false,
+                CommandArgumentType::Integer {
+                    min: Some(10),
+                    max: Some(1),
+                },
+            )],
+            // Empty choices
+            vec![argument(
+                "item",
+                false,
+                CommandArgumentType::Choice { choices: vec![] },
+            )],
+            // Duplicate names
+            vec![
+                argument("user", false, CommandArgumentType::User),
+                argument("user", false, CommandArgumentType::User),
+            ],
+            // Empty name
+            vec![argument(" ", false, CommandArgumentType::User)],
+        ];
+
+        for schema in cases {
+            assert!(
+                validate_command_arguments(&schema).is_err(),
+                "schema: {schema:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! # Counter
+//!
+//! Named counters stored in the key value store, used by counter commands
+//! and the `$(counter name)` / `$(count name)` template variables
+
+use anyhow::Context;
+use log::debug;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{
+    TwitchEventUser,
+    feedback::send_feedback_message,
+    matching::EventInputData,
+    processing::has_required_role,
+    template::{TemplateContext, render_template},
+};
+use crate::{
+    database::{
+        DbPool, DbResult,
+        entity::{
+            key_value::{CreateKeyValue, KeyValueModel, KeyValueType},
+            shared::MinimumRequireRole,
+        },
+    },
+    twitch::manager::Twitch,
+};
+
+/// Prefix for the key value keys that counters are stored under
+pub const COUNTER_KEY_PREFIX: &str = "counter.";
+
+/// Message used by counter commands without a message
+const DEFAULT_COUNTER_MESSAGE: &str = "$(name): $(value)";
+
+/// Counter and its current value
+#[derive(Debug, Serialize)]
+pub struct Counter {
+    pub name: String,
+    pub value: i64,
+}
+
+/// Action requested by the arguments of a counter command
+#[derive(Debug, PartialEq)]
+pub enum CounterAction {
+    /// Show the current value
+    View,
+    /// Increase the value by an amount
+    Increment(i64),
+    /// Decrease the value by an amount
+    Decrement(i64),
+    /// Set the value back to zero
+    Reset,
+    /// Set the value to a specific number
+    Set(i64),
+}
+
+/// Roles required to perform each counter action, viewing is
+/// restricted by the role required for the command itself
+pub struct CounterRoles<'a> {
+    pub increment: &'a MinimumRequireRole,
+    pub decrement: &'a MinimumRequireRole,
+    pub reset: &'a MinimumRequireRole,
+}
+
+fn counter_key(name: &str) -> String {
+    format!("{COUNTER_KEY_PREFIX}{name}")
+}
+
+/// Get the current value of a counter, missing counters are zero
+pub async fn get_counter(db: &DbPool, name: &str) -> DbResult<i64> {
+    let value = KeyValueModel::get_by_key(db, &counter_key(name)).await?;
+    Ok(value
+        .and_then(|value| value.value.parse().ok())
+        .unwrap_or_default())
+}
+
+/// Add an amount to a counter, returns the new value
+pub async fn add_counter(db: &DbPool, name: &str, amount: i64) -> DbResult<i64> {
+    KeyValueModel::increment(db, &counter_key(name), amount).await
+}
+
+/// Set the value of a counter
+pub async fn set_counter(db: &DbPool, name: &str, value: i64) -> DbResult<()> {
+    KeyValueModel::create(
+        db,
+        CreateKeyValue {
+            key: counter_key(name),
+            value: value.to_string(),
+            ty: KeyValueType::Number,
+            expires_at: None,
+        },
+    )
+    .await
+}
+
+/// Delete a counter
+pub async fn delete_counter(db: &DbPool, name: &str) -> DbResult<()> {
+    KeyValueModel::delete_by_key(db, &counter_key(name)).await
+}
+
+/// Get all the counters and their values
+pub async fn all_counters(db: &DbPool) -> DbResult<Vec<Counter>> {
+    let values = KeyValueModel::get_by_key_prefix(db, COUNTER_KEY_PREFIX).await?;
+    Ok(values
+        .into_iter()
+        .filter_map(|value| {
+            let name = value.key.strip_prefix(COUNTER_KEY_PREFIX)?.to_string();
+            let value = value.value.parse().unwrap_or_default();
+            Some(Counter { name, value })
+        })
+        .collect())
+}
+
+/// Parses the action for a counter command from its arguments, `+` and `-`
+/// change the value by one or by a specific amount (`+5`), `reset` sets the
+/// value to zero and `set 5` sets the value. Anything else views the value
+pub fn parse_counter_action(args: &[String]) -> CounterAction {
+    let Some(first) = args.first() else {
+        return CounterAction::View;
+    };
+
+    let amount = |value: &str| -> Option<i64> {
+        if value.is_empty() {
+            return Some(1);
+        }
+        value.parse().ok().filter(|value: &i64| *value > 0)
+    };
+
+    if let Some(value) = first.strip_prefix('+') {
+        return amount(value).map_or(CounterAction::View, CounterAction::Increment);
+    }
+
+    if let Some(value) = first.strip_prefix('-') {
+        return amount(value).map_or(CounterAction::View, CounterAction::Decrement);
+    }
+
+    if first.eq_ignore_ascii_case("reset") {
+        return CounterAction::Reset;
+    }
+
+    if first.eq_ignore_ascii_case("set") {
+        if let Some(value) = args.get(1).and_then(|value| value.parse().ok()) {
+            return CounterAction::Set(value);
+        }
+    }
+
+    CounterAction::View
+}
+
+/// Executes a counter command, performs the action requested by the arguments
+/// if the user has the role required and replies with the counter value
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_counter_command(
+    db: &DbPool,
+    twitch: &Twitch,
+    command_id: Uuid,
+    user: &TwitchEventUser,
+    args: &[String],
+    input_data: &EventInputData,
+    name: &str,
+    message: &str,
+    roles: CounterRoles<'_>,
+    require_role_message: Option<&str>,
+) -> anyhow::Result<()> {
+    let action = parse_counter_action(args);
+
+    let required_role = match action {
+        CounterAction::View => &MinimumRequireRole::None,
+        CounterAction::Increment(_) => roles.increment,
+        CounterAction::Decrement(_) => roles.decrement,
+        CounterAction::Reset | CounterAction::Set(_) => roles.reset,
+    };
+
+    if !has_required_role(twitch, Some(&user.id), required_role).await {
+        debug!("skipping counter action: missing required role");
+        send_feedback_message(
+            db,
+            twitch,
+            command_id,
+            require_role_message,
+            user,
+            input_data,
+            &[],
+        )
+        .await;
+        return Ok(());
+    }
+
+    let value = match action {
+        CounterAction::View => get_counter(db, name).await,
+        CounterAction::Increment(amount) => add_counter(db, name, amount).await,
+        CounterAction::Decrement(amount) => add_counter(db, name, -amount).await,
+        CounterAction::Reset => set_counter(db, name, 0).await.map(|_| 0),
+        CounterAction::Set(value) => set_counter(db, name, value).await.map(|_| value),
+    }
+    .context("failed to update counter")?;
+
+    let message = if message.trim().is_empty() {
+        DEFAULT_COUNTER_MESSAGE
+    } else {
+        message
+    };
+
+    let message = message
+        .replace("$(name)", name)
+        .replace("$(value)", &value.to_string());
+
+    let ctx = TemplateContext {
+        db,
+        twitch,
+        user: Some(user),
+        args,
+        input_data,
+        variables: &[],
+    };
+
+    let message = render_template(&message, &ctx).await;
+    twitch.send_chat_message_chunked(&message).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        CounterAction, add_counter, all_counters, delete_counter, get_counter,
+        parse_counter_action, set_counter,
+    };
+    use crate::database::mock_database;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Tests parsing the counter action from the command arguments
+    #[test]
+    fn test_parse_counter_action() {
+        let cases = [
+            (args(&[]), CounterAction::View),
+            (args(&["+"]), CounterAction::Increment(1)),
+            (args(&["+5"]), CounterAction::Increment(5)),
+            (args(&["-"]), CounterAction::Decrement(1)),
+            (args(&["-2"]), CounterAction::Decrement(2)),
+            (args(&["reset"]), CounterAction::Reset),
+            (args(&["SET", "10"]), CounterAction::Set(10)),
+            (args(&["set", "abc"]), CounterAction::View),
+            (args(&["+abc"]), CounterAction::View),
+            (args(&["+-1"]), CounterAction::View),
+            (args(&["hello"]), CounterAction::View),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(parse_counter_action(&args), expected, "args: {args:?}");
+        }
+    }
+
+    /// Tests modifying and listing counters
+    #[tokio::test]
+    async fn test_counters() {
+        let db = mock_database().await;
+
+        assert_eq!(get_counter(&db, "deaths").await.unwrap(), 0);
+        assert_eq!(add_counter(&db, "deaths", 2).await.unwrap(), 2);
+        assert_eq!(add_counter(&db, "deaths", -1).await.unwrap(), 1);
+
+        set_counter(&db, "wins", 10).await.unwrap();
+
+        let counters = all_counters(&db).await.unwrap();
+        let counters: Vec<(String, i64)> = counters
+            .into_iter()
+            .map(|counter| (counter.name, counter.value))
+            .collect();
+        assert_eq!(
+            counters,
+            vec![("deaths".to_string(), 1), ("wins".to_string(), 10)]
+        );
+
+        delete_counter(&db, "deaths").await.unwrap();
+        assert_eq!(get_counter(&db, "deaths").await.unwrap(), 0);
+    }
+}
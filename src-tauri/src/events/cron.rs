@@ -0,0 +1,326 @@
+//! Minimal parser and evaluator for standard 5 field cron expressions
+//! (minute hour day-of-month month day-of-week)
+//!
+//! Supports `*`, single values, ranges (`1-5`), lists (`1,15,30`), steps
+//! (`*/15`, `0-30/10`) and month / weekday names (`JAN`, `MON-FRI`)
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeDelta, TimeZone, Timelike};
+use std::str::FromStr;
+
+/// Maximum number of days to search ahead for the next matching
+/// run, enough to cover expressions that only match on leap days
+const MAX_SEARCH_DAYS: u64 = 366 * 8;
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Parsed cron expression, each field is stored as a bit set
+/// of the values that are allowed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    /// Whether the day of month field was restricted (not `*` or `*/step`)
+    days_of_month_restricted: bool,
+    /// Whether the day of week field was restricted (not `*` or `*/step`)
+    days_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields.as_slice() else {
+            bail!("cron expression must have 5 fields");
+        };
+
+        let minutes = parse_field(minutes, 0, 59, &[]).context("invalid minute field")?;
+        let hours = parse_field(hours, 0, 23, &[]).context("invalid hour field")?;
+        let days_of_month =
+            parse_field(days_of_month, 1, 31, &[]).context("invalid day of month field")?;
+        let months = parse_field(months, 1, 12, MONTH_NAMES).context("invalid month field")?;
+        let mut days_of_week =
+            parse_field(days_of_week, 0, 7, WEEKDAY_NAMES).context("invalid day of week field")?;
+
+        // 7 is an alias for sunday
+        if days_of_week.values & (1 << 7) != 0 {
+            days_of_week.values |= 1;
+        }
+
+        Ok(CronSchedule {
+            minutes: minutes.values,
+            hours: hours.values as u32,
+            days_of_month: days_of_month.values as u32,
+            months: months.values as u16,
+            days_of_week: (days_of_week.values & 0x7f) as u8,
+            days_of_month_restricted: days_of_month.restricted,
+            days_of_week_restricted: days_of_week.restricted,
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Finds the next time strictly after `after` that matches the
+    /// expression, evaluated in the timezone of `after`
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+
+        // Cron runs on whole minutes, start from the next whole minute
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let start_date = start.date();
+
+        for day_offset in 0..MAX_SEARCH_DAYS {
+            let date = start_date.checked_add_days(Days::new(day_offset))?;
+            if !self.matches_date(date) {
+                continue;
+            }
+
+            let (start_hour, start_minute) = if day_offset == 0 {
+                (start.hour(), start.minute())
+            } else {
+                (0, 0)
+            };
+
+            for hour in start_hour..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+
+                let first_minute = if hour == start_hour { start_minute } else { 0 };
+
+                for minute in first_minute..60 {
+                    if self.minutes & (1 << minute) == 0 {
+                        continue;
+                    }
+
+                    let time = date.and_hms_opt(hour, minute, 0)?;
+
+                    // Times that don't exist in the timezone (DST gaps) are skipped
+                    let time = timezone
+                        .from_local_datetime(&time)
+                        .earliest()
+                        .filter(|time| time > after);
+
+                    if time.is_some() {
+                        return time;
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Checks if the provided date matches the day and month fields, when both
+    /// day fields are restricted matching either field is enough (Standard cron behavior)
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+
+        if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+/// Parsed field of a cron expression
+struct CronField {
+    /// Bit set of the allowed values
+    values: u64,
+    /// Whether the field restricts the allowed values (Field does not start with `*`)
+    restricted: bool,
+}
+
+/// Parses a single cron field into a bit set of the allowed values between
+/// `min` and `max`, `names` are alternative names for values starting at `min`
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> anyhow::Result<CronField> {
+    let mut values = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().context("invalid step")?;
+                if step == 0 {
+                    bail!("step must be greater than zero");
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // A single value with a step runs from the value to the end of the range
+            (value, if step.is_some() { max } else { value })
+        };
+
+        if start > end {
+            bail!("range start {start} is after range end {end}");
+        }
+
+        let step = step.unwrap_or(1);
+        for value in (start..=end).step_by(step as usize) {
+            values |= 1 << value;
+        }
+    }
+
+    Ok(CronField {
+        values,
+        restricted: !field.starts_with('*'),
+    })
+}
+
+/// Parses a single value within a field, allowing names
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> anyhow::Result<u32> {
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(min + index as u32);
+    }
+
+    let value: u32 = value
+        .parse()
+        .with_context(|| format!("invalid value \"{value}\""))?;
+
+    if value < min || value > max {
+        bail!("value {value} is outside the range {min}-{max}");
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+
+    use super::CronSchedule;
+
+    /// Tests parsing invalid expressions
+    #[test]
+    fn test_parse_invalid() {
+        let invalid = [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "30-10 * * * *",
+            "abc * * * *",
+        ];
+
+        for expression in invalid {
+            assert!(
+                expression.parse::<CronSchedule>().is_err(),
+                "expected \"{expression}\" to be invalid"
+            );
+        }
+    }
+
+    /// Tests the next run for a fixed time of day
+    #[test]
+    fn test_next_fixed_time() {
+        let schedule: CronSchedule = "30 9 * * *".parse().unwrap();
+
+        // Later the same day
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 9, 30, 0).unwrap());
+
+        // Exactly at the time should move to the next day
+        let next = schedule.next_after(&next).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 2, 9, 30, 0).unwrap());
+    }
+
+    /// Tests steps within the minute field
+    #[test]
+    fn test_next_step() {
+        let schedule: CronSchedule = "*/15 * * * *".parse().unwrap();
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 8, 7, 30).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 8, 15, 0).unwrap());
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 23, 50, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap());
+    }
+
+    /// Tests restricting the day of the week using names
+    #[test]
+    fn test_next_weekday_names() {
+        let schedule: CronSchedule = "0 12 * * mon-fri".parse().unwrap();
+
+        // 2025-01-04 is a saturday, next weekday is monday the 6th
+        let after = Utc.with_ymd_and_hms(2025, 1, 4, 8, 0, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 6, 12, 0, 0).unwrap());
+    }
+
+    /// Tests that sunday can be specified as either 0 or 7
+    #[test]
+    fn test_sunday_alias() {
+        let zero: CronSchedule = "0 12 * * 0".parse().unwrap();
+        let seven: CronSchedule = "0 12 * * 7".parse().unwrap();
+        assert_eq!(zero, seven);
+    }
+
+    /// Tests that when both day fields are restricted either can match
+    #[test]
+    fn test_next_day_of_month_or_week() {
+        let schedule: CronSchedule = "0 0 15 * fri".parse().unwrap();
+
+        // 2025-01-03 is a friday, before the 15th
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 3, 0, 0, 0).unwrap());
+
+        // 2025-01-15 is a wednesday but matches the day of month
+        let after = Utc.with_ymd_and_hms(2025, 1, 13, 0, 0, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap());
+    }
+
+    /// Tests expressions that only match on leap days
+    #[test]
+    fn test_next_leap_day() {
+        let schedule: CronSchedule = "0 0 29 feb *".parse().unwrap();
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap());
+    }
+
+    /// Tests expressions that can never match
+    #[test]
+    fn test_next_never() {
+        let schedule: CronSchedule = "0 0 31 feb *".parse().unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert!(schedule.next_after(&after).is_none());
+    }
+}
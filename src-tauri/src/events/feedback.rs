@@ -0,0 +1,169 @@
+//! # Feedback
+//!
+//! Chat replies sent to users when a command or event is skipped (Cooldown,
+//! missing role, usage limit). Replies have their own rate limit separate from
+//! the command or event so that users cannot use them to spam chat
+
+use chrono::TimeDelta;
+use log::{debug, error};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use twitch_api::types::UserId;
+use uuid::Uuid;
+
+use super::{
+    TwitchEventUser,
+    matching::EventInputData,
+    template::{TemplateContext, event_template_args, render_template},
+};
+use crate::{database::DbPool, twitch::manager::Twitch};
+
+/// Minimum time between feedback replies to the same user for the same command or event
+const USER_FEEDBACK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Minimum time between any feedback replies
+const GLOBAL_FEEDBACK_INTERVAL: Duration = Duration::from_secs(2);
+
+static FEEDBACK_LIMITER: LazyLock<Mutex<FeedbackLimiter>> = LazyLock::new(Default::default);
+
+/// Rate limiter for feedback replies
+#[derive(Default)]
+struct FeedbackLimiter {
+    /// Last time any feedback reply was sent
+    last_sent: Option<Instant>,
+    /// Last time a reply was sent for a command or event to a specific user
+    last_sent_user: HashMap<(Uuid, UserId), Instant>,
+}
+
+impl FeedbackLimiter {
+    /// Attempts to acquire permission to send a reply for the command or event
+    /// `target_id` to the user, returns false if the reply is rate limited
+    fn try_acquire(&mut self, target_id: Uuid, user_id: &UserId, now: Instant) -> bool {
+        // Remove expired entries
+        self.last_sent_user
+            .retain(|_, sent_at| now.duration_since(*sent_at) < USER_FEEDBACK_INTERVAL);
+
+        if self
+            .last_sent
+            .is_some_and(|sent_at| now.duration_since(sent_at) < GLOBAL_FEEDBACK_INTERVAL)
+        {
+            return false;
+        }
+
+        let key = (target_id, user_id.clone());
+        if self.last_sent_user.contains_key(&key) {
+            return false;
+        }
+
+        self.last_sent = Some(now);
+        self.last_sent_user.insert(key, now);
+        true
+    }
+}
+
+/// Sends a feedback reply to the user for the command or event `target_id`, does
+/// nothing if no message is configured or the reply is rate limited.
+///
+/// The message is rendered as a template, the provided `variables` are available
+/// alongside the usual template variables (i.e `$(remaining)` for cooldowns)
+pub async fn send_feedback_message(
+    db: &DbPool,
+    twitch: &Twitch,
+    target_id: Uuid,
+    message: Option<&str>,
+    user: &TwitchEventUser,
+    input_data: &EventInputData,
+    variables: &[(&str, String)],
+) {
+    let Some(message) = message.filter(|message| !message.trim().is_empty()) else {
+        return;
+    };
+
+    if !FEEDBACK_LIMITER
+        .lock()
+        .try_acquire(target_id, &user.id, Instant::now())
+    {
+        debug!("skipping feedback message: rate limited");
+        return;
+    }
+
+    let args = event_template_args(input_data);
+    let ctx = TemplateContext {
+        db,
+        twitch,
+        user: Some(user),
+        args: &args,
+        input_data,
+        variables,
+    };
+
+    let message = render_template(message, &ctx).await;
+
+    if let Err(err) = twitch.send_chat_message_chunked(&message).await {
+        error!("failed to send feedback message: {err:?}");
+    }
+}
+
+/// Formats the remaining time of a cooldown as a whole number of seconds
+pub fn remaining_seconds(remaining: TimeDelta) -> String {
+    // Round up so that a cooldown is never reported as zero seconds
+    let seconds = (remaining.num_milliseconds() + 999) / 1000;
+    seconds.max(1).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use chrono::TimeDelta;
+    use twitch_api::types::UserId;
+    use uuid::Uuid;
+
+    use super::{
+        FeedbackLimiter, GLOBAL_FEEDBACK_INTERVAL, USER_FEEDBACK_INTERVAL, remaining_seconds,
+    };
+
+    /// Tests that replies to the same user for the same target are limited
+    #[test]
+    fn test_limit_same_user() {
+        let mut limiter = FeedbackLimiter::default();
+        let target_id = Uuid::new_v4();
+        let user_id = UserId::from_static("1");
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(target_id, &user_id, now));
+
+        // Limited until the user interval has passed
+        let later = now + GLOBAL_FEEDBACK_INTERVAL;
+        assert!(!limiter.try_acquire(target_id, &user_id, later));
+
+        let later = now + USER_FEEDBACK_INTERVAL;
+        assert!(limiter.try_acquire(target_id, &user_id, later));
+    }
+
+    /// Tests that replies to different users are limited by the global interval
+    #[test]
+    fn test_limit_global() {
+        let mut limiter = FeedbackLimiter::default();
+        let target_id = Uuid::new_v4();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(target_id, &UserId::from_static("1"), now));
+        assert!(!limiter.try_acquire(target_id, &UserId::from_static("2"), now));
+
+        let later = now + GLOBAL_FEEDBACK_INTERVAL + Duration::from_millis(1);
+        assert!(limiter.try_acquire(target_id, &UserId::from_static("2"), later));
+    }
+
+    /// Tests formatting the remaining cooldown time
+    #[test]
+    fn test_remaining_seconds() {
+        assert_eq!(remaining_seconds(TimeDelta::milliseconds(0)), "1");
+        assert_eq!(remaining_seconds(TimeDelta::milliseconds(1500)), "2");
+        assert_eq!(remaining_seconds(TimeDelta::seconds(30)), "30");
+    }
+}
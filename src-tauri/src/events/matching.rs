@@ -1,9 +1,18 @@
-use std::str::FromStr;
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
 
 use anyhow::{Context, bail};
-use chrono::Utc;
+use chrono::{DateTime, Days, Utc};
+use futures::future::join_all;
 use log::{debug, error};
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use tokio::join;
 use twitch_api::{
     eventsub::channel::chat::Fragment,
@@ -15,21 +24,33 @@ use crate::{
     database::{
         DbPool,
         entity::{
+            app_data::AppDataModel,
             chat_history::{ChatHistoryModel, CreateChatHistory},
-            commands::CommandModel,
+            command_alias::{CommandAliasModel, CommandWithAliases},
+            commands::{CommandMatchMode, CommandModel},
             event_execution::EventExecutionModel,
-            events::{EventModel, EventTrigger, EventTriggerType},
+            events::{
+                ChatPatternMatchType, EventCondition, EventConditionCheck, EventModel,
+                EventTrigger, EventTriggerType, FirstMessageScope, HypeTrainPhase, PollPhase,
+                PredictionPhase,
+            },
+            viewers::ViewerModel,
         },
     },
     events::{
         TwitchEventAdBreakBegin, TwitchEventChatMsg, TwitchEventCheerBits, TwitchEventFollow,
-        TwitchEventGiftSub, TwitchEventRaid, TwitchEventReSub, TwitchEventRedeem,
-        TwitchEventShoutoutReceive, TwitchEventSub, TwitchEventUser,
+        TwitchEventGiftSub, TwitchEventHypeTrain, TwitchEventPoll, TwitchEventPollChoice,
+        TwitchEventPrediction, TwitchEventPredictionOutcome, TwitchEventRaid, TwitchEventReSub,
+        TwitchEventRedeem, TwitchEventShoutoutReceive, TwitchEventSub, TwitchEventUser,
     },
     twitch::manager::Twitch,
 };
 
-use super::TimerCompleted;
+use super::{
+    TimerCompleted,
+    arguments::{CommandArgumentError, CommandArguments, parse_command_arguments},
+    redemption::RedemptionGuard,
+};
 
 /// Data for matched events to trigger
 #[derive(Default)]
@@ -42,6 +63,23 @@ pub struct EventMatchingData {
 
     /// Additional data attached to the event
     pub event_data: EventData,
+
+    /// Named captures from chat pattern triggers for each matched event
+    pub captures: HashMap<Uuid, HashMap<String, String>>,
+
+    /// Suggested command when a chat message was close to a command
+    pub command_suggestion: Option<CommandSuggestion>,
+
+    /// Redemption shared by the matched events that update their redemption
+    pub redemption: Option<RedemptionGuard>,
+}
+
+/// Suggestion for a command that was likely mistyped
+pub struct CommandSuggestion {
+    /// ID of the suggested command
+    pub command_id: Uuid,
+    /// Trigger of the suggested command
+    pub trigger: String,
 }
 
 /// Command to trigger with some additional context
@@ -53,6 +91,9 @@ pub struct CommandWithContext {
 
     /// Args with the first argument command/alias removed
     pub args: Vec<String>,
+
+    /// Args parsed using the command arguments schema
+    pub arguments: Result<CommandArguments, CommandArgumentError>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +106,21 @@ pub struct EventData {
     pub input_data: EventInputData,
 }
 
+impl EventData {
+    /// Creates a copy of the event data with the provided chat pattern captures
+    pub fn with_captures(&self, captures: HashMap<String, String>) -> EventData {
+        let mut event_data = self.clone();
+        if let EventInputData::Chat {
+            captures: event_captures,
+            ..
+        } = &mut event_data.input_data
+        {
+            *event_captures = captures;
+        }
+        event_data
+    }
+}
+
 /// Additional event-specific input data
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(untagged, rename_all_fields = "camelCase")]
@@ -141,6 +197,10 @@ pub enum EventInputData {
 
         /// Optional amount of bits cheered (If user cheered bits)
         cheer: Option<usize>,
+
+        /// Named captures from the matched chat pattern
+        #[serde(default)]
+        captures: HashMap<String, String>,
     },
 
     /// Raid specific data
@@ -161,6 +221,52 @@ pub enum EventInputData {
         viewer_count: i64,
     },
 
+    /// Hype train specific data
+    HypeTrain {
+        /// Phase of the hype train
+        phase: HypeTrainPhase,
+        /// Current level of the hype train
+        level: i64,
+        /// Total points contributed to the hype train
+        total: i64,
+        /// Points contributed towards the current level
+        progress: i64,
+        /// Points required to reach the next level
+        goal: i64,
+    },
+
+    /// Poll specific data
+    Poll {
+        /// ID of the poll
+        poll_id: String,
+        /// Phase of the poll
+        phase: PollPhase,
+        /// Question displayed for the poll
+        title: String,
+        /// Choices for the poll and their votes
+        choices: Vec<TwitchEventPollChoice>,
+        /// Title of the choice with the most votes (Current leader until the poll ends)
+        winning_choice: Option<String>,
+        /// Votes for the winning choice
+        winning_votes: i64,
+    },
+
+    /// Prediction specific data
+    Prediction {
+        /// ID of the prediction
+        prediction_id: String,
+        /// Phase of the prediction
+        phase: PredictionPhase,
+        /// Question displayed for the prediction
+        title: String,
+        /// Outcomes for the prediction and their predictions
+        outcomes: Vec<TwitchEventPredictionOutcome>,
+        /// Title of the winning outcome (Only present once the prediction has been resolved)
+        winning_outcome: Option<String>,
+        /// Channel points spent on the winning outcome
+        winning_points: i64,
+    },
+
     /// No additional input data
     #[default]
     #[serde(deserialize_with = "deserialize_ignore_any")]
@@ -173,6 +279,133 @@ pub fn deserialize_ignore_any<'de, D: Deserializer<'de>, T: Default>(
     serde::de::IgnoredAny::deserialize(deserializer).map(|_| T::default())
 }
 
+/// Event input data that conditions are checked against, the input data is
+/// only converted to JSON once it is needed and is shared between events
+pub struct ConditionInput<'a> {
+    input_data: &'a EventInputData,
+    value: OnceCell<Option<Value>>,
+}
+
+impl<'a> ConditionInput<'a> {
+    pub fn new(input_data: &'a EventInputData) -> Self {
+        Self {
+            input_data,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Gets the JSON form of the input data
+    fn value(&self) -> Option<&Value> {
+        self.value
+            .get_or_init(|| match serde_json::to_value(self.input_data) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    error!("failed to serialize event input data: {err:?}");
+                    None
+                }
+            })
+            .as_ref()
+    }
+}
+
+/// Checks whether all the provided conditions are met by the event input data
+pub fn is_event_conditions_met(conditions: &[EventCondition], input: &ConditionInput<'_>) -> bool {
+    if conditions.is_empty() {
+        return true;
+    }
+
+    let Some(input_data) = input.value() else {
+        return false;
+    };
+
+    conditions
+        .iter()
+        .all(|condition| is_event_condition_met(condition, input_data))
+}
+
+fn is_event_condition_met(condition: &EventCondition, input_data: &Value) -> bool {
+    // Conditions on fields not present in the input data are never met
+    let value = match condition_field(input_data, &condition.field) {
+        Some(value) => value,
+        None => return false,
+    };
+
+    match &condition.check {
+        EventConditionCheck::Equals { value: expected } => {
+            is_condition_value_equal(value, expected)
+        }
+        EventConditionCheck::NotEquals { value: expected } => {
+            !is_condition_value_equal(value, expected)
+        }
+        EventConditionCheck::GreaterThan { value: expected } => {
+            condition_number(value).is_some_and(|value| value > *expected)
+        }
+        EventConditionCheck::LessThan { value: expected } => {
+            condition_number(value).is_some_and(|value| value < *expected)
+        }
+        EventConditionCheck::Between { range } => {
+            condition_number(value).is_some_and(|value| value >= range.min && value <= range.max)
+        }
+        EventConditionCheck::Contains { value: expected } => condition_text(value)
+            .is_some_and(|value| value.to_lowercase().contains(&expected.to_lowercase())),
+        EventConditionCheck::Matches { pattern } => {
+            // Condition patterns share the compiled chat pattern cache
+            let Some(compiled) = cached_chat_pattern(pattern, ChatPatternMatchType::Regex, true)
+            else {
+                return false;
+            };
+
+            let CompiledChatPattern::Regex { regex, .. } = &*compiled else {
+                return false;
+            };
+
+            condition_text(value).is_some_and(|value| regex.is_match(&value))
+        }
+    }
+}
+
+/// Gets the value of a condition `field` from the input data, nested values
+/// are accessed using a dot separated path (i.e "choices.0.votes")
+fn condition_field<'a>(input_data: &'a Value, field: &str) -> Option<&'a Value> {
+    if let Some(value) = input_data.get(field) {
+        return Some(value);
+    }
+
+    field
+        .split('.')
+        .try_fold(input_data, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// Compares two condition values, numeric values are compared by value so that
+/// string numbers such as subscription tiers ("3000") can be compared against numbers
+fn is_condition_value_equal(value: &Value, expected: &Value) -> bool {
+    match (condition_number(value), condition_number(expected)) {
+        (Some(value), Some(expected)) => value == expected,
+        _ => value == expected,
+    }
+}
+
+fn condition_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(value) => value.as_f64(),
+        Value::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn condition_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
 pub async fn match_redeem_event(
     db: &DbPool,
     event: TwitchEventRedeem,
@@ -195,7 +428,7 @@ pub async fn match_redeem_event(
         .filter(|event| {
             matches!(
                 &event.config.trigger,
-                EventTrigger::Redeem { reward_id } if event_reward_id.eq(reward_id)
+                EventTrigger::Redeem { reward_id, .. } if event_reward_id.eq(reward_id)
             )
         })
         .collect();
@@ -219,6 +452,9 @@ pub async fn match_redeem_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -268,6 +504,9 @@ pub async fn match_cheer_bits_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -297,6 +536,9 @@ pub async fn match_follow_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -329,6 +571,9 @@ pub async fn match_subscription_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -369,6 +614,9 @@ pub async fn match_gifted_subscription_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -404,6 +652,9 @@ pub async fn match_re_subscription_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
@@ -425,82 +676,124 @@ async fn store_chat_event(db: &DbPool, event: &TwitchEventChatMsg) -> anyhow::Re
     Ok(())
 }
 
+/// Loads first message and returning viewer events that match the
+/// previous state of the viewer that sent a message
+async fn match_viewer_events(
+    db: &DbPool,
+    previous: Option<ViewerModel>,
+    session_started_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Vec<EventModel> {
+    let (first_message_events, returning_events) = join!(
+        EventModel::get_by_trigger_type(db, EventTriggerType::FirstMessage),
+        EventModel::get_by_trigger_type(db, EventTriggerType::ReturningViewer),
+    );
+
+    let mut events: Vec<EventModel> = match first_message_events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    let returning_events = match returning_events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    events.extend(returning_events);
+
+    events
+        .into_iter()
+        .filter(|event| match (&event.config.trigger, &previous) {
+            // Brand new viewers match any first message scope
+            (EventTrigger::FirstMessage { .. }, None) => true,
+            (EventTrigger::FirstMessage { scope }, Some(previous)) => match scope {
+                FirstMessageScope::Ever => false,
+                FirstMessageScope::Session => {
+                    session_started_at.is_some_and(|started_at| previous.last_seen_at < started_at)
+                }
+            },
+            (EventTrigger::ReturningViewer { days }, Some(previous)) => previous
+                .last_seen_at
+                .checked_add_days(Days::new(*days as u64))
+                .is_some_and(|returning_at| returning_at <= now),
+            _ => false,
+        })
+        .collect()
+}
+
 pub async fn match_chat_event(
     db: &DbPool,
     event: TwitchEventChatMsg,
+    session_started_at: Option<DateTime<Utc>>,
 ) -> anyhow::Result<EventMatchingData> {
     if let Err(err) = store_chat_event(db, &event).await {
         error!("failed to log chat message history: {err}");
     }
 
-    let message = event.message.text.clone();
-    let mut args: Vec<String> = message
-        .split_whitespace()
-        .map(|value| value.to_string())
-        .collect();
-
-    let first_arg = if args.is_empty() {
-        None
-    } else {
-        Some(args.remove(0))
+    let now = Utc::now();
+    let viewer_events = match ViewerModel::record_message(db, event.user_id.as_str(), now).await {
+        Ok(previous) => match_viewer_events(db, previous, session_started_at, now).await,
+        Err(err) => {
+            error!("failed to store chat viewer: {err}");
+            Default::default()
+        }
     };
 
-    let (events, commands) = if let Some(first_arg) = first_arg {
-        // Get the command argument from the first argument
-        let command_arg = first_arg.trim().to_lowercase();
+    let message = event.message.text.clone();
 
-        let (events, commands) = join!(
-            // Load all command event triggers
-            EventModel::get_by_trigger_type(db, EventTriggerType::Command),
-            // Load all commands
-            CommandModel::get_by_command(db, &command_arg),
-        );
+    let pattern_events = EventModel::get_by_trigger_type(db, EventTriggerType::ChatPattern).await;
+    let pattern_events = match pattern_events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
 
-        let events = match events {
-            Ok(value) => value,
-            Err(err) => {
-                error!("failed to load events: {:?}", err);
-                Default::default()
-            }
-        };
+    let mut captures = HashMap::new();
 
-        let commands = match commands {
-            Ok(value) => value,
-            Err(err) => {
-                error!("failed to load commands: {:?}", err);
-                Default::default()
+    // Filter events for matching chat patterns
+    let pattern_events: Vec<EventModel> = pattern_events
+        .into_iter()
+        .filter(|pattern_event| {
+            let EventTrigger::ChatPattern {
+                pattern,
+                match_type,
+                case_sensitive,
+            } = &pattern_event.config.trigger
+            else {
+                return false;
+            };
+
+            match match_chat_pattern(
+                pattern,
+                *match_type,
+                *case_sensitive,
+                &message,
+                &event.message.fragments,
+            ) {
+                Some(event_captures) => {
+                    // Only patterns with capture groups provide captures
+                    if !event_captures.is_empty() {
+                        captures.insert(pattern_event.id, event_captures);
+                    }
+                    true
+                }
+                None => false,
             }
-        };
-
-        // Filter events for matching command messages
-        let events = events
-            .into_iter()
-            .filter(|event| matches!(&event.config.trigger, EventTrigger::Command { message } if message.trim().to_lowercase().eq(&command_arg)))
-            .collect();
-
-        // Provide additional context to commands
-        let commands = commands
-            .into_iter()
-            .map(|command| {
-                // Strip prefix and trim any leading space
-                let message = message
-                    .strip_prefix(&first_arg)
-                    .unwrap_or(&message)
-                    .trim_start()
-                    .to_string();
+        })
+        .collect();
 
-                CommandWithContext {
-                    command,
-                    message,
-                    args: args.clone(),
-                }
-            })
-            .collect();
+    let (mut events, commands, command_suggestion) = match_chat_commands(db, &message).await;
 
-        (events, commands)
-    } else {
-        (Default::default(), Default::default())
-    };
+    events.extend(pattern_events);
+    events.extend(viewer_events);
 
     let event_data = EventData {
         input_data: EventInputData::Chat {
@@ -508,6 +801,7 @@ pub async fn match_chat_event(
             message: event.message.text,
             fragments: event.message.fragments,
             cheer: event.cheer.map(|cheer| cheer.bits),
+            captures: Default::default(),
         },
         user: Some(TwitchEventUser {
             id: event.user_id,
@@ -520,57 +814,41 @@ pub async fn match_chat_event(
         events,
         commands,
         event_data,
+        captures,
+        command_suggestion,
+        redemption: Default::default(),
     })
 }
 
-pub async fn match_raid_event(
+/// Matches the commands and command events triggered by a chat message along
+/// with a suggestion for the closest command if nothing was matched
+async fn match_chat_commands(
     db: &DbPool,
-    event: TwitchEventRaid,
-) -> anyhow::Result<EventMatchingData> {
-    let events = EventModel::get_by_trigger_type(db, EventTriggerType::Raid).await;
+    message: &str,
+) -> (
+    Vec<EventModel>,
+    Vec<CommandWithContext>,
+    Option<CommandSuggestion>,
+) {
+    let words: Vec<&str> = message.split_whitespace().collect();
+    if words.is_empty() {
+        return Default::default();
+    }
 
-    let events = match events {
+    let (main_config, events) = join!(
+        AppDataModel::get_main_config(db),
+        // Load all command event triggers
+        EventModel::get_by_trigger_type(db, EventTriggerType::Command),
+    );
+
+    let main_config = match main_config {
         Ok(value) => value,
         Err(err) => {
-            error!("failed to load events: {:?}", err);
+            error!("failed to load main config: {:?}", err);
             Default::default()
         }
     };
 
-    let raiders = event.viewers;
-
-    // Filter events for the matching viewer minimum amount
-    let events = events
-       .into_iter()
-       .filter(|event| {
-           matches!(&event.config.trigger, EventTrigger::Raid { min_raiders } if raiders >= *min_raiders as i64)
-       })
-       .collect();
-
-    let event_data = EventData {
-        input_data: EventInputData::Raid {
-            viewers: event.viewers,
-        },
-        user: Some(TwitchEventUser {
-            id: event.user_id,
-            name: event.user_name,
-            display_name: event.user_display_name,
-        }),
-    };
-
-    Ok(EventMatchingData {
-        events,
-        commands: Default::default(),
-        event_data,
-    })
-}
-
-pub async fn match_ad_break_event(
-    db: &DbPool,
-    event: TwitchEventAdBreakBegin,
-) -> anyhow::Result<EventMatchingData> {
-    let events = EventModel::get_by_trigger_type(db, EventTriggerType::AdBreakBegin).await;
-
     let events = match events {
         Ok(value) => value,
         Err(err) => {
@@ -579,81 +857,545 @@ pub async fn match_ad_break_event(
         }
     };
 
-    let event_data = EventData {
-        input_data: EventInputData::AdBreakBegin {
-            duration_seconds: event.duration_seconds,
-        },
-        user: None,
+    // All enabled commands, only loaded when a word has no exact match
+    let mut all_commands: Option<Vec<CommandWithAliases>> = None;
+
+    let prefixes = &main_config.command_prefixes;
+
+    // Only the first word can trigger commands unless mid message commands are enabled
+    let candidates = if main_config.mid_message_commands {
+        words.len()
+    } else {
+        1
     };
 
-    Ok(EventMatchingData {
-        events,
-        commands: Default::default(),
-        event_data,
-    })
-}
+    for (index, word) in words.iter().take(candidates).enumerate() {
+        // Words after the first must use a prefix to be treated as a command
+        if index > 0 && strip_command_prefix(word, prefixes).is_none() {
+            continue;
+        }
 
-pub async fn match_timer_complete_event(
-    db: &DbPool,
-    twitch: &Twitch,
-    event: TimerCompleted,
-) -> anyhow::Result<EventMatchingData> {
-    let event = EventModel::get_by_id(db, event.event_id)
-        .await
-        .context("failed to get event to trigger")?
-        .context("requested timer event does not exist")?;
+        // Filter events for matching command messages
+        let matched_events: Vec<EventModel> = events
+            .iter()
+            .filter(|event| match &event.config.trigger {
+                EventTrigger::Command { message } => is_command_trigger_match(
+                    message,
+                    word,
+                    prefixes,
+                    CommandMatchMode::CaseInsensitive,
+                ),
+                _ => false,
+            })
+            .cloned()
+            .collect();
 
-    // Get minimum amount of chat messages required
-    let min_chat_messages = match &event.config.trigger {
-        EventTrigger::Timer {
-            min_chat_messages, ..
-        } => *min_chat_messages,
-        _ => {
-            bail!("attempted to execute timer event that was not a timer event");
+        let mut matched_commands = match_exact_commands(db, word, prefixes).await;
+
+        // Fallback to comparing against every command (Fuzzy matching, etc)
+        if matched_commands.is_empty() {
+            let commands = match &all_commands {
+                Some(commands) => commands,
+                None => all_commands.insert(load_enabled_commands(db).await),
+            };
+
+            matched_commands = commands
+                .iter()
+                .filter(|command| {
+                    let match_mode = command.command.config.match_mode;
+                    std::iter::once(&command.command.command)
+                        .chain(command.aliases.iter())
+                        .any(|trigger| {
+                            is_command_trigger_match(trigger, word, prefixes, match_mode)
+                        })
+                })
+                .map(|command| command.command.clone())
+                .collect();
         }
-    };
 
-    let user_id = twitch.get_user_id().await;
+        if matched_events.is_empty() && matched_commands.is_empty() {
+            continue;
+        }
 
-    // Ensure minimum chat messages has been reached
-    if min_chat_messages > 0 {
-        let last_execution = EventExecutionModel::last(db, event.id, 0)
-            .await
-            .context("failed to get last execution")?;
+        // Message following the command and its individual arguments
+        let command_message = message_after_word(message, index).to_string();
+        let args: Vec<String> = words[index + 1..]
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
 
-        if let Some(last_execution) = last_execution {
-            let message_count =
-                ChatHistoryModel::count_since(db, last_execution.created_at, user_id).await?;
+        // Provide additional context to commands
+        let matched_commands = matched_commands
+            .into_iter()
+            .map(|command| {
+                let arguments = parse_command_arguments(&command.config.arguments, &args);
 
-            if message_count < min_chat_messages {
-                debug!("skipping timer execution, not enough chat messages since last execution");
-                return Ok(Default::default());
-            }
-        }
+                CommandWithContext {
+                    command,
+                    message: command_message.clone(),
+                    args: args.clone(),
+                    arguments,
+                }
+            })
+            .collect();
+
+        return (matched_events, matched_commands, None);
     }
 
-    Ok(EventMatchingData {
-        events: vec![event],
-        commands: Default::default(),
-        event_data: Default::default(),
-    })
-}
+    let suggestion = if main_config.command_suggestions {
+        let commands = match &all_commands {
+            Some(commands) => commands,
+            None => all_commands.insert(load_enabled_commands(db).await),
+        };
 
-pub async fn match_shoutout_receive_event(
-    db: &DbPool,
-    event: TwitchEventShoutoutReceive,
-) -> anyhow::Result<EventMatchingData> {
-    let events = EventModel::get_by_trigger_type(db, EventTriggerType::ShoutoutReceive).await;
+        suggest_command(words[0], prefixes, commands)
+    } else {
+        None
+    };
 
-    let events = match events {
+    (Vec::new(), Vec::new(), suggestion)
+}
+
+/// Loads all enabled commands along with their aliases
+async fn load_enabled_commands(db: &DbPool) -> Vec<CommandWithAliases> {
+    match CommandAliasModel::get_enabled_with_aliases(db).await {
         Ok(value) => value,
         Err(err) => {
-            error!("failed to load events: {:?}", err);
+            error!("failed to load commands: {:?}", err);
             Default::default()
         }
-    };
+    }
+}
 
-    let viewers = event.viewer_count;
+/// Finds the commands with a trigger or alias exactly matching a chat `word`,
+/// a prefixed word is also looked up using each of the other `prefixes`
+///
+/// Lookups using the lowercase word only match commands that ignore case
+async fn match_exact_commands(db: &DbPool, word: &str, prefixes: &[String]) -> Vec<CommandModel> {
+    let mut triggers = vec![word.to_string()];
+    if let Some(name) = strip_command_prefix(word, prefixes) {
+        triggers.extend(
+            prefixes
+                .iter()
+                .filter(|prefix| !prefix.is_empty())
+                .map(|prefix| format!("{prefix}{name}")),
+        );
+    }
+
+    let mut lookups: Vec<(String, bool)> = Vec::with_capacity(triggers.len() * 2);
+    for trigger in triggers {
+        let lowercase = trigger.to_lowercase();
+        if !lookups.iter().any(|(value, _)| value == &trigger) {
+            lookups.push((trigger.clone(), true));
+        }
+        if lowercase != trigger && !lookups.iter().any(|(value, _)| value == &lowercase) {
+            lookups.push((lowercase, false));
+        }
+    }
+
+    let results = join_all(lookups.iter().map(|(trigger, exact_case)| async move {
+        match CommandModel::get_by_command(db, trigger).await {
+            Ok(commands) => commands
+                .into_iter()
+                .map(|command| (command, *exact_case))
+                .collect(),
+            Err(err) => {
+                error!("failed to load commands: {:?}", err);
+                Vec::new()
+            }
+        }
+    }))
+    .await;
+
+    let mut matched: Vec<CommandModel> = Vec::new();
+    for (command, exact_case) in results.into_iter().flatten() {
+        // Case sensitive commands must match the word as it was written
+        if !exact_case && command.config.match_mode == CommandMatchMode::CaseSensitive {
+            continue;
+        }
+
+        if !matched.iter().any(|existing| existing.id == command.id) {
+            matched.push(command);
+        }
+    }
+
+    matched
+}
+
+/// Strips the first of the command `prefixes` that `value` starts with
+pub fn strip_command_prefix<'a>(value: &'a str, prefixes: &[String]) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .filter(|prefix| !prefix.is_empty())
+        .find_map(|prefix| value.strip_prefix(prefix.as_str()))
+}
+
+/// Checks whether a chat `word` triggers the command `trigger`.
+///
+/// Triggers starting with a configured prefix can be triggered using any of
+/// the prefixes, other triggers must be written in full
+pub fn is_command_trigger_match(
+    trigger: &str,
+    word: &str,
+    prefixes: &[String],
+    match_mode: CommandMatchMode,
+) -> bool {
+    let trigger = trigger.trim();
+    let (trigger, word) = match strip_command_prefix(trigger, prefixes) {
+        Some(trigger) => match strip_command_prefix(word, prefixes) {
+            Some(word) => (trigger, word),
+            None => return false,
+        },
+        None => (trigger, word),
+    };
+
+    if trigger.is_empty() {
+        return false;
+    }
+
+    match match_mode {
+        CommandMatchMode::CaseSensitive => trigger == word,
+        CommandMatchMode::CaseInsensitive => trigger.to_lowercase() == word.to_lowercase(),
+        CommandMatchMode::Fuzzy => {
+            let trigger = trigger.to_lowercase();
+            strsim::damerau_levenshtein(&trigger, &word.to_lowercase())
+                <= max_typo_distance(&trigger)
+        }
+    }
+}
+
+/// Maximum number of edits allowed for a word to be considered a typo of
+/// `trigger`, shorter triggers allow fewer edits
+fn max_typo_distance(trigger: &str) -> usize {
+    match trigger.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Finds the closest command to a prefixed chat `word` that did not match any command
+fn suggest_command(
+    word: &str,
+    prefixes: &[String],
+    commands: &[CommandWithAliases],
+) -> Option<CommandSuggestion> {
+    let word = strip_command_prefix(word, prefixes)?.to_lowercase();
+
+    commands
+        .iter()
+        .flat_map(|command| {
+            std::iter::once(&command.command.command)
+                .chain(command.aliases.iter())
+                .map(move |trigger| (command.command.id, trigger))
+        })
+        .filter_map(|(command_id, trigger)| {
+            let name = strip_command_prefix(trigger.trim(), prefixes)?.to_lowercase();
+            let distance = strsim::damerau_levenshtein(&name, &word);
+            (distance > 0 && distance <= max_typo_distance(&name))
+                .then(|| (distance, command_id, trigger))
+        })
+        .min_by_key(|(distance, _, _)| *distance)
+        .map(|(_, command_id, trigger)| CommandSuggestion {
+            command_id,
+            trigger: trigger.trim().to_string(),
+        })
+}
+
+/// Gets the portion of `message` after the word at `index` (Split by whitespace)
+fn message_after_word(message: &str, index: usize) -> &str {
+    let mut rest = message;
+    for _ in 0..=index {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = &rest[end..];
+    }
+    rest.trim_start()
+}
+
+/// Maximum number of compiled chat patterns kept in the cache, the
+/// cache is cleared once full to drop patterns that are no longer used
+const MAX_CACHED_CHAT_PATTERNS: usize = 256;
+
+/// Cache key for a compiled chat pattern
+type ChatPatternKey = (String, ChatPatternMatchType, bool);
+
+/// Cache of compiled chat patterns, patterns that failed to compile are
+/// stored as None so the error is only logged once
+static CHAT_PATTERN_CACHE: LazyLock<
+    Mutex<HashMap<ChatPatternKey, Option<Arc<CompiledChatPattern>>>>,
+> = LazyLock::new(Default::default);
+
+/// Chat pattern compiled ahead of matching
+#[derive(Debug)]
+pub enum CompiledChatPattern {
+    Glob(glob::Pattern),
+    Regex {
+        regex: Regex,
+        /// Whether the regex has named capture groups
+        has_captures: bool,
+    },
+}
+
+/// Compiles a glob or regex chat pattern, other match types
+/// are matched directly and produce None
+pub fn compile_chat_pattern(
+    pattern: &str,
+    match_type: ChatPatternMatchType,
+    case_sensitive: bool,
+) -> anyhow::Result<Option<CompiledChatPattern>> {
+    match match_type {
+        ChatPatternMatchType::Glob => {
+            let glob = glob::Pattern::new(pattern).context("invalid glob pattern")?;
+            Ok(Some(CompiledChatPattern::Glob(glob)))
+        }
+        ChatPatternMatchType::Regex => {
+            let regex = RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .context("invalid regex pattern")?;
+            let has_captures = regex.capture_names().flatten().next().is_some();
+            Ok(Some(CompiledChatPattern::Regex {
+                regex,
+                has_captures,
+            }))
+        }
+        ChatPatternMatchType::Substring | ChatPatternMatchType::Emote => Ok(None),
+    }
+}
+
+/// Gets the compiled form of a glob or regex chat pattern from the
+/// cache, compiling the pattern if it is not cached
+fn cached_chat_pattern(
+    pattern: &str,
+    match_type: ChatPatternMatchType,
+    case_sensitive: bool,
+) -> Option<Arc<CompiledChatPattern>> {
+    let key: ChatPatternKey = (pattern.to_string(), match_type, case_sensitive);
+    let cache = &mut *CHAT_PATTERN_CACHE.lock();
+
+    if let Some(compiled) = cache.get(&key) {
+        return compiled.clone();
+    }
+
+    let compiled = match compile_chat_pattern(pattern, match_type, case_sensitive) {
+        Ok(value) => value.map(Arc::new),
+        Err(err) => {
+            error!("invalid chat pattern: {err:?}");
+            None
+        }
+    };
+
+    if cache.len() >= MAX_CACHED_CHAT_PATTERNS {
+        cache.clear();
+    }
+
+    cache.insert(key, compiled.clone());
+    compiled
+}
+
+/// Attempts to match a chat pattern against a chat message, returning the
+/// named captures from the pattern when the message matches
+pub fn match_chat_pattern(
+    pattern: &str,
+    match_type: ChatPatternMatchType,
+    case_sensitive: bool,
+    message: &str,
+    fragments: &[Fragment],
+) -> Option<HashMap<String, String>> {
+    match match_type {
+        ChatPatternMatchType::Substring => {
+            let matched = if case_sensitive {
+                message.contains(pattern)
+            } else {
+                message.to_lowercase().contains(&pattern.to_lowercase())
+            };
+
+            matched.then(HashMap::new)
+        }
+        ChatPatternMatchType::Glob | ChatPatternMatchType::Regex => {
+            match &*cached_chat_pattern(pattern, match_type, case_sensitive)? {
+                CompiledChatPattern::Glob(glob) => {
+                    let options = glob::MatchOptions {
+                        case_sensitive,
+                        require_literal_separator: false,
+                        require_literal_leading_dot: false,
+                    };
+
+                    glob.matches_with(message, options).then(HashMap::new)
+                }
+
+                // Patterns without captures only need to check for a match
+                CompiledChatPattern::Regex {
+                    regex,
+                    has_captures: false,
+                } => regex.is_match(message).then(HashMap::new),
+
+                CompiledChatPattern::Regex { regex, .. } => {
+                    let captures = regex.captures(message)?;
+
+                    Some(
+                        regex
+                            .capture_names()
+                            .flatten()
+                            .filter_map(|name| {
+                                let value = captures.name(name)?;
+                                Some((name.to_string(), value.as_str().to_string()))
+                            })
+                            .collect(),
+                    )
+                }
+            }
+        }
+        ChatPatternMatchType::Emote => fragments
+            .iter()
+            .any(|fragment| {
+                matches!(fragment, Fragment::Emote { emote, .. } if emote.id.as_str() == pattern)
+            })
+            .then(HashMap::new),
+    }
+}
+
+pub async fn match_raid_event(
+    db: &DbPool,
+    event: TwitchEventRaid,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::Raid).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    let raiders = event.viewers;
+
+    // Filter events for the matching viewer minimum amount
+    let events = events
+       .into_iter()
+       .filter(|event| {
+           matches!(&event.config.trigger, EventTrigger::Raid { min_raiders } if raiders >= *min_raiders as i64)
+       })
+       .collect();
+
+    let event_data = EventData {
+        input_data: EventInputData::Raid {
+            viewers: event.viewers,
+        },
+        user: Some(TwitchEventUser {
+            id: event.user_id,
+            name: event.user_name,
+            display_name: event.user_display_name,
+        }),
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_ad_break_event(
+    db: &DbPool,
+    event: TwitchEventAdBreakBegin,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::AdBreakBegin).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    let event_data = EventData {
+        input_data: EventInputData::AdBreakBegin {
+            duration_seconds: event.duration_seconds,
+        },
+        user: None,
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_timer_complete_event(
+    db: &DbPool,
+    twitch: &Twitch,
+    event: TimerCompleted,
+) -> anyhow::Result<EventMatchingData> {
+    let event = EventModel::get_by_id(db, event.event_id)
+        .await
+        .context("failed to get event to trigger")?
+        .context("requested timer event does not exist")?;
+
+    // Get minimum amount of chat messages required
+    let min_chat_messages = match &event.config.trigger {
+        EventTrigger::Timer {
+            min_chat_messages, ..
+        } => *min_chat_messages,
+        _ => {
+            bail!("attempted to execute timer event that was not a timer event");
+        }
+    };
+
+    let user_id = twitch.get_user_id().await;
+
+    // Ensure minimum chat messages has been reached
+    if min_chat_messages > 0 {
+        let last_execution = EventExecutionModel::last(db, event.id, 0)
+            .await
+            .context("failed to get last execution")?;
+
+        if let Some(last_execution) = last_execution {
+            let message_count =
+                ChatHistoryModel::count_since(db, last_execution.created_at, user_id).await?;
+
+            if message_count < min_chat_messages {
+                debug!("skipping timer execution, not enough chat messages since last execution");
+                return Ok(Default::default());
+            }
+        }
+    }
+
+    Ok(EventMatchingData {
+        events: vec![event],
+        commands: Default::default(),
+        event_data: Default::default(),
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_shoutout_receive_event(
+    db: &DbPool,
+    event: TwitchEventShoutoutReceive,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::ShoutoutReceive).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    let viewers = event.viewer_count;
 
     // Filter events for the matching viewer minimum amount
     let events = events
@@ -678,40 +1420,240 @@ pub async fn match_shoutout_receive_event(
         events,
         commands: Default::default(),
         event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_hype_train_event(
+    db: &DbPool,
+    phase: HypeTrainPhase,
+    event: TwitchEventHypeTrain,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::HypeTrain).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    let level = event.level;
+
+    // Filter events for the matching phase and minimum level
+    let events = events
+        .into_iter()
+        .filter(|event| {
+            matches!(
+                &event.config.trigger,
+                EventTrigger::HypeTrain { phase: trigger_phase, min_level }
+                    if *trigger_phase == phase && level >= *min_level as i64
+            )
+        })
+        .collect();
+
+    let event_data = EventData {
+        input_data: EventInputData::HypeTrain {
+            phase,
+            level: event.level,
+            total: event.total,
+            progress: event.progress,
+            goal: event.goal,
+        },
+        user: None,
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_stream_status_event(
+    db: &DbPool,
+    trigger_type: EventTriggerType,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, trigger_type).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data: Default::default(),
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_poll_event(
+    db: &DbPool,
+    phase: PollPhase,
+    event: TwitchEventPoll,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::Poll).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    // Filter events for the matching phase
+    let events = events
+        .into_iter()
+        .filter(|event| {
+            matches!(
+                &event.config.trigger,
+                EventTrigger::Poll { phase: trigger_phase } if *trigger_phase == phase
+            )
+        })
+        .collect();
+
+    // Find the choice with the most votes (First choice wins ties)
+    let winning_choice = event.choices.iter().rev().max_by_key(|choice| choice.votes);
+
+    let event_data = EventData {
+        input_data: EventInputData::Poll {
+            poll_id: event.id,
+            phase,
+            title: event.title,
+            winning_choice: winning_choice.map(|choice| choice.title.clone()),
+            winning_votes: winning_choice
+                .map(|choice| choice.votes)
+                .unwrap_or_default(),
+            choices: event.choices,
+        },
+        user: None,
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
+    })
+}
+
+pub async fn match_prediction_event(
+    db: &DbPool,
+    phase: PredictionPhase,
+    event: TwitchEventPrediction,
+) -> anyhow::Result<EventMatchingData> {
+    let events = EventModel::get_by_trigger_type(db, EventTriggerType::Prediction).await;
+
+    let events = match events {
+        Ok(value) => value,
+        Err(err) => {
+            error!("failed to load events: {:?}", err);
+            Default::default()
+        }
+    };
+
+    // Filter events for the matching phase
+    let events = events
+        .into_iter()
+        .filter(|event| {
+            matches!(
+                &event.config.trigger,
+                EventTrigger::Prediction { phase: trigger_phase } if *trigger_phase == phase
+            )
+        })
+        .collect();
+
+    let winning_outcome = event.winning_outcome_id.as_ref().and_then(|outcome_id| {
+        event
+            .outcomes
+            .iter()
+            .find(|outcome| outcome.id.eq(outcome_id))
+    });
+
+    let event_data = EventData {
+        input_data: EventInputData::Prediction {
+            prediction_id: event.id,
+            phase,
+            title: event.title,
+            winning_outcome: winning_outcome.map(|outcome| outcome.title.clone()),
+            winning_points: winning_outcome
+                .map(|outcome| outcome.channel_points)
+                .unwrap_or_default(),
+            outcomes: event.outcomes,
+        },
+        user: None,
+    };
+
+    Ok(EventMatchingData {
+        events,
+        commands: Default::default(),
+        event_data,
+        captures: Default::default(),
+        command_suggestion: Default::default(),
+        redemption: Default::default(),
     })
 }
 
 #[cfg(test)]
 mod test {
     use super::{
-        match_ad_break_event, match_chat_event, match_cheer_bits_event, match_follow_event,
-        match_gifted_subscription_event, match_raid_event, match_re_subscription_event,
-        match_redeem_event, match_shoutout_receive_event, match_subscription_event,
+        ConditionInput, EventInputData, is_command_trigger_match, is_event_conditions_met,
+        match_ad_break_event, match_chat_event, match_chat_pattern, match_cheer_bits_event,
+        match_exact_commands, match_follow_event, match_gifted_subscription_event,
+        match_hype_train_event, match_raid_event, match_re_subscription_event, match_redeem_event,
+        match_shoutout_receive_event, match_subscription_event, match_viewer_events,
+        message_after_word,
     };
     use crate::{
         database::{
+            DbPool,
             entity::{
-                commands::{CommandConfig, CommandModel, CommandOutcome, CreateCommand},
+                app_data::{AppData, AppDataModel, MainConfig},
+                commands::{
+                    CommandConfig, CommandMatchMode, CommandModel, CommandOutcome, CreateCommand,
+                },
                 events::{
-                    CreateEvent, EventConfig, EventModel, EventOutcome, EventOutcomeSendChat,
-                    EventTrigger,
+                    ChatPatternMatchType, CreateEvent, EventCondition, EventConditionCheck,
+                    EventConfig, EventModel, EventTrigger, FirstMessageScope, HypeTrainPhase,
+                    PollPhase,
                 },
+                shared::MinMax,
+                viewers::ViewerModel,
             },
             mock_database,
         },
         events::{
             TwitchEventAdBreakBegin, TwitchEventChatMsg, TwitchEventCheerBits, TwitchEventFollow,
-            TwitchEventGiftSub, TwitchEventRaid, TwitchEventReSub, TwitchEventRedeem,
-            TwitchEventShoutoutReceive, TwitchEventSub,
+            TwitchEventGiftSub, TwitchEventHypeTrain, TwitchEventPollChoice, TwitchEventRaid,
+            TwitchEventReSub, TwitchEventRedeem, TwitchEventShoutoutReceive, TwitchEventSub,
         },
     };
+    use chrono::{DateTime, TimeDelta, Utc};
+    use std::collections::HashMap;
     use twitch_api::{
         eventsub::channel::{
             channel_points_custom_reward_redemption::Reward,
             subscription::message::SubscriptionMessage,
         },
-        types::{DisplayName, RedemptionId, SubscriptionTier, UserId, UserName},
+        types::{DisplayName, MsgId, RedemptionId, SubscriptionTier, UserId, UserName},
     };
+    use uuid::Uuid;
 
     /// Tests that a reward redemption event can successfully match using "match_redeem_event"
     /// when the ID is the same as the stored database model
@@ -727,13 +1669,9 @@ mod test {
                 config: EventConfig {
                     trigger: EventTrigger::Redeem {
                         reward_id: "test-reward".to_string(),
+                        update_redemption: false,
                     },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -814,12 +1752,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Bits { min_bits: 0 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -859,12 +1792,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Bits { min_bits: 500 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -922,12 +1850,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Follow,
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -980,12 +1903,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Subscription,
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1042,12 +1960,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::GiftedSubscription,
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1108,12 +2021,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Subscription,
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1190,12 +2098,7 @@ mod test {
                     trigger: EventTrigger::Command {
                         message: "!test".to_string(),
                     },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1220,6 +2123,7 @@ mod test {
                 user_name: UserName::from_static("mockuser"),
                 user_display_name: DisplayName::from_static("Mock User"),
             },
+            None,
         )
         .await
         .unwrap();
@@ -1246,6 +2150,13 @@ mod test {
                     },
                     cooldown: Default::default(),
                     require_role: Default::default(),
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
                 },
 
                 aliases: Vec::new(),
@@ -1272,6 +2183,7 @@ mod test {
                 user_name: UserName::from_static("mockuser"),
                 user_display_name: DisplayName::from_static("Mock User"),
             },
+            None,
         )
         .await
         .unwrap();
@@ -1301,6 +2213,13 @@ mod test {
                     },
                     cooldown: Default::default(),
                     require_role: Default::default(),
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode: Default::default(),
                 },
                 aliases: Vec::new(),
             },
@@ -1326,6 +2245,7 @@ mod test {
                 user_name: UserName::from_static("mockuser"),
                 user_display_name: DisplayName::from_static("Mock User"),
             },
+            None,
         )
         .await
         .unwrap();
@@ -1337,82 +2257,270 @@ mod test {
     }
 
     #[tokio::test]
-    async fn test_match_chat_event_not_matching() {
+    async fn test_match_chat_event_not_matching() {
+        let db = mock_database().await;
+
+        EventModel::create(
+            &db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Event".to_string(),
+                config: EventConfig {
+                    trigger: EventTrigger::Command {
+                        message: "!test".to_string(),
+                    },
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+        let message = serde_json::json!({
+            "text": "",
+            "fragments": []
+        });
+        let message: twitch_api::eventsub::channel::chat::Message =
+            serde_json::from_value(message).unwrap();
+
+        let found_event = match_chat_event(
+            &db,
+            TwitchEventChatMsg {
+                message_id: "mock-message".into(),
+                message,
+                cheer: None,
+
+                user_id: UserId::from_static("mock-user-id"),
+                user_name: UserName::from_static("mockuser"),
+                user_display_name: DisplayName::from_static("Mock User"),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(found_event.events.is_empty(), "should not match any events");
+    }
+
+    #[tokio::test]
+    async fn test_match_chat_event_non_existent() {
+        let db = mock_database().await;
+
+        let message = serde_json::json!({
+            "text": "",
+            "fragments": []
+        });
+        let message: twitch_api::eventsub::channel::chat::Message =
+            serde_json::from_value(message).unwrap();
+
+        let found_event = match_chat_event(
+            &db,
+            TwitchEventChatMsg {
+                message_id: "mock-message".into(),
+                message,
+                cheer: None,
+
+                user_id: UserId::from_static("mock-user-id"),
+                user_name: UserName::from_static("mockuser"),
+                user_display_name: DisplayName::from_static("Mock User"),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(found_event.events.is_empty(), "should not match any events");
+    }
+
+    #[tokio::test]
+    async fn test_match_chat_event_first_message() {
+        let db = mock_database().await;
+
+        let expected_event = EventModel::create(
+            &db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Event".to_string(),
+                config: EventConfig {
+                    trigger: EventTrigger::FirstMessage {
+                        scope: FirstMessageScope::Ever,
+                    },
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+        let message = serde_json::json!({
+            "text": "hello",
+            "fragments": []
+        });
+        let message: twitch_api::eventsub::channel::chat::Message =
+            serde_json::from_value(message).unwrap();
+
+        let chat_event = TwitchEventChatMsg {
+            message_id: "mock-message".into(),
+            message,
+            cheer: None,
+
+            user_id: UserId::from_static("mock-user-id"),
+            user_name: UserName::from_static("mockuser"),
+            user_display_name: DisplayName::from_static("Mock User"),
+        };
+
+        let found_event = match_chat_event(&db, chat_event.clone(), None)
+            .await
+            .unwrap();
+
+        let event = found_event.events.first().expect("missing matching event");
+
+        // Expect found event to match created
+        assert_eq!(event.id, expected_event.id);
+
+        // Second message from the same viewer should not match
+        let found_event = match_chat_event(&db, chat_event, None).await.unwrap();
+        assert!(found_event.events.is_empty(), "should not match any events");
+    }
+
+    /// Creates a first message event for each scope and a returning viewer
+    /// event for viewers away for 7 days, returns the (ever, session, returning) IDs
+    async fn create_viewer_events(db: &DbPool) -> (Uuid, Uuid, Uuid) {
+        let mut ids = Vec::new();
+
+        for trigger in [
+            EventTrigger::FirstMessage {
+                scope: FirstMessageScope::Ever,
+            },
+            EventTrigger::FirstMessage {
+                scope: FirstMessageScope::Session,
+            },
+            EventTrigger::ReturningViewer { days: 7 },
+        ] {
+            let event = EventModel::create(
+                db,
+                CreateEvent {
+                    enabled: true,
+                    name: "Test Event".to_string(),
+                    config: EventConfig {
+                        trigger,
+                        ..Default::default()
+                    },
+                },
+            )
+            .await
+            .unwrap();
+            ids.push(event.id);
+        }
+
+        (ids[0], ids[1], ids[2])
+    }
+
+    /// Creates the previous state of a viewer last seen at `last_seen_at`
+    fn previous_viewer(last_seen_at: DateTime<Utc>) -> ViewerModel {
+        ViewerModel {
+            user_id: "test".to_string(),
+            message_count: 1,
+            first_seen_at: last_seen_at,
+            last_seen_at,
+            previous_seen_at: None,
+        }
+    }
+
+    /// Runs [match_viewer_events] returning the IDs of the matched events
+    async fn matched_viewer_events(
+        db: &DbPool,
+        previous: Option<ViewerModel>,
+        session_started_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = match_viewer_events(db, previous, session_started_at, now)
+            .await
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Tests that new viewers match every first message scope but
+    /// not returning viewer events
+    #[tokio::test]
+    async fn test_match_viewer_events_new_viewer() {
         let db = mock_database().await;
+        let (ever, session, _) = create_viewer_events(&db).await;
+        let now = Utc::now();
 
-        EventModel::create(
-            &db,
-            CreateEvent {
-                enabled: true,
-                name: "Test Event".to_string(),
-                config: EventConfig {
-                    trigger: EventTrigger::Command {
-                        message: "!test".to_string(),
-                    },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
-                },
-            },
-        )
-        .await
-        .unwrap();
+        let mut expected = vec![ever, session];
+        expected.sort();
 
-        let message = serde_json::json!({
-            "text": "",
-            "fragments": []
-        });
-        let message: twitch_api::eventsub::channel::chat::Message =
-            serde_json::from_value(message).unwrap();
+        assert_eq!(
+            matched_viewer_events(&db, None, Some(now - TimeDelta::hours(1)), now).await,
+            expected
+        );
 
-        let found_event = match_chat_event(
-            &db,
-            TwitchEventChatMsg {
-                message_id: "mock-message".into(),
-                message,
-                cheer: None,
+        // Session scope also matches new viewers while offline
+        assert_eq!(matched_viewer_events(&db, None, None, now).await, expected);
+    }
 
-                user_id: UserId::from_static("mock-user-id"),
-                user_name: UserName::from_static("mockuser"),
-                user_display_name: DisplayName::from_static("Mock User"),
-            },
-        )
-        .await
-        .unwrap();
+    /// Tests that session scoped first message events only match viewers
+    /// that have not been seen since the session started
+    #[tokio::test]
+    async fn test_match_viewer_events_session() {
+        let db = mock_database().await;
+        let (_, session, _) = create_viewer_events(&db).await;
+        let now = Utc::now();
+        let started_at = now - TimeDelta::hours(1);
+
+        // Seen before the session started
+        let previous = previous_viewer(now - TimeDelta::hours(2));
+        assert_eq!(
+            matched_viewer_events(&db, Some(previous.clone()), Some(started_at), now).await,
+            vec![session]
+        );
 
-        assert!(found_event.events.is_empty(), "should not match any events");
+        // Seen during the session
+        let seen = previous_viewer(now - TimeDelta::minutes(30));
+        assert!(
+            matched_viewer_events(&db, Some(seen), Some(started_at), now)
+                .await
+                .is_empty()
+        );
+
+        // No session while offline
+        assert!(
+            matched_viewer_events(&db, Some(previous), None, now)
+                .await
+                .is_empty()
+        );
     }
 
+    /// Tests that returning viewer events only match viewers that
+    /// have been away for at least the configured days
     #[tokio::test]
-    async fn test_match_chat_event_non_existent() {
+    async fn test_match_viewer_events_returning() {
         let db = mock_database().await;
+        let (_, _, returning) = create_viewer_events(&db).await;
+        let now = Utc::now();
 
-        let message = serde_json::json!({
-            "text": "",
-            "fragments": []
-        });
-        let message: twitch_api::eventsub::channel::chat::Message =
-            serde_json::from_value(message).unwrap();
-
-        let found_event = match_chat_event(
-            &db,
-            TwitchEventChatMsg {
-                message_id: "mock-message".into(),
-                message,
-                cheer: None,
+        let previous = previous_viewer(now - TimeDelta::days(8));
+        assert_eq!(
+            matched_viewer_events(&db, Some(previous), None, now).await,
+            vec![returning]
+        );
 
-                user_id: UserId::from_static("mock-user-id"),
-                user_name: UserName::from_static("mockuser"),
-                user_display_name: DisplayName::from_static("Mock User"),
-            },
-        )
-        .await
-        .unwrap();
+        let previous = previous_viewer(now - TimeDelta::days(7));
+        assert_eq!(
+            matched_viewer_events(&db, Some(previous), None, now).await,
+            vec![returning]
+        );
 
-        assert!(found_event.events.is_empty(), "should not match any events");
+        let previous = previous_viewer(now - TimeDelta::days(2));
+        assert!(
+            matched_viewer_events(&db, Some(previous), None, now)
+                .await
+                .is_empty()
+        );
     }
 
     #[tokio::test]
@@ -1426,12 +2534,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Raid { min_raiders: 1 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1467,12 +2570,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::Raid { min_raiders: 5 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1524,12 +2622,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::AdBreakBegin,
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1578,12 +2671,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::ShoutoutReceive { min_viewers: 1 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1619,12 +2707,7 @@ mod test {
                 name: "Test Event".to_string(),
                 config: EventConfig {
                     trigger: EventTrigger::ShoutoutReceive { min_viewers: 5 },
-                    cooldown: Default::default(),
-                    outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
-                        template: "test".to_string(),
-                    }),
-                    outcome_delay: Default::default(),
-                    require_role: Default::default(),
+                    ..Default::default()
                 },
             },
         )
@@ -1664,4 +2747,504 @@ mod test {
 
         assert!(found_event.events.is_empty(), "should not match any events");
     }
+
+    /// Tests that hype train events only match their phase and minimum level
+    #[tokio::test]
+    async fn test_match_hype_train_event() {
+        let db = mock_database().await;
+
+        let expected_event = EventModel::create(
+            &db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Event".to_string(),
+                config: EventConfig {
+                    trigger: EventTrigger::HypeTrain {
+                        phase: HypeTrainPhase::Progress,
+                        min_level: 2,
+                    },
+                    ..Default::default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+
+        let hype_train = |level| TwitchEventHypeTrain {
+            level,
+            total: 1500,
+            progress: 300,
+            goal: 1800,
+        };
+
+        let found_event = match_hype_train_event(&db, HypeTrainPhase::Progress, hype_train(2))
+            .await
+            .unwrap();
+
+        let event = found_event.events.first().expect("missing matching event");
+
+        // Expect found event to match created
+        assert_eq!(event.id, expected_event.id);
+
+        // Expect the input data to contain the hype train
+        assert!(matches!(
+            found_event.event_data.input_data,
+            EventInputData::HypeTrain {
+                phase: HypeTrainPhase::Progress,
+                level: 2,
+                total: 1500,
+                progress: 300,
+                goal: 1800,
+            }
+        ));
+
+        // Lower level and other phases should not match
+        let found_event = match_hype_train_event(&db, HypeTrainPhase::Progress, hype_train(1))
+            .await
+            .unwrap();
+        assert!(found_event.events.is_empty(), "should not match any events");
+
+        let found_event = match_hype_train_event(&db, HypeTrainPhase::Begin, hype_train(2))
+            .await
+            .unwrap();
+        assert!(found_event.events.is_empty(), "should not match any events");
+    }
+
+    fn conditions_met(conditions: &[EventCondition], input_data: &EventInputData) -> bool {
+        is_event_conditions_met(conditions, &ConditionInput::new(input_data))
+    }
+
+    /// Tests that numeric range conditions are checked against the input data
+    #[test]
+    fn test_event_conditions_between() {
+        let conditions = vec![EventCondition {
+            field: "bits".to_string(),
+            check: EventConditionCheck::Between {
+                range: MinMax {
+                    min: 100.0,
+                    max: 499.0,
+                },
+            },
+        }];
+
+        let input_data = |bits| EventInputData::Bits {
+            bits,
+            anonymous: false,
+            message: "".to_string(),
+        };
+
+        assert!(conditions_met(&conditions, &input_data(100)));
+        assert!(conditions_met(&conditions, &input_data(499)));
+        assert!(!conditions_met(&conditions, &input_data(99)));
+        assert!(!conditions_met(&conditions, &input_data(500)));
+    }
+
+    /// Tests that subscription tiers can be compared against their numeric value
+    #[test]
+    fn test_event_conditions_sub_tier() {
+        let conditions = vec![EventCondition {
+            field: "tier".to_string(),
+            check: EventConditionCheck::Equals {
+                value: serde_json::json!(3000),
+            },
+        }];
+
+        let input_data = |tier| EventInputData::Subscription {
+            tier,
+            is_gift: false,
+        };
+
+        assert!(conditions_met(
+            &conditions,
+            &input_data(SubscriptionTier::Tier3)
+        ));
+        assert!(!conditions_met(
+            &conditions,
+            &input_data(SubscriptionTier::Tier1)
+        ));
+    }
+
+    /// Tests that pattern conditions match against text input data
+    #[test]
+    fn test_event_conditions_pattern() {
+        let conditions = vec![EventCondition {
+            field: "userInput".to_string(),
+            check: EventConditionCheck::Matches {
+                pattern: "^#[0-9a-fA-F]{6}$".to_string(),
+            },
+        }];
+
+        let input_data = |user_input: &str| EventInputData::Redeem {
+            redemption_id: "test-redemption".to_string(),
+            reward_id: "test-reward".to_string(),
+            reward_name: "Test Reward".to_string(),
+            cost: 100,
+            user_input: user_input.to_string(),
+        };
+
+        assert!(conditions_met(&conditions, &input_data("#ff00ff")));
+        assert!(!conditions_met(&conditions, &input_data("purple")));
+    }
+
+    /// Tests that conditions on fields missing from the input data are not met
+    #[test]
+    fn test_event_conditions_missing_field() {
+        let conditions = vec![EventCondition {
+            field: "viewers".to_string(),
+            check: EventConditionCheck::GreaterThan { value: 50.0 },
+        }];
+
+        assert!(!conditions_met(&conditions, &EventInputData::None));
+        assert!(conditions_met(
+            &conditions,
+            &EventInputData::Raid { viewers: 51 }
+        ));
+    }
+
+    /// Tests that conditions can check nested fields using dot separated paths
+    #[test]
+    fn test_event_conditions_nested_field() {
+        let condition = |field: &str, check| EventCondition {
+            field: field.to_string(),
+            check,
+        };
+
+        let input_data = EventInputData::Chat {
+            message_id: MsgId::from_static("test-message"),
+            message: "throw 5 hearts".to_string(),
+            fragments: Vec::new(),
+            cheer: None,
+            captures: HashMap::from([("amount".to_string(), "5".to_string())]),
+        };
+
+        assert!(conditions_met(
+            &[condition(
+                "captures.amount",
+                EventConditionCheck::GreaterThan { value: 4.0 }
+            )],
+            &input_data
+        ));
+        assert!(!conditions_met(
+            &[condition(
+                "captures.item",
+                EventConditionCheck::Contains {
+                    value: "hearts".to_string()
+                }
+            )],
+            &input_data
+        ));
+
+        let input_data = EventInputData::Poll {
+            poll_id: "test-poll".to_string(),
+            phase: PollPhase::End,
+            title: "Test Poll".to_string(),
+            choices: vec![TwitchEventPollChoice {
+                id: "test-choice".to_string(),
+                title: "Yes".to_string(),
+                votes: 10,
+            }],
+            winning_choice: Some("Yes".to_string()),
+            winning_votes: 10,
+        };
+
+        assert!(conditions_met(
+            &[condition(
+                "choices.0.votes",
+                EventConditionCheck::Equals {
+                    value: serde_json::json!(10)
+                }
+            )],
+            &input_data
+        ));
+        assert!(!conditions_met(
+            &[condition(
+                "choices.1.votes",
+                EventConditionCheck::GreaterThan { value: 0.0 }
+            )],
+            &input_data
+        ));
+    }
+
+    /// Tests that substring patterns match anywhere within a message
+    #[test]
+    fn test_match_chat_pattern_substring() {
+        let pattern = |message| {
+            match_chat_pattern("bonk", ChatPatternMatchType::Substring, false, message, &[])
+        };
+
+        assert!(pattern("time to BONK the streamer").is_some());
+        assert!(pattern("nothing to see here").is_none());
+    }
+
+    /// Tests that glob patterns match against the whole message
+    #[test]
+    fn test_match_chat_pattern_glob() {
+        let pattern =
+            |message| match_chat_pattern("*b?nk*", ChatPatternMatchType::Glob, false, message, &[]);
+
+        assert!(pattern("time to bank the streamer").is_some());
+        assert!(pattern("bonk").is_some());
+        assert!(pattern("nothing to see here").is_none());
+    }
+
+    /// Tests that regex patterns provide their named capture groups
+    #[test]
+    fn test_match_chat_pattern_regex_captures() {
+        let captures = match_chat_pattern(
+            r"throw (?<amount>\d+) (?<item>\w+)",
+            ChatPatternMatchType::Regex,
+            false,
+            "please THROW 5 hearts",
+            &[],
+        )
+        .expect("pattern should match");
+
+        assert_eq!(captures.get("amount").map(String::as_str), Some("5"));
+        assert_eq!(captures.get("item").map(String::as_str), Some("hearts"));
+    }
+
+    /// Tests that regex patterns without capture groups provide no captures
+    /// and that invalid patterns never match
+    #[test]
+    fn test_match_chat_pattern_regex_without_captures() {
+        let captures = match_chat_pattern(
+            r"throw \d+",
+            ChatPatternMatchType::Regex,
+            false,
+            "please THROW 5 hearts",
+            &[],
+        )
+        .expect("pattern should match");
+        assert!(captures.is_empty());
+
+        assert!(
+            match_chat_pattern("(throw", ChatPatternMatchType::Regex, false, "(throw", &[])
+                .is_none()
+        );
+    }
+
+    /// Tests matching chat words against command triggers
+    #[test]
+    fn test_is_command_trigger_match() {
+        let prefixes = vec!["!".to_string(), "?".to_string()];
+
+        let cases = [
+            ("!test", "!test", CommandMatchMode::CaseInsensitive, true),
+            ("!test", "?TEST", CommandMatchMode::CaseInsensitive, true),
+            ("!test", "test", CommandMatchMode::CaseInsensitive, false),
+            ("!Test", "!test", CommandMatchMode::CaseSensitive, false),
+            ("!Test", "?Test", CommandMatchMode::CaseSensitive, true),
+            ("!test", "!tset", CommandMatchMode::Fuzzy, true),
+            ("!test", "!best", CommandMatchMode::Fuzzy, true),
+            ("!test", "!toast", CommandMatchMode::Fuzzy, false),
+            ("!hi", "!ho", CommandMatchMode::Fuzzy, false),
+            // Triggers without a configured prefix must be written in full
+            ("hello", "hello", CommandMatchMode::CaseInsensitive, true),
+            ("#hello", "#hello", CommandMatchMode::CaseInsensitive, true),
+            ("#hello", "!hello", CommandMatchMode::CaseInsensitive, false),
+            ("!", "!", CommandMatchMode::CaseInsensitive, false),
+        ];
+
+        for (trigger, word, match_mode, expected) in cases {
+            assert_eq!(
+                is_command_trigger_match(trigger, word, &prefixes, match_mode),
+                expected,
+                "trigger: {trigger}, word: {word}, mode: {match_mode:?}"
+            );
+        }
+    }
+
+    /// Tests getting the message after a word
+    #[test]
+    fn test_message_after_word() {
+        assert_eq!(message_after_word("!test a  b", 0), "a  b");
+        assert_eq!(message_after_word("  hello   !test a b", 1), "a b");
+        assert_eq!(message_after_word("!test", 0), "");
+    }
+
+    async fn create_test_command(db: &DbPool, command: &str) -> CommandModel {
+        create_test_command_with_mode(db, command, Default::default()).await
+    }
+
+    async fn create_test_command_with_mode(
+        db: &DbPool,
+        command: &str,
+        match_mode: CommandMatchMode,
+    ) -> CommandModel {
+        CommandModel::create(
+            db,
+            CreateCommand {
+                enabled: true,
+                name: "Test Command".to_string(),
+                command: command.to_string(),
+                config: CommandConfig {
+                    outcome: CommandOutcome::Template {
+                        message: "test".to_string(),
+                    },
+                    cooldown: Default::default(),
+                    require_role: Default::default(),
+                    live_only: Default::default(),
+                    usage_limits: Default::default(),
+                    usage_limit_message: Default::default(),
+                    cooldown_message: Default::default(),
+                    require_role_message: Default::default(),
+                    arguments: Default::default(),
+                    match_mode,
+                },
+                aliases: Vec::new(),
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn set_main_config(db: &DbPool, main_config: MainConfig) {
+        let mut app_data = AppData::default();
+        app_data.app.main_config = main_config;
+        AppDataModel::set(db, app_data).await.unwrap();
+    }
+
+    fn mock_chat_event(text: &str) -> TwitchEventChatMsg {
+        let message = serde_json::json!({
+            "text": text,
+            "fragments": []
+        });
+
+        TwitchEventChatMsg {
+            message_id: "mock-message".into(),
+            message: serde_json::from_value(message).unwrap(),
+            cheer: None,
+
+            user_id: UserId::from_static("mock-user-id"),
+            user_name: UserName::from_static("mockuser"),
+            user_display_name: DisplayName::from_static("Mock User"),
+        }
+    }
+
+    /// Tests that commands can be triggered mid message using any configured prefix
+    #[tokio::test]
+    async fn test_match_chat_event_command_mid_message() {
+        let db = mock_database().await;
+        let expected_command = create_test_command(&db, "!test").await;
+
+        // Mid message commands are disabled by default
+        let found_event = match_chat_event(&db, mock_chat_event("hello ?test a b"), None)
+            .await
+            .unwrap();
+        assert!(found_event.commands.is_empty());
+
+        let mut main_config = MainConfig::default();
+        main_config.command_prefixes = vec!["!".to_string(), "?".to_string()];
+        main_config.mid_message_commands = true;
+        set_main_config(&db, main_config).await;
+
+        let found_event = match_chat_event(&db, mock_chat_event("hello ?test a  b"), None)
+            .await
+            .unwrap();
+
+        let command = found_event
+            .commands
+            .first()
+            .expect("missing matching command");
+
+        assert_eq!(command.command.id, expected_command.id);
+        assert_eq!(command.message, "a  b");
+        assert_eq!(command.args, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    /// Tests that a suggestion is provided for near misses when enabled
+    #[tokio::test]
+    async fn test_match_chat_event_command_suggestion() {
+        let db = mock_database().await;
+        let expected_command = create_test_command(&db, "!test").await;
+
+        // Suggestions are disabled by default
+        let found_event = match_chat_event(&db, mock_chat_event("!tset"), None)
+            .await
+            .unwrap();
+        assert!(found_event.command_suggestion.is_none());
+
+        let mut main_config = MainConfig::default();
+        main_config.command_suggestions = true;
+        set_main_config(&db, main_config).await;
+
+        let found_event = match_chat_event(&db, mock_chat_event("!tset"), None)
+            .await
+            .unwrap();
+
+        assert!(found_event.commands.is_empty());
+
+        let suggestion = found_event
+            .command_suggestion
+            .expect("missing command suggestion");
+        assert_eq!(suggestion.command_id, expected_command.id);
+        assert_eq!(suggestion.trigger, "!test");
+
+        // Unrelated messages should not get suggestions
+        let found_event = match_chat_event(&db, mock_chat_event("!lurk"), None)
+            .await
+            .unwrap();
+        assert!(found_event.command_suggestion.is_none());
+    }
+
+    /// Tests the exact command lookup used before comparing against every command
+    #[tokio::test]
+    async fn test_match_exact_commands() {
+        let db = mock_database().await;
+        let prefixes = vec!["!".to_string(), "?".to_string()];
+
+        let insensitive = create_test_command(&db, "!hello").await;
+        let sensitive =
+            create_test_command_with_mode(&db, "!Case", CommandMatchMode::CaseSensitive).await;
+        create_test_command_with_mode(&db, "!lurking", CommandMatchMode::Fuzzy).await;
+
+        let ids = |commands: Vec<CommandModel>| -> Vec<Uuid> {
+            commands.into_iter().map(|command| command.id).collect()
+        };
+
+        assert_eq!(
+            ids(match_exact_commands(&db, "!hello", &prefixes).await),
+            vec![insensitive.id]
+        );
+        assert_eq!(
+            ids(match_exact_commands(&db, "?HELLO", &prefixes).await),
+            vec![insensitive.id]
+        );
+        assert_eq!(
+            ids(match_exact_commands(&db, "?Case", &prefixes).await),
+            vec![sensitive.id]
+        );
+
+        // Case sensitive commands are not matched using the lowercase word
+        assert!(
+            match_exact_commands(&db, "!CASE", &prefixes)
+                .await
+                .is_empty()
+        );
+
+        // Typos are left to the fallback
+        assert!(
+            match_exact_commands(&db, "!lurkign", &prefixes)
+                .await
+                .is_empty()
+        );
+    }
+
+    /// Tests that fuzzy commands are matched when there is no exact match
+    #[tokio::test]
+    async fn test_match_chat_event_command_fuzzy_fallback() {
+        let db = mock_database().await;
+        let expected_command =
+            create_test_command_with_mode(&db, "!lurking", CommandMatchMode::Fuzzy).await;
+
+        let found_event = match_chat_event(&db, mock_chat_event("!lurkign"), None)
+            .await
+            .unwrap();
+
+        let command = found_event
+            .commands
+            .first()
+            .expect("missing matching command");
+        assert_eq!(command.command.id, expected_command.id);
+    }
 }
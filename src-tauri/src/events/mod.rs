@@ -12,10 +12,19 @@ use twitch_api::{
 };
 use uuid::Uuid;
 
+pub mod arguments;
+pub mod counter;
+pub mod cron;
+pub mod feedback;
 pub mod matching;
 pub mod outcome;
+pub mod pause;
 pub mod processing;
+pub mod queue;
+pub mod redemption;
 pub mod scheduler;
+pub mod template;
+pub mod validation;
 
 pub type AppEventReceiver = mpsc::UnboundedReceiver<AppEvent>;
 pub type AppEventSender = mpsc::UnboundedSender<AppEvent>;
@@ -43,6 +52,30 @@ pub enum AppEvent {
     AdBreakBegin(TwitchEventAdBreakBegin),
     /// Twitch shoutout received
     ShoutoutReceive(TwitchEventShoutoutReceive),
+    /// Twitch hype train started
+    HypeTrainBegin(TwitchEventHypeTrain),
+    /// Twitch hype train progressed
+    HypeTrainProgress(TwitchEventHypeTrain),
+    /// Twitch hype train ended
+    HypeTrainEnd(TwitchEventHypeTrain),
+    /// Twitch poll started
+    PollBegin(TwitchEventPoll),
+    /// Twitch poll progressed
+    PollProgress(TwitchEventPoll),
+    /// Twitch poll ended
+    PollEnd(TwitchEventPoll),
+    /// Twitch prediction started
+    PredictionBegin(TwitchEventPrediction),
+    /// Twitch prediction progressed
+    PredictionProgress(TwitchEventPrediction),
+    /// Twitch prediction locked
+    PredictionLock(TwitchEventPrediction),
+    /// Twitch prediction ended
+    PredictionEnd(TwitchEventPrediction),
+    /// Twitch stream went live
+    StreamOnline,
+    /// Twitch stream went offline
+    StreamOffline,
     /// Scheduler timer completed
     TimerCompleted(TimerCompleted),
 
@@ -61,6 +94,9 @@ pub enum AppEvent {
 
     /// Twitch has been logged in
     TwitchClientLoggedIn,
+
+    /// Event processing was resumed after being paused
+    ProcessingResumed,
 }
 
 #[derive(Debug, Clone)]
@@ -186,3 +222,61 @@ pub struct TwitchEventShoutoutReceive {
     /// The number of users that were watching the from-broadcaster’s stream at the time of the Shoutout.
     pub viewer_count: i64,
 }
+
+#[derive(Debug, Clone)]
+pub struct TwitchEventHypeTrain {
+    /// Current level of the hype train
+    pub level: i64,
+    /// Total points contributed to the hype train
+    pub total: i64,
+    /// Points contributed towards the current level
+    pub progress: i64,
+    /// Points required to reach the next level (Zero when the hype train has ended)
+    pub goal: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwitchEventPoll {
+    /// ID of the poll
+    pub id: String,
+    /// Question displayed for the poll
+    pub title: String,
+    /// Choices for the poll and their current votes
+    pub choices: Vec<TwitchEventPollChoice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwitchEventPollChoice {
+    /// ID of the choice
+    pub id: String,
+    /// Title of the choice
+    pub title: String,
+    /// Total votes for the choice
+    pub votes: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwitchEventPrediction {
+    /// ID of the prediction
+    pub id: String,
+    /// Question displayed for the prediction
+    pub title: String,
+    /// Outcomes for the prediction and their current predictions
+    pub outcomes: Vec<TwitchEventPredictionOutcome>,
+    /// ID of the outcome that won (Only present once the prediction has been resolved)
+    pub winning_outcome_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwitchEventPredictionOutcome {
+    /// ID of the outcome
+    pub id: String,
+    /// Title of the outcome
+    pub title: String,
+    /// Number of users that predicted the outcome
+    pub users: i64,
+    /// Channel points spent predicting the outcome
+    pub channel_points: i64,
+}
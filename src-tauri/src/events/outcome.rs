@@ -1,51 +1,262 @@
-use super::matching::{EventData, EventInputData};
+use super::{
+    matching::{EventData, EventInputData},
+    template::{event_template_args, render_template, TemplateContext},
+};
 use crate::{
     database::{
         entity::{
             events::{
                 EventModel, EventOutcome, EventOutcomeBits, EventOutcomeChannelEmotes,
-                EventOutcomePlaySound, EventOutcomeScript, EventOutcomeSendChat,
-                EventOutcomeThrowable, EventOutcomeTriggerHotkey, ThrowableAmountData,
+                EventOutcomeCreatePoll, EventOutcomeCreatePrediction, EventOutcomeMode,
+                EventOutcomePlaySound, EventOutcomeScript, EventOutcomeSendChat, EventOutcomeStep,
+                EventOutcomeThrowable, EventOutcomeToggleReward, EventOutcomeTriggerHotkey,
+                ThrowableAmountData, ToggleRewardAction,
             },
             items::{ItemConfig, ItemImageConfig, ItemModel},
             sounds::{PartialSoundModel, SoundModel},
         },
         DbPool,
     },
-    overlay::{ItemsWithSounds, OverlayMessage, PartialItemModel, ThrowItemConfig},
+    overlay::{
+        ItemsWithSounds, OverlayMessage, OverlayMessageSender, PartialItemModel, ThrowItemConfig,
+    },
     script::runtime::{RuntimeExecutionContext, ScriptExecutorHandle},
-    twitch::manager::Twitch,
+    twitch::manager::{Twitch, UpdateReward},
 };
 use anyhow::{anyhow, Context};
-use std::collections::HashSet;
-use twitch_api::types::SubscriptionTier;
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::error;
+use rand::distr::{weighted::WeightedIndex, Distribution};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+use tokio::time::sleep;
 use uuid::Uuid;
 
+/// Record of a random outcome pick made while executing an outcome chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomOutcomePick {
+    /// Index of the outcome step the pick was made for
+    pub step: usize,
+    /// Index of the picked outcome for each level of random outcomes
+    pub path: Vec<usize>,
+    /// The outcome that was picked
+    pub outcome: EventOutcome,
+}
+
+/// Execute the chain of outcomes for an event, sending any produced
+/// overlay messages to the overlay
+///
+/// Returns the random outcome picks that were made during execution
+/// along with the result, picks are returned even when execution fails
+pub async fn execute_outcomes(
+    db: &DbPool,
+    twitch: &Twitch,
+    script_handle: &ScriptExecutorHandle,
+    event_sender: &OverlayMessageSender,
+
+    event: EventModel,
+    event_data: &EventData,
+) -> (Vec<RandomOutcomePick>, anyhow::Result<()>) {
+    let event_id = event.id;
+    let mut picks = Vec::new();
+
+    let result = match event.config.outcome_mode {
+        EventOutcomeMode::Sequential => {
+            let mut result = Ok(());
+
+            for (index, step) in event.config.outcomes.into_iter().enumerate() {
+                let (pick, step_result) = execute_outcome_step(
+                    db,
+                    twitch,
+                    script_handle,
+                    event_sender,
+                    event_id,
+                    index,
+                    step,
+                    event_data,
+                )
+                .await;
+
+                picks.extend(pick);
+
+                // Remaining steps are not executed after a failure
+                if step_result.is_err() {
+                    result = step_result;
+                    break;
+                }
+            }
+
+            result
+        }
+        EventOutcomeMode::Parallel => {
+            let mut futures = event
+                .config
+                .outcomes
+                .into_iter()
+                .enumerate()
+                .map(|(index, step)| {
+                    execute_outcome_step(
+                        db,
+                        twitch,
+                        script_handle,
+                        event_sender,
+                        event_id,
+                        index,
+                        step,
+                        event_data,
+                    )
+                })
+                .collect::<FuturesUnordered<_>>();
+
+            let mut result = Ok(());
+
+            // Allow all steps to complete before reporting the first error
+            while let Some((pick, step_result)) = futures.next().await {
+                picks.extend(pick);
+
+                if let Err(err) = step_result {
+                    if result.is_ok() {
+                        result = Err(err);
+                    } else {
+                        error!("error while executing event outcome step: {err:?}");
+                    }
+                }
+            }
+
+            // Steps complete out of order, keep picks in step order
+            picks.sort_by_key(|pick| pick.step);
+
+            result
+        }
+    };
+
+    (picks, result)
+}
+
+/// Execute a single step of an outcome chain, waiting for the step
+/// delay before producing and sending the outcome
+///
+/// Returns the random outcome pick made for the step along with the result
+#[allow(clippy::too_many_arguments)]
+async fn execute_outcome_step(
+    db: &DbPool,
+    twitch: &Twitch,
+    script_handle: &ScriptExecutorHandle,
+    event_sender: &OverlayMessageSender,
+
+    event_id: Uuid,
+    index: usize,
+    step: EventOutcomeStep,
+    event_data: &EventData,
+) -> (Option<RandomOutcomePick>, anyhow::Result<()>) {
+    // Wait for the step delay
+    if step.delay > 0 {
+        sleep(Duration::from_millis(step.delay as u64)).await;
+    }
+
+    // Resolve any random outcomes to the picked outcome
+    let (outcome, path) = match resolve_random_outcome(step.outcome) {
+        Ok(value) => value,
+        Err(err) => return (None, Err(err)),
+    };
+    let pick = (!path.is_empty()).then(|| RandomOutcomePick {
+        step: index,
+        path,
+        outcome: outcome.clone(),
+    });
+
+    // Produce outcome message and send it
+    let result =
+        produce_outcome_message(db, twitch, script_handle, event_id, outcome, event_data).await;
+
+    match result {
+        Ok(Some(msg)) => {
+            _ = event_sender.send(msg);
+            (pick, Ok(()))
+        }
+        Ok(None) => (pick, Ok(())),
+        Err(err) => (pick, Err(err)),
+    }
+}
+
+/// Resolves a random outcome into one of its weighted outcomes, repeating
+/// for nested random outcomes until a concrete outcome is reached
+///
+/// Returns the resolved outcome along with the index picked at each level,
+/// non random outcomes are returned as is with an empty path
+pub fn resolve_random_outcome(
+    mut outcome: EventOutcome,
+) -> anyhow::Result<(EventOutcome, Vec<usize>)> {
+    let mut path = Vec::new();
+
+    while let EventOutcome::Random(data) = outcome {
+        let weights = data.outcomes.iter().map(|outcome| outcome.weight);
+        let distribution =
+            WeightedIndex::new(weights).context("random outcome has no pickable outcomes")?;
+        let index = distribution.sample(&mut rand::rng());
+
+        path.push(index);
+        outcome = data
+            .outcomes
+            .into_iter()
+            .nth(index)
+            .context("picked random outcome was missing")?
+            .outcome;
+    }
+
+    Ok((outcome, path))
+}
+
 /// Produce a message for an outcome
 pub async fn produce_outcome_message(
     db: &DbPool,
     twitch: &Twitch,
     script_handle: &ScriptExecutorHandle,
 
-    event: EventModel,
+    event_id: Uuid,
+    outcome: EventOutcome,
     event_data: &EventData,
 ) -> anyhow::Result<Option<OverlayMessage>> {
-    match event.config.outcome {
+    match outcome {
         EventOutcome::ThrowBits(data) => throw_bits_outcome(db, event_data, data).await.map(Some),
         EventOutcome::Throwable(data) => throwable_outcome(db, event_data, data).await.map(Some),
         EventOutcome::TriggerHotkey(data) => trigger_hotkey_outcome(data).map(Some),
         EventOutcome::PlaySound(data) => play_sound_outcome(db, data).await.map(Some),
         EventOutcome::SendChatMessage(data) => {
-            send_chat_message(twitch, event_data, data).await?;
+            send_chat_message(db, twitch, event_data, data).await?;
             Ok(None)
         }
         EventOutcome::Script(data) => {
-            execute_script(script_handle, event.id, event_data, data).await?;
+            execute_script(script_handle, event_id, event_data, data).await?;
             Ok(None)
         }
         EventOutcome::ChannelEmotes(data) => throw_channel_emotes_outcome(twitch, event_data, data)
             .await
             .map(Some),
+        EventOutcome::CreatePoll(data) => {
+            create_poll_outcome(twitch, data).await?;
+            Ok(None)
+        }
+        EventOutcome::CreatePrediction(data) => {
+            create_prediction_outcome(twitch, data).await?;
+            Ok(None)
+        }
+        EventOutcome::ToggleReward(data) => {
+            toggle_reward_outcome(twitch, data).await?;
+            Ok(None)
+        }
+        EventOutcome::Random(data) => {
+            let (outcome, _path) = resolve_random_outcome(EventOutcome::Random(data))?;
+            Box::pin(produce_outcome_message(
+                db,
+                twitch,
+                script_handle,
+                event_id,
+                outcome,
+                event_data,
+            ))
+            .await
+        }
     }
 }
 
@@ -59,6 +270,8 @@ pub async fn execute_script(
         .execute(
             RuntimeExecutionContext::Event { event_id },
             data.script,
+            data.permissions,
+            data.limits,
             event_data.clone(),
         )
         .await?;
@@ -66,104 +279,76 @@ pub async fn execute_script(
     Ok(())
 }
 
-fn format_subscription_tier(tier: &SubscriptionTier) -> &'static str {
-    match tier {
-        SubscriptionTier::Tier1 => "Tier 1",
-        SubscriptionTier::Tier2 => "Tier 2",
-        SubscriptionTier::Tier3 => "Tier 3",
-        SubscriptionTier::Prime => "Prime",
-        SubscriptionTier::Other(_) => "Other",
-    }
+async fn create_poll_outcome(twitch: &Twitch, data: EventOutcomeCreatePoll) -> anyhow::Result<()> {
+    twitch
+        .create_poll(&data.title, &data.choices, data.duration)
+        .await
+        .context("failed to create poll")?;
+
+    Ok(())
 }
 
-async fn send_chat_message(
+async fn create_prediction_outcome(
     twitch: &Twitch,
-    event_data: &EventData,
-    data: EventOutcomeSendChat,
+    data: EventOutcomeCreatePrediction,
 ) -> anyhow::Result<()> {
-    let mut message = data.template;
+    twitch
+        .create_prediction(&data.title, &data.outcomes, data.duration)
+        .await
+        .context("failed to create prediction")?;
 
-    let user_name = event_data
-        .user
-        .as_ref()
-        .map(|user| user.name.to_string())
-        .unwrap_or_else(|| "Anonymous".to_string());
+    Ok(())
+}
 
-    message = message.replace("$(user)", user_name.as_str());
+async fn toggle_reward_outcome(
+    twitch: &Twitch,
+    data: EventOutcomeToggleReward,
+) -> anyhow::Result<()> {
+    let update = match data.action {
+        ToggleRewardAction::Enable => UpdateReward {
+            enabled: Some(true),
+            ..Default::default()
+        },
+        ToggleRewardAction::Disable => UpdateReward {
+            enabled: Some(false),
+            ..Default::default()
+        },
+        ToggleRewardAction::Pause => UpdateReward {
+            paused: Some(true),
+            ..Default::default()
+        },
+        ToggleRewardAction::Unpause => UpdateReward {
+            paused: Some(false),
+            ..Default::default()
+        },
+    };
 
-    match &event_data.input_data {
-        EventInputData::Redeem {
-            reward_name,
-            cost,
-            user_input,
-            ..
-        } => {
-            message = message.replace("$(userInput)", user_input.as_str());
-            message = message.replace("$(rewardName)", reward_name.as_str());
-            message = message.replace("$(rewardCost)", cost.to_string().as_str());
-        }
-        EventInputData::Bits {
-            bits,
-            message: user_input,
-            ..
-        } => {
-            message = message.replace("$(userInput)", user_input.to_string().as_str());
-            message = message.replace("$(bits)", bits.to_string().as_str());
-        }
-        EventInputData::AdBreakBegin { duration_seconds } => {
-            message = message.replace("$(duration)", duration_seconds.to_string().as_str());
-        }
-        EventInputData::Subscription { tier, .. } => {
-            message = message.replace("$(tier)", format_subscription_tier(tier));
-        }
-        EventInputData::GiftedSubscription { total, tier, .. } => {
-            message = message.replace("$(tier)", format_subscription_tier(tier));
-            message = message.replace("$(total)", total.to_string().as_str());
-        }
-        EventInputData::ReSubscription {
-            cumulative_months,
-            duration_months,
-            message: user_input,
-            tier,
-            ..
-        } => {
-            message = message.replace("$(tier)", format_subscription_tier(tier));
-            message = message.replace("$(userInput)", user_input.as_str());
-            message = message.replace(
-                "$(cumulativeMonths)",
-                cumulative_months.to_string().as_str(),
-            );
-            message = message.replace("$(durationMonths)", duration_months.to_string().as_str());
-        }
-        EventInputData::Chat {
-            message: user_input,
-            ..
-        } => {
-            message = message.replace("$(userInput)", user_input.as_str());
-        }
-        EventInputData::Raid { viewers } => {
-            message = message.replace("$(viewers)", viewers.to_string().as_str());
-        }
-        EventInputData::ShoutoutReceive { viewer_count } => {
-            message = message.replace("$(viewers)", viewer_count.to_string().as_str());
-        }
-        EventInputData::None => {}
-    }
+    twitch
+        .update_reward(&data.reward_id, update)
+        .await
+        .context("failed to update reward")?;
 
-    if message.len() < 500 {
-        twitch.send_chat_message(&message).await?;
-    } else {
-        let mut chars = message.chars();
+    Ok(())
+}
 
-        loop {
-            let message = chars.by_ref().take(500).collect::<String>();
-            if message.is_empty() {
-                break;
-            }
+async fn send_chat_message(
+    db: &DbPool,
+    twitch: &Twitch,
+    event_data: &EventData,
+    data: EventOutcomeSendChat,
+) -> anyhow::Result<()> {
+    let args = event_template_args(&event_data.input_data);
+    let ctx = TemplateContext {
+        db,
+        twitch,
+        user: event_data.user.as_ref(),
+        args: &args,
+        input_data: &event_data.input_data,
+        variables: &[],
+    };
 
-            twitch.send_chat_message(&message).await?;
-        }
-    }
+    let message = render_template(&data.template, &ctx).await;
+    twitch.send_chat_message_chunked(&message).await?;
 
     Ok(())
 }
@@ -270,6 +455,7 @@ fn get_event_data_input_amount(event_data: &EventData) -> Option<i64> {
         } => Some(*cumulative_months),
         EventInputData::Chat { cheer, .. } => cheer.map(|value| value as i64),
         EventInputData::Raid { viewers } => Some(*viewers),
+        EventInputData::HypeTrain { level, .. } => Some(*level),
 
         _ => None,
     }
@@ -472,3 +658,218 @@ pub fn create_default_bit_throwable(amount: i64) -> ItemsWithSounds {
         sounds: impact_sounds,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{execute_outcomes, resolve_random_outcome};
+    use crate::{
+        database::{
+            entity::events::{
+                EventConfig, EventModel, EventOutcome, EventOutcomeMode, EventOutcomeRandom,
+                EventOutcomeScript, EventOutcomeStep, EventOutcomeTriggerHotkey, EventTrigger,
+                WeightedEventOutcome,
+            },
+            mock_database,
+        },
+        events::matching::{EventData, EventInputData},
+        overlay::OverlayMessage,
+        script::runtime::ScriptExecutorHandle,
+        twitch::manager::Twitch,
+    };
+    use chrono::Utc;
+    use tokio::sync::{broadcast, mpsc};
+    use uuid::Uuid;
+
+    /// Creates an event that triggers a hotkey for each of the provided
+    /// (hotkey, delay) steps using the outcome `mode`
+    fn test_event(steps: &[(&str, u32)], mode: EventOutcomeMode) -> EventModel {
+        EventModel {
+            id: Uuid::new_v4(),
+            enabled: true,
+            name: "Test Event".to_string(),
+            config: EventConfig {
+                trigger: EventTrigger::Follow,
+                outcomes: steps
+                    .iter()
+                    .map(|(hotkey_id, delay)| EventOutcomeStep {
+                        delay: *delay,
+                        outcome: EventOutcome::TriggerHotkey(EventOutcomeTriggerHotkey {
+                            hotkey_id: hotkey_id.to_string(),
+                        }),
+                    })
+                    .collect(),
+                outcome_mode: mode,
+                ..Default::default()
+            },
+            order: 0,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Executes the outcomes of `event` returning the hotkeys that were
+    /// triggered in the order they were sent to the overlay
+    async fn execute_hotkeys(event: EventModel) -> Vec<String> {
+        let db = mock_database().await;
+        let (app_tx, _app_rx) = mpsc::unbounded_channel();
+        let twitch = Twitch::new(app_tx);
+        let script_handle = ScriptExecutorHandle::mock();
+        let (event_sender, mut event_rx) = broadcast::channel(16);
+
+        let event_data = EventData {
+            user: None,
+            input_data: EventInputData::None,
+        };
+
+        let (_picks, result) = execute_outcomes(
+            &db,
+            &twitch,
+            &script_handle,
+            &event_sender,
+            event,
+            &event_data,
+        )
+        .await;
+        result.unwrap();
+
+        let mut hotkeys = Vec::new();
+        while let Ok(msg) = event_rx.try_recv() {
+            if let OverlayMessage::TriggerHotkey { hotkey_id } = msg {
+                hotkeys.push(hotkey_id);
+            }
+        }
+        hotkeys
+    }
+
+    /// Tests that sequential steps run in order, each waiting for the
+    /// previous step before its own delay starts
+    #[tokio::test]
+    async fn test_sequential_outcome_order() {
+        let event = test_event(
+            &[("first", 100), ("second", 0), ("third", 10)],
+            EventOutcomeMode::Sequential,
+        );
+
+        let hotkeys = execute_hotkeys(event).await;
+        assert_eq!(hotkeys, vec!["first", "second", "third"]);
+    }
+
+    /// Tests that parallel steps start together, completing in the
+    /// order of their delays rather than their position
+    #[tokio::test]
+    async fn test_parallel_outcome_order() {
+        let event = test_event(
+            &[("first", 200), ("second", 0), ("third", 100)],
+            EventOutcomeMode::Parallel,
+        );
+
+        let hotkeys = execute_hotkeys(event).await;
+        assert_eq!(hotkeys, vec!["second", "third", "first"]);
+    }
+
+    fn hotkey(hotkey_id: &str) -> EventOutcome {
+        EventOutcome::TriggerHotkey(EventOutcomeTriggerHotkey {
+            hotkey_id: hotkey_id.to_string(),
+        })
+    }
+
+    /// Creates a random outcome from (weight, outcome) pairs
+    fn random(outcomes: Vec<(u32, EventOutcome)>) -> EventOutcome {
+        EventOutcome::Random(EventOutcomeRandom {
+            outcomes: outcomes
+                .into_iter()
+                .map(|(weight, outcome)| WeightedEventOutcome { weight, outcome })
+                .collect(),
+        })
+    }
+
+    /// Tests that outcomes with no weight are never picked
+    #[test]
+    fn test_resolve_random_weighted() {
+        let outcome = random(vec![(0, hotkey("never")), (1, hotkey("always"))]);
+
+        for _ in 0..20 {
+            let (outcome, path) = resolve_random_outcome(outcome.clone()).unwrap();
+            assert!(matches!(
+                outcome,
+                EventOutcome::TriggerHotkey(EventOutcomeTriggerHotkey { hotkey_id })
+                    if hotkey_id == "always"
+            ));
+            assert_eq!(path, vec![1]);
+        }
+    }
+
+    /// Tests that random outcomes without any weighted outcomes fail to resolve
+    #[test]
+    fn test_resolve_random_no_weights() {
+        let outcome = random(vec![(0, hotkey("first")), (0, hotkey("second"))]);
+        assert!(resolve_random_outcome(outcome).is_err());
+
+        assert!(resolve_random_outcome(random(vec![])).is_err());
+    }
+
+    /// Tests that nested random outcomes resolve to a concrete outcome,
+    /// recording the index picked at each level
+    #[test]
+    fn test_resolve_random_nested() {
+        let outcome = random(vec![
+            (0, hotkey("outer")),
+            (1, random(vec![(0, hotkey("never")), (1, hotkey("inner"))])),
+        ]);
+
+        let (outcome, path) = resolve_random_outcome(outcome).unwrap();
+        assert!(matches!(
+            outcome,
+            EventOutcome::TriggerHotkey(EventOutcomeTriggerHotkey { hotkey_id })
+                if hotkey_id == "inner"
+        ));
+        assert_eq!(path, vec![1, 1]);
+
+        // Non random outcomes are returned as is
+        let (_outcome, path) = resolve_random_outcome(hotkey("plain")).unwrap();
+        assert!(path.is_empty());
+    }
+
+    /// Tests that random picks are returned when the picked outcome fails
+    #[tokio::test]
+    async fn test_random_picks_on_error() {
+        let db = mock_database().await;
+        let (app_tx, _app_rx) = mpsc::unbounded_channel();
+        let twitch = Twitch::new(app_tx);
+        let script_handle = ScriptExecutorHandle::mock();
+        let (event_sender, _event_rx) = broadcast::channel(16);
+
+        // Mock executor has no workers to run the script on
+        let mut event = test_event(&[], EventOutcomeMode::Sequential);
+        event.config.outcomes = vec![EventOutcomeStep {
+            delay: 0,
+            outcome: random(vec![(
+                1,
+                EventOutcome::Script(EventOutcomeScript {
+                    script: String::new(),
+                    permissions: None,
+                    limits: Default::default(),
+                }),
+            )]),
+        }];
+
+        let event_data = EventData {
+            user: None,
+            input_data: EventInputData::None,
+        };
+
+        let (picks, result) = execute_outcomes(
+            &db,
+            &twitch,
+            &script_handle,
+            &event_sender,
+            event,
+            &event_data,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(picks.len(), 1);
+        assert_eq!(picks[0].step, 0);
+        assert_eq!(picks[0].path, vec![0]);
+    }
+}
@@ -0,0 +1,197 @@
+//! # Pause
+//!
+//! Global switch for stopping all event and command processing, while
+//! paused matched events are either dropped or buffered to run once
+//! processing is resumed (Depending on the configured pause mode)
+
+use log::{debug, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+
+use super::{AppEvent, AppEventSender, matching::EventMatchingData, queue::EventQueue};
+
+/// Maximum number of matched events to keep buffered while paused,
+/// the oldest events are dropped once the limit is reached
+const MAX_BUFFERED_EVENTS: usize = 100;
+
+/// Name of the event emitted to the app when the pause state changes
+pub const PAUSE_CHANGED_EVENT: &str = "processing_pause_changed";
+
+/// Snapshot of the current pause state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseSnapshot {
+    /// Whether processing is paused
+    pub paused: bool,
+    /// Number of events buffered to run when resumed
+    pub buffered: usize,
+}
+
+#[derive(Clone)]
+pub struct ProcessingPause {
+    inner: Arc<ProcessingPauseInner>,
+}
+
+struct ProcessingPauseInner {
+    state: Mutex<ProcessingPauseState>,
+    /// Sender for notifying the event processor of resumes
+    event_tx: AppEventSender,
+    /// Event queue, paused along with processing so queued
+    /// events do not keep playing while paused
+    event_queue: EventQueue,
+    /// App handle for notifying the frontend of changes
+    /// (Not present when mocked)
+    app_handle: Option<AppHandle>,
+}
+
+#[derive(Default)]
+struct ProcessingPauseState {
+    /// Whether processing is paused
+    paused: bool,
+    /// Matched events waiting for processing to resume
+    buffered: Vec<EventMatchingData>,
+}
+
+impl ProcessingPause {
+    pub fn new(app_handle: AppHandle, event_tx: AppEventSender, event_queue: EventQueue) -> Self {
+        Self::with_app_handle(Some(app_handle), event_tx, event_queue)
+    }
+
+    /// Creates a pause that does not notify the frontend of changes
+    #[cfg(test)]
+    pub fn mock(event_tx: AppEventSender, event_queue: EventQueue) -> Self {
+        Self::with_app_handle(None, event_tx, event_queue)
+    }
+
+    fn with_app_handle(
+        app_handle: Option<AppHandle>,
+        event_tx: AppEventSender,
+        event_queue: EventQueue,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ProcessingPauseInner {
+                state: Default::default(),
+                event_tx,
+                event_queue,
+                app_handle,
+            }),
+        }
+    }
+
+    /// Checks whether processing is currently paused
+    pub fn is_paused(&self) -> bool {
+        self.inner.state.lock().paused
+    }
+
+    /// Creates a snapshot of the current pause state
+    pub fn snapshot(&self) -> PauseSnapshot {
+        let state = &*self.inner.state.lock();
+        PauseSnapshot {
+            paused: state.paused,
+            buffered: state.buffered.len(),
+        }
+    }
+
+    /// Pauses or resumes processing, resuming will trigger any
+    /// buffered events to run
+    ///
+    /// The event queue is paused along with processing, events that are
+    /// currently playing are allowed to finish
+    pub fn set_paused(&self, paused: bool) {
+        let resume_buffered = {
+            let state = &mut *self.inner.state.lock();
+            if state.paused == paused {
+                return;
+            }
+
+            state.paused = paused;
+            !paused && !state.buffered.is_empty()
+        };
+
+        debug!("event processing paused: {paused}");
+
+        self.inner.event_queue.set_processing_paused(paused);
+
+        if resume_buffered {
+            _ = self.inner.event_tx.send(AppEvent::ProcessingResumed);
+        }
+
+        self.emit_changed();
+    }
+
+    /// Buffers matched event data to run when processing is resumed
+    pub fn buffer(&self, match_data: EventMatchingData) {
+        {
+            let state = &mut *self.inner.state.lock();
+            if state.buffered.len() >= MAX_BUFFERED_EVENTS {
+                warn!("pause buffer is full, dropping oldest buffered event");
+                state.buffered.remove(0);
+            }
+
+            state.buffered.push(match_data);
+        }
+
+        self.emit_changed();
+    }
+
+    /// Takes all the currently buffered events
+    pub fn take_buffered(&self) -> Vec<EventMatchingData> {
+        let buffered = std::mem::take(&mut self.inner.state.lock().buffered);
+        if !buffered.is_empty() {
+            self.emit_changed();
+        }
+        buffered
+    }
+
+    /// Notifies the frontend that the pause state has changed
+    fn emit_changed(&self) {
+        if let Some(app_handle) = self.inner.app_handle.as_ref() {
+            _ = app_handle.emit(PAUSE_CHANGED_EVENT, self.snapshot());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::sync::mpsc;
+
+    use super::ProcessingPause;
+    use crate::events::{AppEvent, queue::EventQueue};
+
+    /// Tests that pausing processing pauses the event queue
+    #[test]
+    fn test_pause_event_queue() {
+        let event_queue = EventQueue::mock();
+        let pause = ProcessingPause::mock(mpsc::unbounded_channel().0, event_queue.clone());
+
+        pause.set_paused(true);
+        assert!(pause.is_paused());
+        assert!(event_queue.snapshot().processing_paused);
+
+        pause.set_paused(false);
+        assert!(!pause.is_paused());
+        assert!(!event_queue.snapshot().processing_paused);
+    }
+
+    /// Tests that resuming only notifies the processor when
+    /// events were buffered
+    #[test]
+    fn test_resume_buffered() {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let pause = ProcessingPause::mock(event_tx, EventQueue::mock());
+
+        pause.set_paused(true);
+        pause.set_paused(false);
+        assert!(event_rx.try_recv().is_err());
+
+        pause.set_paused(true);
+        pause.buffer(Default::default());
+        pause.set_paused(false);
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(AppEvent::ProcessingResumed)
+        ));
+        assert_eq!(pause.take_buffered().len(), 1);
+    }
+}
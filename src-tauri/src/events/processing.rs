@@ -1,25 +1,37 @@
 use crate::{
     database::{
         entity::{
+            app_data::{AppDataModel, PauseMode},
             command_execution::{
                 CommandExecutionMetadata, CommandExecutionModel, CreateCommandExecution,
             },
             commands::{CommandModel, CommandOutcome},
             event_execution::{CreateEventExecution, EventExecutionMetadata, EventExecutionModel},
-            events::EventModel,
-            shared::MinimumRequireRole,
+            events::{
+                EventModel, EventQueueConfig, EventTriggerType, HypeTrainPhase, PollPhase,
+                PredictionPhase,
+            },
+            shared::{MinimumRequireRole, UsageLimit, UsageLimitWindow},
         },
         DbPool,
     },
     events::{
+        arguments::command_usage,
+        counter::{execute_counter_command, CounterRoles},
+        feedback::{remaining_seconds, send_feedback_message},
         matching::{
-            match_ad_break_event, match_chat_event, match_cheer_bits_event, match_follow_event,
-            match_gifted_subscription_event, match_raid_event, match_re_subscription_event,
-            match_redeem_event, match_shoutout_receive_event, match_subscription_event,
-            match_timer_complete_event, CommandWithContext, EventData, EventInputData,
+            is_event_conditions_met, match_ad_break_event, match_chat_event,
+            match_cheer_bits_event, match_follow_event, match_gifted_subscription_event,
+            match_hype_train_event, match_poll_event, match_prediction_event, match_raid_event,
+            match_re_subscription_event, match_redeem_event, match_shoutout_receive_event,
+            match_stream_status_event, match_subscription_event, match_timer_complete_event,
+            strip_command_prefix, CommandWithContext, ConditionInput, EventData, EventInputData,
             EventMatchingData,
         },
-        outcome::produce_outcome_message,
+        outcome::execute_outcomes,
+        queue::{EventQueue, QueuedEvent, QueuedEventInfo},
+        redemption::{is_update_redemption, RedemptionGuard},
+        template::{render_template, TemplateContext},
         AppEvent, TwitchEventUser,
     },
     overlay::OverlayMessageSender,
@@ -32,12 +44,20 @@ use anyhow::{anyhow, Context};
 use chrono::{DateTime, TimeDelta, Utc};
 use futures::{future::BoxFuture, stream::FuturesUnordered, Stream};
 use log::{debug, error};
-use std::{future::poll_fn, task::Poll, time::Duration};
+use std::{borrow::Cow, future::poll_fn, task::Poll, time::Duration};
 use tauri::{AppHandle, Emitter};
 use tokio::try_join;
 use twitch_api::types::UserId;
+use uuid::Uuid;
+
+use super::{pause::ProcessingPause, AppEventReceiver, TwitchEventChatMsg};
+
+/// Name of the chat command used by moderators to pause and resume
+/// processing, used with any of the configured command prefixes
+const PAUSE_COMMAND_NAME: &str = "vtftk";
 
-use super::AppEventReceiver;
+/// Reply sent when a chat message was close to matching a command
+const COMMAND_SUGGESTION_MESSAGE: &str = "@$(user) did you mean $(command)?";
 
 pub async fn process_events(
     db: DbPool,
@@ -45,6 +65,8 @@ pub async fn process_events(
     script_handle: ScriptExecutorHandle,
     event_sender: OverlayMessageSender,
     app_handle: AppHandle,
+    event_queue: EventQueue,
+    pause: ProcessingPause,
 
     mut event_rx: AppEventReceiver,
 ) {
@@ -70,6 +92,8 @@ pub async fn process_events(
                 &script_handle,
                 &event_sender,
                 &app_handle,
+                &event_queue,
+                &pause,
                 event,
             ));
         }
@@ -86,27 +110,130 @@ pub async fn process_events(
     .await;
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_event(
     db: &DbPool,
     twitch: &Twitch,
     script_handle: &ScriptExecutorHandle,
     event_sender: &OverlayMessageSender,
     app_handle: &AppHandle,
+    event_queue: &EventQueue,
+    pause: &ProcessingPause,
     event: AppEvent,
 ) -> anyhow::Result<()> {
-    let match_data: EventMatchingData = match event {
+    // Timers are not buffered while paused, they will run again on their next schedule
+    let bufferable = !matches!(event, AppEvent::TimerCompleted(_));
+
+    // Whether a matched event would be dropped due to the pause
+    let drop_paused = pause.is_paused() && (!bufferable || is_pause_mode_drop(db).await);
+
+    // Skip matching events that would be dropped, matching has side effects
+    if drop_paused && is_skipped_while_paused(&event) {
+        debug!("processing is paused, dropping event");
+        return Ok(());
+    }
+
+    let mut match_data: EventMatchingData = match event {
         // Matchable events
-        AppEvent::Redeem(event) => match_redeem_event(db, event).await?,
+        AppEvent::Redeem(event) => {
+            let mut match_data = match_redeem_event(db, event).await?;
+
+            // Redemption is refunded if the events updating it are dropped without succeeding
+            match_data.redemption =
+                RedemptionGuard::for_events(twitch, &match_data.events, &match_data.event_data);
+
+            match_data
+        }
         AppEvent::CheerBits(event) => match_cheer_bits_event(db, event).await?,
         AppEvent::Follow(event) => match_follow_event(db, event).await?,
         AppEvent::Sub(event) => match_subscription_event(db, event).await?,
         AppEvent::GiftSub(event) => match_gifted_subscription_event(db, event).await?,
         AppEvent::ResubMsg(event) => match_re_subscription_event(db, event).await?,
-        AppEvent::ChatMsg(event) => match_chat_event(db, event).await?,
+        AppEvent::ChatMsg(event) => {
+            let main_config = AppDataModel::get_main_config(db).await?;
+
+            // Pause commands must be handled even while paused
+            if handle_pause_command(twitch, pause, &main_config.command_prefixes, &event).await {
+                return Ok(());
+            }
+
+            // Messages are not stored in the chat history or viewer list
+            // when they would be dropped
+            if drop_paused {
+                debug!("processing is paused, dropping chat message");
+                return Ok(());
+            }
+
+            let mut match_data = match_chat_event(db, event, twitch.session_started_at()).await?;
+
+            // Suggestions are not sent while paused
+            if pause.is_paused() {
+                match_data.command_suggestion = None;
+            }
+
+            if let (Some(suggestion), Some(user)) = (
+                match_data.command_suggestion.take(),
+                match_data.event_data.user.as_ref(),
+            ) {
+                send_feedback_message(
+                    db,
+                    twitch,
+                    suggestion.command_id,
+                    Some(COMMAND_SUGGESTION_MESSAGE),
+                    user,
+                    &match_data.event_data.input_data,
+                    &[("command", suggestion.trigger)],
+                )
+                .await;
+            }
+
+            match_data
+        }
         AppEvent::Raid(event) => match_raid_event(db, event).await?,
         AppEvent::AdBreakBegin(event) => match_ad_break_event(db, event).await?,
         AppEvent::ShoutoutReceive(event) => match_shoutout_receive_event(db, event).await?,
+        AppEvent::HypeTrainBegin(event) => {
+            match_hype_train_event(db, HypeTrainPhase::Begin, event).await?
+        }
+        AppEvent::HypeTrainProgress(event) => {
+            match_hype_train_event(db, HypeTrainPhase::Progress, event).await?
+        }
+        AppEvent::HypeTrainEnd(event) => {
+            match_hype_train_event(db, HypeTrainPhase::End, event).await?
+        }
+        AppEvent::PollBegin(event) => match_poll_event(db, PollPhase::Begin, event).await?,
+        AppEvent::PollProgress(event) => match_poll_event(db, PollPhase::Progress, event).await?,
+        AppEvent::PollEnd(event) => match_poll_event(db, PollPhase::End, event).await?,
+        AppEvent::PredictionBegin(event) => {
+            match_prediction_event(db, PredictionPhase::Begin, event).await?
+        }
+        AppEvent::PredictionProgress(event) => {
+            match_prediction_event(db, PredictionPhase::Progress, event).await?
+        }
+        AppEvent::PredictionLock(event) => {
+            match_prediction_event(db, PredictionPhase::Lock, event).await?
+        }
+        AppEvent::PredictionEnd(event) => {
+            match_prediction_event(db, PredictionPhase::End, event).await?
+        }
         AppEvent::TimerCompleted(event) => match_timer_complete_event(db, twitch, event).await?,
+        AppEvent::StreamOnline => {
+            // Live state is tracked even while paused
+            twitch.set_live(true);
+            if drop_paused {
+                return Ok(());
+            }
+
+            match_stream_status_event(db, EventTriggerType::StreamOnline).await?
+        }
+        AppEvent::StreamOffline => {
+            twitch.set_live(false);
+            if drop_paused {
+                return Ok(());
+            }
+
+            match_stream_status_event(db, EventTriggerType::StreamOffline).await?
+        }
 
         // Internal events
         AppEvent::ModeratorsChanged => {
@@ -138,8 +265,69 @@ async fn process_event(
             _ = app_handle.emit("authenticated", ());
             return Ok(());
         }
+        AppEvent::ProcessingResumed => {
+            let buffered = pause.take_buffered();
+            debug!("running {} buffered events", buffered.len());
+
+            let futures = buffered.into_iter().map(|match_data| {
+                execute_matched(
+                    db,
+                    twitch,
+                    script_handle,
+                    event_sender,
+                    event_queue,
+                    match_data,
+                )
+            });
+
+            futures::future::join_all(futures).await;
+            return Ok(());
+        }
     };
 
+    // Remove events whose conditions are not met by the input data
+    let condition_input = ConditionInput::new(&match_data.event_data.input_data);
+    match_data
+        .events
+        .retain(|event| is_event_conditions_met(&event.config.conditions, &condition_input));
+
+    if match_data.events.is_empty() && match_data.commands.is_empty() {
+        return Ok(());
+    }
+
+    // Processing may have been paused while matching
+    if pause.is_paused() {
+        if bufferable && !is_pause_mode_drop(db).await {
+            pause.buffer(match_data);
+        } else {
+            debug!("processing is paused, dropping matched event");
+        }
+
+        return Ok(());
+    }
+
+    execute_matched(
+        db,
+        twitch,
+        script_handle,
+        event_sender,
+        event_queue,
+        match_data,
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Executes the commands and events from matched event data
+async fn execute_matched(
+    db: &DbPool,
+    twitch: &Twitch,
+    script_handle: &ScriptExecutorHandle,
+    event_sender: &OverlayMessageSender,
+    event_queue: &EventQueue,
+    match_data: EventMatchingData,
+) {
     let command_futures =
         match_data
             .commands
@@ -154,20 +342,60 @@ async fn process_event(
                 ))
             });
 
-    let event_futures =
-        match_data
-            .events
-            .into_iter()
-            .map(|event| -> BoxFuture<'_, anyhow::Result<()>> {
-                Box::pin(execute_event(
+    let event_data = &match_data.event_data;
+    let mut captures = match_data.captures;
+    let mut events = Vec::with_capacity(match_data.events.len());
+
+    for event in match_data.events {
+        // Chat pattern events get their own copy of the event data with their captures
+        let event_data = match captures.remove(&event.id) {
+            Some(captures) => Cow::Owned(event_data.with_captures(captures)),
+            None => Cow::Borrowed(event_data),
+        };
+
+        // Only events that update the redemption share its guard
+        let redemption = if is_update_redemption(&event) {
+            match_data.redemption.clone()
+        } else {
+            None
+        };
+
+        // Queued events are played through the event queue
+        match event.config.queue.clone() {
+            Some(config) => queue_event(
+                db,
+                twitch,
+                script_handle,
+                event_sender,
+                event_queue,
+                event,
+                event_data.into_owned(),
+                redemption,
+                config,
+            ),
+            None => events.push((event, event_data, redemption)),
+        }
+    }
+
+    // Release the matched redemption, the remaining guards are held by the events
+    drop(match_data.redemption);
+
+    let event_futures = events.into_iter().map(
+        |(event, event_data, redemption)| -> BoxFuture<'_, anyhow::Result<()>> {
+            Box::pin(async move {
+                execute_event(
                     db,
                     twitch,
                     script_handle,
                     event_sender,
                     event,
-                    &match_data.event_data,
-                ))
-            });
+                    &event_data,
+                    redemption,
+                )
+                .await
+            })
+        },
+    );
 
     let mut futures = command_futures
         .chain(event_futures)
@@ -180,33 +408,175 @@ async fn process_event(
             error!("error while executing event outcome: {err:?}");
         }
     }
+}
 
-    Ok(())
+/// Checks if the configured pause mode drops matched events
+async fn is_pause_mode_drop(db: &DbPool) -> bool {
+    AppDataModel::get_main_config(db)
+        .await
+        .map(|config| matches!(config.pause_mode, PauseMode::Drop))
+        .unwrap_or_default()
 }
 
-pub fn is_cooldown_elapsed(
+/// Checks if the event can be skipped without matching when processing is
+/// paused and matched events would be dropped
+///
+/// Redeems are still matched so their redemption can be refunded, chat
+/// messages are checked for pause commands and stream status changes
+/// still update the live state
+fn is_skipped_while_paused(event: &AppEvent) -> bool {
+    !matches!(
+        event,
+        AppEvent::Redeem(_)
+            | AppEvent::ChatMsg(_)
+            | AppEvent::StreamOnline
+            | AppEvent::StreamOffline
+            | AppEvent::ModeratorsChanged
+            | AppEvent::VipsChanged
+            | AppEvent::RewardsChanged
+            | AppEvent::TwitchClientReset
+            | AppEvent::TwitchClientLoggedOut
+            | AppEvent::TwitchClientLoggedIn
+            | AppEvent::ProcessingResumed
+    )
+}
+
+/// Parses the built in pause command (i.e `!vtftk pause` or `!vtftk resume`)
+/// which can be used with any of the command `prefixes`
+///
+/// Returns whether the command pauses processing, None when the message
+/// is not a pause command
+fn parse_pause_command(prefixes: &[String], message: &str) -> Option<bool> {
+    let mut parts = message.split_whitespace();
+    let command = strip_command_prefix(parts.next()?, prefixes)?;
+    if !command.eq_ignore_ascii_case(PAUSE_COMMAND_NAME) {
+        return None;
+    }
+
+    match parts.next()?.to_lowercase().as_str() {
+        "pause" => Some(true),
+        "resume" | "unpause" => Some(false),
+        _ => None,
+    }
+}
+
+/// Handles the built in pause and resume chat commands used by
+/// moderators to pause and resume processing
+///
+/// Returns whether the message was a pause command
+async fn handle_pause_command(
+    twitch: &Twitch,
+    pause: &ProcessingPause,
+    prefixes: &[String],
+    event: &TwitchEventChatMsg,
+) -> bool {
+    let Some(paused) = parse_pause_command(prefixes, &event.message.text) else {
+        return false;
+    };
+
+    if !has_required_role(twitch, Some(&event.user_id), &MinimumRequireRole::Mod).await {
+        debug!("user without mod role attempted to use pause command");
+        return true;
+    }
+
+    pause.set_paused(paused);
+
+    let message = if paused {
+        "VTFTK has been paused"
+    } else {
+        "VTFTK has been resumed"
+    };
+
+    if let Err(err) = twitch.send_chat_message(message).await {
+        error!("failed to send pause command response: {err:?}");
+    }
+
+    true
+}
+
+/// Adds an event to the event queue, the event will execute once
+/// the events before it in the queue have finished
+#[allow(clippy::too_many_arguments)]
+fn queue_event(
+    db: &DbPool,
+    twitch: &Twitch,
+    script_handle: &ScriptExecutorHandle,
+    event_sender: &OverlayMessageSender,
+    event_queue: &EventQueue,
+    event: EventModel,
+    event_data: EventData,
+    redemption: Option<RedemptionGuard>,
+    config: EventQueueConfig,
+) {
+    // Events without a shared queue are only queued against themselves
+    let name = config.name.unwrap_or_else(|| event.id.to_string());
+
+    let info = QueuedEventInfo {
+        id: Uuid::new_v4(),
+        event_id: event.id,
+        event_name: event.name.clone(),
+        user: event_data.user.clone(),
+        queued_at: Utc::now(),
+    };
+
+    let db = db.clone();
+    let twitch = twitch.clone();
+    let script_handle = script_handle.clone();
+    let event_sender = event_sender.clone();
+
+    let future = Box::pin(async move {
+        execute_event(
+            &db,
+            &twitch,
+            &script_handle,
+            &event_sender,
+            event,
+            &event_data,
+            redemption,
+        )
+        .await
+    });
+
+    event_queue.push(
+        name,
+        QueuedEvent {
+            info,
+            gap: config.gap,
+            future,
+        },
+    );
+}
+
+/// Determines the time remaining on a cooldown, [None] when the cooldown has elapsed
+pub fn cooldown_remaining(
     execution_time: DateTime<Utc>,
     current_time: DateTime<Utc>,
     cooldown: u32,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<Option<TimeDelta>> {
     let cooldown_end_time = execution_time
         .checked_add_signed(TimeDelta::milliseconds(cooldown as i64))
         .context("cooldown finishes too far in the future to compute")?;
 
-    Ok(current_time > cooldown_end_time)
+    if current_time > cooldown_end_time {
+        return Ok(None);
+    }
+
+    Ok(Some(cooldown_end_time - current_time))
 }
 
-pub async fn is_command_cooldown_elapsed(
+/// Determines the time remaining on the cooldown of a command for
+/// the provided user, [None] when the cooldown has elapsed
+pub async fn command_cooldown_remaining(
     db: &DbPool,
     command: &CommandModel,
     user_id: &UserId,
     current_time: DateTime<Utc>,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<Option<TimeDelta>> {
     let cooldown = &command.config.cooldown;
 
     // No cooldown enabled
     if !cooldown.enabled {
-        return Ok(true);
+        return Ok(None);
     }
 
     // Handle global cooldown (Check last execution)
@@ -217,48 +587,22 @@ pub async fn is_command_cooldown_elapsed(
 
         let last_execution = match last_execution {
             Some(value) => value,
-            None => return Ok(true),
+            None => return Ok(None),
         };
 
-        return is_cooldown_elapsed(last_execution.created_at, current_time, cooldown.duration);
+        return cooldown_remaining(last_execution.created_at, current_time, cooldown.duration);
     }
 
-    let mut offset = 0;
-
-    loop {
-        let last_execution = CommandExecutionModel::last(db, command.id, offset)
-            .await
-            .context("failed to request last execution for command")?;
-
-        // Cooldown elapsed if no more to check
-        let last_execution = match last_execution {
-            Some(value) => value,
-            None => return Ok(true),
-        };
-
-        // Check if the execution has elapsed the cooldown
-        let last_execution_elapsed =
-            is_cooldown_elapsed(last_execution.created_at, current_time, cooldown.duration)?;
-
-        // Execution was not from the target user
-        if last_execution
-            .metadata
-            .user
-            .is_none_or(|event_user| event_user.id != user_id)
-        {
-            offset += 1;
-
-            // If the execution had elapsed the cooldown we can treat the rest as elapsed too (We can skip checking the rest)
-            if last_execution_elapsed {
-                return Ok(true);
-            }
+    let last_execution = CommandExecutionModel::last_by_user(db, command.id, user_id.as_str())
+        .await
+        .context("failed to request last user execution for command")?;
 
-            continue;
-        }
+    let last_execution = match last_execution {
+        Some(value) => value,
+        None => return Ok(None),
+    };
 
-        // Found an execution
-        return Ok(last_execution_elapsed);
-    }
+    cooldown_remaining(last_execution.created_at, current_time, cooldown.duration)
 }
 
 pub async fn execute_command(
@@ -282,16 +626,84 @@ pub async fn execute_command(
         None => return Err(anyhow!("got chat event without a user")),
     };
 
+    // Ensure the stream is live for live only commands
+    if command.command.config.live_only && !twitch.is_live() {
+        debug!("skipping command: stream is not live");
+        return Ok(());
+    }
+
     // Ensure required role is present
     if !has_required_role(twitch, Some(&user.id), &command.command.config.require_role).await {
         debug!("skipping command: missing required role");
+        send_feedback_message(
+            db,
+            twitch,
+            command.command.id,
+            command.command.config.require_role_message.as_deref(),
+            &user,
+            &event_data.input_data,
+            &[],
+        )
+        .await;
         return Ok(());
     }
 
+    // Ensure the arguments match the command arguments schema
+    let arguments = match command.arguments {
+        Ok(value) => value,
+        Err(err) => {
+            debug!("skipping command: invalid arguments: {err}");
+            let usage = command_usage(&command.command.command, &command.command.config.arguments);
+            let message = format!("{err}, usage: {usage}");
+            send_feedback_message(
+                db,
+                twitch,
+                command.command.id,
+                Some(&message),
+                &user,
+                &event_data.input_data,
+                &[],
+            )
+            .await;
+            return Ok(());
+        }
+    };
+
     let current_time = Utc::now();
 
-    if !is_command_cooldown_elapsed(db, &command.command, &user.id, current_time).await? {
+    if let Some(remaining) =
+        command_cooldown_remaining(db, &command.command, &user.id, current_time).await?
+    {
         debug!("skipping command: cooldown");
+        send_feedback_message(
+            db,
+            twitch,
+            command.command.id,
+            command.command.config.cooldown_message.as_deref(),
+            &user,
+            &event_data.input_data,
+            &[("remaining", remaining_seconds(remaining))],
+        )
+        .await;
+        return Ok(());
+    }
+
+    // Ensure no usage limits have been reached
+    if let Some(limit) =
+        find_command_usage_limit_reached(db, twitch, &command.command, &user.id, current_time)
+            .await?
+    {
+        debug!("skipping command: usage limit reached");
+        send_feedback_message(
+            db,
+            twitch,
+            command.command.id,
+            command.command.config.usage_limit_message.as_deref(),
+            &user,
+            &event_data.input_data,
+            &[("limit", limit.max_uses.to_string())],
+        )
+        .await;
         return Ok(());
     }
 
@@ -307,18 +719,48 @@ pub async fn execute_command(
 
     match command.command.config.outcome {
         CommandOutcome::Template { message } => {
-            let to_usr = command
-                .args
-                .first()
-                .map(|value| value.as_str())
-                .unwrap_or_default();
-            let message = message
-                .replace("$(user)", user.name.as_str())
-                .replace("$(touser)", to_usr);
+            let ctx = TemplateContext {
+                db,
+                twitch,
+                user: Some(&user),
+                args: &command.args,
+                input_data: &event_data.input_data,
+                variables: &[],
+            };
 
+            let message = render_template(&message, &ctx).await;
             twitch.send_chat_message_chunked(&message).await?;
         }
-        CommandOutcome::Script { script } => {
+        CommandOutcome::Counter {
+            counter,
+            message,
+            increment_role,
+            decrement_role,
+            reset_role,
+        } => {
+            execute_counter_command(
+                db,
+                twitch,
+                command.command.id,
+                &user,
+                &command.args,
+                &event_data.input_data,
+                &counter,
+                &message,
+                CounterRoles {
+                    increment: &increment_role,
+                    decrement: &decrement_role,
+                    reset: &reset_role,
+                },
+                command.command.config.require_role_message.as_deref(),
+            )
+            .await?;
+        }
+        CommandOutcome::Script {
+            script,
+            permissions,
+            limits,
+        } => {
             let user = CommandContextUser {
                 id: user.id,
                 name: user.name,
@@ -331,6 +773,7 @@ pub async fn execute_command(
                 input_data: event_data.input_data.clone(),
                 message: command.message,
                 args: command.args,
+                arguments,
                 user,
             };
 
@@ -340,6 +783,8 @@ pub async fn execute_command(
                         command_id: command.command.id,
                     },
                     script,
+                    permissions,
+                    limits,
                     ctx,
                 )
                 .await?;
@@ -361,17 +806,19 @@ pub async fn execute_command(
     Ok(())
 }
 
-pub async fn is_event_cooldown_elapsed(
+/// Determines the time remaining on the cooldown of an event for
+/// the provided user, [None] when the cooldown has elapsed
+pub async fn event_cooldown_remaining(
     db: &DbPool,
     event: &EventModel,
     user: Option<&TwitchEventUser>,
     current_time: DateTime<Utc>,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<Option<TimeDelta>> {
     let cooldown = &event.config.cooldown;
 
     // No cooldown enabled
     if !cooldown.enabled {
-        return Ok(true);
+        return Ok(None);
     }
 
     // Handle global cooldown (Check last execution)
@@ -382,56 +829,32 @@ pub async fn is_event_cooldown_elapsed(
 
         let last_execution = match last_execution {
             Some(value) => value,
-            None => return Ok(true),
+            None => return Ok(None),
         };
 
-        return is_cooldown_elapsed(last_execution.created_at, current_time, cooldown.duration);
+        return cooldown_remaining(last_execution.created_at, current_time, cooldown.duration);
     }
 
     let user = match user {
         Some(user) => user,
         // Anonymous users bypass the cooldown
-        None => return Ok(true),
+        None => return Ok(None),
     };
 
-    let mut offset = 0;
+    let last_execution = EventExecutionModel::last_by_user(db, event.id, user.id.as_str())
+        .await
+        .context("failed to request last user execution for event")?;
 
-    loop {
-        let last_execution = EventExecutionModel::last(db, event.id, offset)
-            .await
-            .context("failed to request last execution for event")?;
-
-        // Cooldown elapsed if no more to check
-        let last_execution = match last_execution {
-            Some(value) => value,
-            None => return Ok(true),
-        };
-
-        // Check if the execution has elapsed the cooldown
-        let last_execution_elapsed =
-            is_cooldown_elapsed(last_execution.created_at, current_time, cooldown.duration)?;
-
-        // Execution was not from the target user
-        if last_execution
-            .metadata
-            .user
-            .is_none_or(|event_user| event_user.id != user.id)
-        {
-            offset += 1;
-
-            // If the execution had elapsed the cooldown we can treat the rest as elapsed too (We can skip checking the rest)
-            if last_execution_elapsed {
-                return Ok(true);
-            }
-
-            continue;
-        }
+    let last_execution = match last_execution {
+        Some(value) => value,
+        None => return Ok(None),
+    };
 
-        // Found an execution
-        return Ok(last_execution_elapsed);
-    }
+    cooldown_remaining(last_execution.created_at, current_time, cooldown.duration)
 }
 
+/// Executes a matched event, `redemption` is fulfilled when the outcomes
+/// succeed and is otherwise refunded once dropped
 pub async fn execute_event(
     db: &DbPool,
     twitch: &Twitch,
@@ -440,7 +863,14 @@ pub async fn execute_event(
     event_sender: &OverlayMessageSender,
     event: EventModel,
     event_data: &EventData,
+    redemption: Option<RedemptionGuard>,
 ) -> anyhow::Result<()> {
+    // Ensure the stream is live for live only events
+    if event.config.live_only && !twitch.is_live() {
+        debug!("skipping event: stream is not live");
+        return Ok(());
+    }
+
     // Ensure required role is present
     if !has_required_role(
         twitch,
@@ -450,19 +880,68 @@ pub async fn execute_event(
     .await
     {
         debug!("skipping event: missing required role");
+        if let Some(user) = event_data.user.as_ref() {
+            send_feedback_message(
+                db,
+                twitch,
+                event.id,
+                event.config.require_role_message.as_deref(),
+                user,
+                &event_data.input_data,
+                &[],
+            )
+            .await;
+        }
         return Ok(());
     }
 
     let current_time = Utc::now();
 
     // Ensure cooldown is not active
-    if !is_event_cooldown_elapsed(db, &event, event_data.user.as_ref(), current_time).await? {
+    if let Some(remaining) =
+        event_cooldown_remaining(db, &event, event_data.user.as_ref(), current_time).await?
+    {
         debug!("skipping event: cooldown");
+        if let Some(user) = event_data.user.as_ref() {
+            send_feedback_message(
+                db,
+                twitch,
+                event.id,
+                event.config.cooldown_message.as_deref(),
+                user,
+                &event_data.input_data,
+                &[("remaining", remaining_seconds(remaining))],
+            )
+            .await;
+        }
+        return Ok(());
+    }
+
+    // Ensure no usage limits have been reached
+    if let Some(limit) =
+        find_event_usage_limit_reached(db, twitch, &event, event_data.user.as_ref(), current_time)
+            .await?
+    {
+        debug!("skipping event: usage limit reached");
+
+        if let Some(user) = event_data.user.as_ref() {
+            send_feedback_message(
+                db,
+                twitch,
+                event.id,
+                event.config.usage_limit_message.as_deref(),
+                user,
+                &event_data.input_data,
+                &[("limit", limit.max_uses.to_string())],
+            )
+            .await;
+        }
+
         return Ok(());
     }
 
     // Create metadata for storage
-    let metadata = EventExecutionMetadata {
+    let mut metadata = EventExecutionMetadata {
         user: event_data.user.clone(),
         data: vec![(
             "input_data".to_string(),
@@ -476,10 +955,22 @@ pub async fn execute_event(
 
     let event_id = event.id;
 
-    // Produce outcome message and send it
-    if let Some(msg) = produce_outcome_message(db, twitch, script_handle, event, event_data).await?
-    {
-        _ = event_sender.send(msg);
+    // Execute the outcome chain
+    let (random_picks, result) =
+        execute_outcomes(db, twitch, script_handle, event_sender, event, event_data).await;
+
+    if let (Ok(()), Some(redemption)) = (&result, &redemption) {
+        redemption.fulfil().await;
+    }
+
+    // Record which random outcomes were picked, failed executions are
+    // still stored as their outcomes may have partially run
+    if !random_picks.is_empty() {
+        metadata.data.push((
+            "random_picks".to_string(),
+            serde_json::to_value(&random_picks)
+                .context("failed to serialize random outcome picks")?,
+        ));
     }
 
     // Store event execution
@@ -494,7 +985,74 @@ pub async fn execute_event(
     .await
     .context("failed to store last event execution")?;
 
-    Ok(())
+    result
+}
+
+/// Determines the start of the window of time that a usage limit counts uses within
+fn usage_limit_window_start(
+    twitch: &Twitch,
+    window: &UsageLimitWindow,
+    current_time: DateTime<Utc>,
+) -> anyhow::Result<DateTime<Utc>> {
+    match window {
+        UsageLimitWindow::Stream => Ok(twitch.last_session_started_at()),
+        UsageLimitWindow::Duration { duration } => current_time
+            .checked_sub_signed(TimeDelta::milliseconds(*duration as i64))
+            .context("usage limit window starts too far in the past to compute"),
+    }
+}
+
+/// Finds the first usage limit of the command that has been reached by the user
+pub async fn find_command_usage_limit_reached<'a>(
+    db: &DbPool,
+    twitch: &Twitch,
+    command: &'a CommandModel,
+    user_id: &UserId,
+    current_time: DateTime<Utc>,
+) -> anyhow::Result<Option<&'a UsageLimit>> {
+    for limit in &command.config.usage_limits {
+        let start_date = usage_limit_window_start(twitch, &limit.window, current_time)?;
+        let user_id = limit.per_user.then_some(user_id.as_str());
+        let uses = CommandExecutionModel::count_since(db, command.id, start_date, user_id)
+            .await
+            .context("failed to count executions for command")?;
+
+        if uses >= limit.max_uses {
+            return Ok(Some(limit));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Finds the first usage limit of the event that has been reached, per user
+/// limits are not applied to anonymous users
+pub async fn find_event_usage_limit_reached<'a>(
+    db: &DbPool,
+    twitch: &Twitch,
+    event: &'a EventModel,
+    user: Option<&TwitchEventUser>,
+    current_time: DateTime<Utc>,
+) -> anyhow::Result<Option<&'a UsageLimit>> {
+    for limit in &event.config.usage_limits {
+        let user_id = match (limit.per_user, user) {
+            (false, _) => None,
+            (true, Some(user)) => Some(user.id.as_str()),
+            // Anonymous users bypass per user limits
+            (true, None) => continue,
+        };
+
+        let start_date = usage_limit_window_start(twitch, &limit.window, current_time)?;
+        let uses = EventExecutionModel::count_since(db, event.id, start_date, user_id)
+            .await
+            .context("failed to count executions for event")?;
+
+        if uses >= limit.max_uses {
+            return Ok(Some(limit));
+        }
+    }
+
+    Ok(None)
 }
 
 pub async fn has_required_role(
@@ -549,3 +1107,311 @@ pub async fn has_required_role(
 
     false
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        execute_event, find_event_usage_limit_reached, is_skipped_while_paused, parse_pause_command,
+    };
+    use crate::{
+        database::{
+            entity::{
+                event_execution::{
+                    CreateEventExecution, EventExecutionMetadata, EventExecutionModel,
+                },
+                events::{
+                    CreateEvent, EventConfig, EventCooldown, EventModel, EventOutcome,
+                    EventOutcomeRandom, EventOutcomeScript, EventOutcomeStep,
+                    EventOutcomeTriggerHotkey, EventTrigger, WeightedEventOutcome,
+                },
+                shared::{MinimumRequireRole, UsageLimit, UsageLimitWindow},
+            },
+            mock_database, DbPool,
+        },
+        events::{
+            matching::{EventData, EventInputData},
+            redemption::RedemptionGuard,
+            AppEvent, TimerCompleted,
+        },
+        script::runtime::ScriptExecutorHandle,
+        twitch::manager::Twitch,
+    };
+    use chrono::Utc;
+    use tokio::sync::{broadcast, mpsc};
+    use twitch_api::helix::points::CustomRewardRedemptionStatus;
+
+    /// Creates an event triggered by a redeem that updates its redemption
+    async fn create_event(db: &DbPool, update: impl FnOnce(&mut EventConfig)) -> EventModel {
+        let mut config = EventConfig {
+            trigger: EventTrigger::Redeem {
+                reward_id: "test-reward".to_string(),
+                update_redemption: true,
+            },
+            outcomes: vec![EventOutcomeStep {
+                delay: 0,
+                outcome: EventOutcome::TriggerHotkey(EventOutcomeTriggerHotkey {
+                    hotkey_id: "test-hotkey".to_string(),
+                }),
+            }],
+            ..Default::default()
+        };
+        update(&mut config);
+
+        EventModel::create(
+            db,
+            CreateEvent {
+                enabled: true,
+                name: "Test Event".to_string(),
+                config,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Executes the event with a mock redemption, returning the
+    /// status the redemption was settled with
+    async fn execute_redemption(
+        db: &DbPool,
+        event: EventModel,
+    ) -> Option<CustomRewardRedemptionStatus> {
+        let (app_tx, _app_rx) = mpsc::unbounded_channel();
+        let twitch = Twitch::new(app_tx);
+        let script_handle = ScriptExecutorHandle::mock();
+        let (event_sender, _event_rx) = broadcast::channel(16);
+        let (redemption, mut rx) = RedemptionGuard::mock();
+
+        let event_data = EventData {
+            user: None,
+            input_data: EventInputData::None,
+        };
+
+        // Skipped and failed events are both reported through the redemption
+        _ = execute_event(
+            db,
+            &twitch,
+            &script_handle,
+            &event_sender,
+            event,
+            &event_data,
+            Some(redemption),
+        )
+        .await;
+
+        RedemptionGuard::mock_settled(&mut rx).await
+    }
+
+    /// Tests that the redemption is fulfilled when the event succeeds
+    #[tokio::test]
+    async fn test_redemption_fulfilled() {
+        let db = mock_database().await;
+        let event = create_event(&db, |_| {}).await;
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Fulfilled)
+        ));
+    }
+
+    /// Tests that the redemption is refunded when the outcome fails
+    #[tokio::test]
+    async fn test_redemption_refunded_error() {
+        let db = mock_database().await;
+        let event = create_event(&db, |config| {
+            // Mock executor has no workers to run the script on
+            config.outcomes[0].outcome = EventOutcome::Script(EventOutcomeScript {
+                script: String::new(),
+                permissions: None,
+                limits: Default::default(),
+            });
+        })
+        .await;
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+    }
+
+    /// Tests that the redemption is refunded when skipped while offline
+    #[tokio::test]
+    async fn test_redemption_refunded_live_only() {
+        let db = mock_database().await;
+        let event = create_event(&db, |config| config.live_only = true).await;
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+    }
+
+    /// Tests that the redemption is refunded when skipped due to a missing role
+    #[tokio::test]
+    async fn test_redemption_refunded_missing_role() {
+        let db = mock_database().await;
+        let event = create_event(&db, |config| {
+            config.require_role = MinimumRequireRole::Mod;
+        })
+        .await;
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+    }
+
+    /// Tests that the redemption is refunded when skipped due to a cooldown
+    #[tokio::test]
+    async fn test_redemption_refunded_cooldown() {
+        let db = mock_database().await;
+        let event = create_event(&db, |config| {
+            config.cooldown = EventCooldown {
+                enabled: true,
+                duration: 60_000,
+                per_user: false,
+            };
+        })
+        .await;
+
+        EventExecutionModel::create(
+            &db,
+            CreateEventExecution {
+                event_id: event.id,
+                metadata: EventExecutionMetadata {
+                    user: None,
+                    data: Vec::new(),
+                },
+                created_at: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+    }
+
+    /// Tests that the random picks are recorded when the picked outcome fails
+    #[tokio::test]
+    async fn test_random_picks_recorded_on_error() {
+        let db = mock_database().await;
+        let event = create_event(&db, |config| {
+            // Mock executor has no workers to run the script on
+            config.outcomes[0].outcome = EventOutcome::Random(EventOutcomeRandom {
+                outcomes: vec![WeightedEventOutcome {
+                    weight: 1,
+                    outcome: EventOutcome::Script(EventOutcomeScript {
+                        script: String::new(),
+                        permissions: None,
+                        limits: Default::default(),
+                    }),
+                }],
+            });
+        })
+        .await;
+        let event_id = event.id;
+
+        assert!(matches!(
+            execute_redemption(&db, event).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+
+        let execution = EventExecutionModel::last(&db, event_id, 0)
+            .await
+            .unwrap()
+            .expect("missing event execution");
+        assert!(execution
+            .metadata
+            .data
+            .iter()
+            .any(|(key, _)| key == "random_picks"));
+    }
+
+    /// Tests that stream usage limits still count uses from the last
+    /// stream session while offline
+    #[tokio::test]
+    async fn test_stream_usage_limit_offline() {
+        let db = mock_database().await;
+        let twitch = Twitch::new(mpsc::unbounded_channel().0);
+        assert!(!twitch.is_live());
+
+        let event = create_event(&db, |config| {
+            config.usage_limits = vec![UsageLimit {
+                max_uses: 1,
+                per_user: false,
+                window: UsageLimitWindow::Stream,
+            }];
+        })
+        .await;
+
+        let limit = find_event_usage_limit_reached(&db, &twitch, &event, None, Utc::now())
+            .await
+            .unwrap();
+        assert!(limit.is_none());
+
+        EventExecutionModel::create(
+            &db,
+            CreateEventExecution {
+                event_id: event.id,
+                metadata: EventExecutionMetadata {
+                    user: None,
+                    data: Vec::new(),
+                },
+                created_at: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let limit = find_event_usage_limit_reached(&db, &twitch, &event, None, Utc::now())
+            .await
+            .unwrap();
+        assert!(limit.is_some());
+    }
+
+    /// Tests parsing the pause command with the configured prefixes
+    #[test]
+    fn test_parse_pause_command() {
+        let prefixes = vec!["!".to_string(), "?".to_string()];
+
+        let tests = [
+            ("!vtftk pause", Some(true)),
+            ("?VTFTK Pause", Some(true)),
+            ("!vtftk resume", Some(false)),
+            ("?vtftk unpause extra words", Some(false)),
+            ("!vtftk", None),
+            ("!vtftk other", None),
+            ("vtftk pause", None),
+            ("#vtftk pause", None),
+            ("!vtftkpause", None),
+            ("hello !vtftk pause", None),
+            ("", None),
+        ];
+
+        for (message, expected) in tests {
+            assert_eq!(
+                parse_pause_command(&prefixes, message),
+                expected,
+                "{message}"
+            );
+        }
+    }
+
+    /// Tests that only events without required side effects are
+    /// skipped before matching while paused
+    #[test]
+    fn test_is_skipped_while_paused() {
+        assert!(is_skipped_while_paused(&AppEvent::TimerCompleted(
+            TimerCompleted {
+                event_id: Default::default()
+            }
+        )));
+
+        assert!(!is_skipped_while_paused(&AppEvent::StreamOnline));
+        assert!(!is_skipped_while_paused(&AppEvent::StreamOffline));
+        assert!(!is_skipped_while_paused(&AppEvent::ProcessingResumed));
+        assert!(!is_skipped_while_paused(&AppEvent::ModeratorsChanged));
+    }
+}
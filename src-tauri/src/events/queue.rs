@@ -0,0 +1,523 @@
+//! # Event Queue
+//!
+//! Queue for events that should play one after the other rather than
+//! running at the same time, each named queue plays its events in order
+//! waiting for the previous event to finish (plus an optional gap)
+
+use chrono::{DateTime, Utc};
+use futures::future::{AbortHandle, Abortable, BoxFuture};
+use log::{debug, error};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::TwitchEventUser;
+
+/// Details about an event within the queue
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedEventInfo {
+    /// Unique ID of the queue item
+    pub id: Uuid,
+    /// ID of the event that was queued
+    pub event_id: Uuid,
+    /// Name of the event that was queued
+    pub event_name: String,
+    /// User that triggered the event
+    pub user: Option<TwitchEventUser>,
+    /// When the event was queued
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Event waiting within the queue
+pub struct QueuedEvent {
+    /// Details about the event
+    pub info: QueuedEventInfo,
+    /// Gap in milliseconds to wait after the event completes
+    pub gap: u32,
+    /// Future that executes the event
+    pub future: BoxFuture<'static, anyhow::Result<()>>,
+}
+
+/// Snapshot of the current state of all the queues
+#[derive(Debug, Serialize)]
+pub struct EventQueueSnapshot {
+    /// Whether the queues are paused
+    pub paused: bool,
+    /// Whether the queues are paused because all processing is paused
+    pub processing_paused: bool,
+    /// Current queues
+    pub queues: Vec<NamedQueueSnapshot>,
+}
+
+/// Snapshot of a single named queue
+#[derive(Debug, Serialize)]
+pub struct NamedQueueSnapshot {
+    /// Name of the queue
+    pub name: String,
+    /// Event currently playing
+    pub current: Option<QueuedEventInfo>,
+    /// Events waiting to play
+    pub pending: Vec<QueuedEventInfo>,
+}
+
+#[derive(Clone)]
+pub struct EventQueue {
+    inner: Arc<EventQueueInner>,
+}
+
+struct EventQueueInner {
+    state: Mutex<EventQueueState>,
+    /// App handle for notifying the frontend of changes
+    /// (Not present when mocked)
+    app_handle: Option<AppHandle>,
+}
+
+#[derive(Default)]
+struct EventQueueState {
+    /// Whether processing of the queues is paused
+    paused: bool,
+    /// Whether all event processing is paused, the queues do not
+    /// start new events while either pause is set
+    processing_paused: bool,
+    /// Queues by name
+    queues: HashMap<String, NamedQueue>,
+}
+
+impl EventQueueState {
+    /// Checks whether new events can be started
+    fn is_paused(&self) -> bool {
+        self.paused || self.processing_paused
+    }
+}
+
+#[derive(Default)]
+struct NamedQueue {
+    /// Events waiting to play
+    pending: VecDeque<QueuedEvent>,
+    /// Event currently playing along with the handle to abort it
+    current: Option<(QueuedEventInfo, AbortHandle)>,
+    /// Whether a worker is currently processing the queue
+    active: bool,
+}
+
+impl EventQueue {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self::with_app_handle(Some(app_handle))
+    }
+
+    /// Creates a queue that does not notify the frontend of changes
+    #[cfg(test)]
+    pub fn mock() -> Self {
+        Self::with_app_handle(None)
+    }
+
+    fn with_app_handle(app_handle: Option<AppHandle>) -> Self {
+        Self {
+            inner: Arc::new(EventQueueInner {
+                state: Default::default(),
+                app_handle,
+            }),
+        }
+    }
+
+    /// Adds an event to the end of the named queue
+    pub fn push(&self, name: String, event: QueuedEvent) {
+        let start_worker = {
+            let state = &mut *self.inner.state.lock();
+            let paused = state.is_paused();
+            let queue = state.queues.entry(name.clone()).or_default();
+            queue.pending.push_back(event);
+
+            let start_worker = !queue.active && !paused;
+            if start_worker {
+                queue.active = true;
+            }
+
+            start_worker
+        };
+
+        if start_worker {
+            tauri::async_runtime::spawn(self.clone().run_queue(name));
+        }
+
+        self.emit_changed();
+    }
+
+    /// Creates a snapshot of the current queue state
+    pub fn snapshot(&self) -> EventQueueSnapshot {
+        let state = &*self.inner.state.lock();
+        let mut queues: Vec<NamedQueueSnapshot> = state
+            .queues
+            .iter()
+            .map(|(name, queue)| NamedQueueSnapshot {
+                name: name.clone(),
+                current: queue.current.as_ref().map(|(info, _)| info.clone()),
+                pending: queue
+                    .pending
+                    .iter()
+                    .map(|event| event.info.clone())
+                    .collect(),
+            })
+            .collect();
+
+        queues.sort_by(|a, b| a.name.cmp(&b.name));
+
+        EventQueueSnapshot {
+            paused: state.paused,
+            processing_paused: state.processing_paused,
+            queues,
+        }
+    }
+
+    /// Skips a queued event, pending events are removed from the queue
+    /// and the currently playing event is stopped
+    ///
+    /// Returns whether the event was found
+    pub fn skip(&self, id: Uuid) -> bool {
+        let found = {
+            let state = &mut *self.inner.state.lock();
+            state.queues.values_mut().any(|queue| {
+                if let Some(index) = queue.pending.iter().position(|event| event.info.id == id) {
+                    queue.pending.remove(index);
+                    return true;
+                }
+
+                match &queue.current {
+                    Some((info, abort_handle)) if info.id == id => {
+                        abort_handle.abort();
+                        true
+                    }
+                    _ => false,
+                }
+            })
+        };
+
+        if found {
+            self.emit_changed();
+        }
+
+        found
+    }
+
+    /// Clears all pending events from the named queue, or all
+    /// queues when no name is provided
+    pub fn clear(&self, name: Option<&str>) {
+        {
+            let state = &mut *self.inner.state.lock();
+            for (queue_name, queue) in state.queues.iter_mut() {
+                if name.is_none_or(|name| name == queue_name) {
+                    queue.pending.clear();
+                }
+            }
+        }
+
+        self.emit_changed();
+    }
+
+    /// Pauses or resumes the queues, pausing allows the current
+    /// events to finish but prevents the next ones from starting
+    pub fn set_paused(&self, paused: bool) {
+        self.update_paused(|state| state.paused = paused);
+    }
+
+    /// Pauses or resumes the queues along with all event processing, the
+    /// queues stay paused while they are also paused using [Self::set_paused]
+    pub fn set_processing_paused(&self, paused: bool) {
+        self.update_paused(|state| state.processing_paused = paused);
+    }
+
+    /// Updates the pause state, starting workers for any queues
+    /// that can play their pending events
+    fn update_paused<F>(&self, update: F)
+    where
+        F: FnOnce(&mut EventQueueState),
+    {
+        let resume_queues: Vec<String> = {
+            let state = &mut *self.inner.state.lock();
+            update(state);
+
+            if state.is_paused() {
+                Vec::new()
+            } else {
+                state
+                    .queues
+                    .iter_mut()
+                    .filter(|(_, queue)| !queue.active && !queue.pending.is_empty())
+                    .map(|(name, queue)| {
+                        queue.active = true;
+                        name.clone()
+                    })
+                    .collect()
+            }
+        };
+
+        for name in resume_queues {
+            tauri::async_runtime::spawn(self.clone().run_queue(name));
+        }
+
+        self.emit_changed();
+    }
+
+    /// Worker that plays the events in a named queue until the
+    /// queue is empty or the queues are paused
+    async fn run_queue(self, name: String) {
+        loop {
+            let (event, abort_registration) = {
+                let state = &mut *self.inner.state.lock();
+                let paused = state.is_paused();
+                let Some(queue) = state.queues.get_mut(&name) else {
+                    return;
+                };
+
+                let next = if paused {
+                    None
+                } else {
+                    queue.pending.pop_front()
+                };
+
+                match next {
+                    Some(event) => {
+                        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+                        queue.current = Some((event.info.clone(), abort_handle));
+                        (event, abort_registration)
+                    }
+                    None => {
+                        queue.active = false;
+
+                        // Remove queues that have nothing left to play
+                        if queue.pending.is_empty() {
+                            state.queues.remove(&name);
+                        }
+
+                        break;
+                    }
+                }
+            };
+
+            self.emit_changed();
+
+            match Abortable::new(event.future, abort_registration).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => error!("error while executing queued event: {err:?}"),
+                Err(_) => debug!("skipped queued event {}", event.info.id),
+            }
+
+            {
+                let state = &mut *self.inner.state.lock();
+                if let Some(queue) = state.queues.get_mut(&name) {
+                    queue.current = None;
+                }
+            }
+
+            self.emit_changed();
+
+            // Wait for the gap before the next event
+            if event.gap > 0 {
+                tokio::time::sleep(Duration::from_millis(event.gap as u64)).await;
+            }
+        }
+
+        self.emit_changed();
+    }
+
+    /// Notifies the frontend that the queue has changed
+    fn emit_changed(&self) {
+        if let Some(app_handle) = self.inner.app_handle.as_ref() {
+            _ = app_handle.emit("event_queue_changed", ());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use futures::FutureExt;
+    use parking_lot::Mutex;
+    use std::{future::Future, sync::Arc, time::Duration};
+    use tokio::sync::oneshot;
+    use uuid::Uuid;
+
+    use super::{EventQueue, QueuedEvent, QueuedEventInfo};
+
+    /// Events that have finished playing, by name
+    type Played = Arc<Mutex<Vec<String>>>;
+
+    /// Creates a queued event that records its name once played
+    fn queued_event(name: &str, played: &Played) -> QueuedEvent {
+        queued_event_until(name, played, async {})
+    }
+
+    /// Creates a queued event that keeps playing until `done`
+    /// completes, then records its name
+    fn queued_event_until<F>(name: &str, played: &Played, done: F) -> QueuedEvent
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let event_name = name.to_string();
+        let played = played.clone();
+
+        QueuedEvent {
+            info: QueuedEventInfo {
+                id: Uuid::new_v4(),
+                event_id: Uuid::new_v4(),
+                event_name: name.to_string(),
+                user: None,
+                queued_at: Utc::now(),
+            },
+            gap: 0,
+            future: async move {
+                done.await;
+                played.lock().push(event_name);
+                Ok(())
+            }
+            .boxed(),
+        }
+    }
+
+    /// Waits for `count` events to finish playing, returning the names
+    /// of the events that played
+    async fn wait_played(played: &Played, count: usize) -> Vec<String> {
+        _ = tokio::time::timeout(Duration::from_secs(1), async {
+            while played.lock().len() < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        played.lock().clone()
+    }
+
+    /// Tests that events in a queue play in the order they were queued
+    #[tokio::test]
+    async fn test_queue_order() {
+        let queue = EventQueue::mock();
+        let played = Played::default();
+
+        for name in ["first", "second", "third"] {
+            queue.push("test".to_string(), queued_event(name, &played));
+        }
+
+        assert_eq!(
+            wait_played(&played, 3).await,
+            vec!["first", "second", "third"]
+        );
+
+        // Queues are removed once they have nothing left to play
+        assert!(wait_for_empty(&queue).await);
+    }
+
+    /// Tests skipping both pending and currently playing events
+    #[tokio::test]
+    async fn test_queue_skip() {
+        let queue = EventQueue::mock();
+        let played = Played::default();
+
+        // Hold the first event until it is skipped
+        let (_hold, done) = oneshot::channel::<()>();
+        let first = queued_event_until("first", &played, async {
+            _ = done.await;
+        });
+        let first_id = first.info.id;
+        let second = queued_event("second", &played);
+        let second_id = second.info.id;
+
+        queue.push("test".to_string(), first);
+        queue.push("test".to_string(), second);
+        queue.push("test".to_string(), queued_event("third", &played));
+
+        // Wait for the first event to start playing
+        _ = tokio::time::timeout(Duration::from_secs(1), async {
+            while queue.snapshot().queues[0].current.is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        assert!(queue.skip(second_id));
+        assert!(queue.skip(first_id));
+        assert!(!queue.skip(Uuid::new_v4()));
+
+        assert_eq!(wait_played(&played, 1).await, vec!["third"]);
+    }
+
+    /// Tests that clearing removes the pending events
+    #[tokio::test]
+    async fn test_queue_clear() {
+        let queue = EventQueue::mock();
+        let played = Played::default();
+
+        queue.set_paused(true);
+        queue.push("first".to_string(), queued_event("first", &played));
+        queue.push("second".to_string(), queued_event("second", &played));
+
+        queue.clear(Some("first"));
+
+        let snapshot = queue.snapshot();
+        let pending: Vec<usize> = snapshot
+            .queues
+            .iter()
+            .map(|queue| queue.pending.len())
+            .collect();
+        assert_eq!(pending, vec![0, 1]);
+
+        queue.clear(None);
+        queue.set_paused(false);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(played.lock().is_empty());
+    }
+
+    /// Tests that paused queues do not start events until resumed
+    #[tokio::test]
+    async fn test_queue_pause_resume() {
+        let queue = EventQueue::mock();
+        let played = Played::default();
+
+        queue.set_paused(true);
+        queue.push("test".to_string(), queued_event("first", &played));
+        queue.push("test".to_string(), queued_event("second", &played));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(played.lock().is_empty());
+        assert!(queue.snapshot().paused);
+
+        queue.set_paused(false);
+        assert_eq!(wait_played(&played, 2).await, vec!["first", "second"]);
+    }
+
+    /// Tests that the queues stay paused until both the queue pause and
+    /// the processing pause are released
+    #[tokio::test]
+    async fn test_queue_processing_paused() {
+        let queue = EventQueue::mock();
+        let played = Played::default();
+
+        queue.set_paused(true);
+        queue.set_processing_paused(true);
+        queue.push("test".to_string(), queued_event("first", &played));
+
+        queue.set_paused(false);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(played.lock().is_empty());
+
+        queue.set_processing_paused(false);
+        assert_eq!(wait_played(&played, 1).await, vec!["first"]);
+    }
+
+    /// Waits for all the queues to be removed
+    async fn wait_for_empty(queue: &EventQueue) -> bool {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !queue.snapshot().queues.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
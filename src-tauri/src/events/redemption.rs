@@ -0,0 +1,208 @@
+//! # Redemption
+//!
+//! Settling of channel point redemptions for events that update their
+//! redemption. A redemption is settled exactly once, it is fulfilled by
+//! the first event to succeed and refunded when every event holding it
+//! is dropped without succeeding (Skipped, failed, cleared from a queue,
+//! dropped while paused, etc)
+
+use futures::future::BoxFuture;
+use log::{debug, error};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use twitch_api::helix::points::CustomRewardRedemptionStatus;
+
+use crate::{
+    database::entity::events::{EventModel, EventTrigger},
+    twitch::manager::Twitch,
+};
+
+use super::matching::{EventData, EventInputData};
+
+/// Callback that applies the final status to the redemption
+type SettleRedemption =
+    Box<dyn FnOnce(CustomRewardRedemptionStatus) -> BoxFuture<'static, ()> + Send>;
+
+/// Shared handle to a redemption that has not yet been settled, clones
+/// are given to each event that updates the redemption
+#[derive(Clone)]
+pub struct RedemptionGuard {
+    inner: Arc<RedemptionGuardInner>,
+}
+
+struct RedemptionGuardInner {
+    /// Callback for settling the redemption, taken once settled
+    settle: Mutex<Option<SettleRedemption>>,
+}
+
+impl RedemptionGuard {
+    /// Creates a guard for the redemption in `event_data` when any of the
+    /// matched `events` update their redemption
+    pub fn for_events(
+        twitch: &Twitch,
+        events: &[EventModel],
+        event_data: &EventData,
+    ) -> Option<Self> {
+        if !events.iter().any(is_update_redemption) {
+            return None;
+        }
+
+        let EventInputData::Redeem {
+            redemption_id,
+            reward_id,
+            ..
+        } = &event_data.input_data
+        else {
+            return None;
+        };
+
+        let twitch = twitch.clone();
+        let reward_id = reward_id.clone();
+        let redemption_id = redemption_id.clone();
+
+        Some(Self::new(move |status| {
+            Box::pin(async move {
+                if let Err(err) = twitch
+                    .update_redemption_status(&reward_id, &redemption_id, status)
+                    .await
+                {
+                    error!("failed to update redemption status: {err:?}");
+                }
+            })
+        }))
+    }
+
+    fn new<F>(settle: F) -> Self
+    where
+        F: FnOnce(CustomRewardRedemptionStatus) -> BoxFuture<'static, ()> + Send + 'static,
+    {
+        Self {
+            inner: Arc::new(RedemptionGuardInner {
+                settle: Mutex::new(Some(Box::new(settle))),
+            }),
+        }
+    }
+
+    /// Marks the redemption as fulfilled, does nothing when the
+    /// redemption has already been settled
+    pub async fn fulfil(&self) {
+        let settle = self.inner.settle.lock().take();
+        if let Some(settle) = settle {
+            settle(CustomRewardRedemptionStatus::Fulfilled).await;
+        }
+    }
+}
+
+impl Drop for RedemptionGuardInner {
+    fn drop(&mut self) {
+        let Some(settle) = self.settle.get_mut().take() else {
+            return;
+        };
+
+        // No event succeeded, refund the redemption
+        debug!("refunding redemption that was not fulfilled");
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(settle(CustomRewardRedemptionStatus::Canceled));
+            }
+            Err(_) => error!("unable to refund redemption outside of async runtime"),
+        }
+    }
+}
+
+/// Checks if the event updates the redemption that triggered it
+pub fn is_update_redemption(event: &EventModel) -> bool {
+    matches!(
+        event.config.trigger,
+        EventTrigger::Redeem {
+            update_redemption: true,
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+impl RedemptionGuard {
+    /// Creates a guard that reports the status it is settled
+    /// with to the returned receiver
+    pub fn mock() -> (
+        Self,
+        tokio::sync::mpsc::UnboundedReceiver<CustomRewardRedemptionStatus>,
+    ) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let guard = Self::new(move |status| {
+            Box::pin(async move {
+                _ = tx.send(status);
+            })
+        });
+        (guard, rx)
+    }
+
+    /// Waits for the status a mock guard was settled with
+    pub async fn mock_settled(
+        rx: &mut tokio::sync::mpsc::UnboundedReceiver<CustomRewardRedemptionStatus>,
+    ) -> Option<CustomRewardRedemptionStatus> {
+        tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RedemptionGuard;
+    use twitch_api::helix::points::CustomRewardRedemptionStatus;
+
+    /// Tests that dropping the guard without fulfilling refunds the redemption
+    #[tokio::test]
+    async fn test_drop_refunds() {
+        let (guard, mut rx) = RedemptionGuard::mock();
+        drop(guard);
+
+        assert!(matches!(
+            RedemptionGuard::mock_settled(&mut rx).await,
+            Some(CustomRewardRedemptionStatus::Canceled)
+        ));
+        assert!(RedemptionGuard::mock_settled(&mut rx).await.is_none());
+    }
+
+    /// Tests that a fulfilled redemption is not refunded when dropped
+    #[tokio::test]
+    async fn test_fulfil() {
+        let (guard, mut rx) = RedemptionGuard::mock();
+        guard.fulfil().await;
+        drop(guard);
+
+        assert!(matches!(
+            RedemptionGuard::mock_settled(&mut rx).await,
+            Some(CustomRewardRedemptionStatus::Fulfilled)
+        ));
+        assert!(RedemptionGuard::mock_settled(&mut rx).await.is_none());
+    }
+
+    /// Tests that the redemption is only updated once when shared by
+    /// several events, and only refunded once all events are dropped
+    #[tokio::test]
+    async fn test_shared_guard() {
+        let (guard, mut rx) = RedemptionGuard::mock();
+        let first = guard.clone();
+        let second = guard.clone();
+        drop(guard);
+
+        // Dropping one event keeps the redemption pending
+        drop(first);
+        assert!(rx.try_recv().is_err());
+
+        second.fulfil().await;
+        second.fulfil().await;
+        drop(second);
+
+        assert!(matches!(
+            RedemptionGuard::mock_settled(&mut rx).await,
+            Some(CustomRewardRedemptionStatus::Fulfilled)
+        ));
+        assert!(RedemptionGuard::mock_settled(&mut rx).await.is_none());
+    }
+}
@@ -1,26 +1,155 @@
-use anyhow::Context;
-use chrono::Local;
+use anyhow::{bail, Context};
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeDelta, TimeZone, Utc, Weekday};
 use futures::future::BoxFuture;
-use log::error;
-use std::{collections::BinaryHeap, future::Future, pin::Pin, task::Poll, time::Duration};
+use log::{debug, error, warn};
+use std::{collections::BinaryHeap, future::Future, pin::Pin, task::Poll};
 use tokio::{
     sync::mpsc,
     time::{sleep_until, Instant},
 };
 use uuid::Uuid;
 
-use super::{AppEvent, AppEventSender, TimerCompleted};
+use crate::{
+    database::{
+        entity::{
+            events::{MissedRunPolicy, TimerSchedule},
+            timer_state::{SaveTimerState, TimerStateModel},
+        },
+        DbPool,
+    },
+    twitch::manager::Twitch,
+};
+
+use super::{cron::CronSchedule, AppEvent, AppEventSender, TimerCompleted};
+
+/// Maximum number of missed runs that will be caught up on when
+/// using [MissedRunPolicy::CatchUp]
+const MAX_CATCH_UP_RUNS: usize = 10;
+
+/// Determines when a scheduled event runs
+#[derive(Debug, Clone)]
+pub struct EventSchedule {
+    /// Base schedule the event runs on
+    pub kind: EventScheduleKind,
+    /// Days of the week the event is allowed to run on, empty allows every day
+    pub days: Vec<Weekday>,
+    /// Maximum random delay in seconds added to each run
+    pub jitter: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum EventScheduleKind {
+    /// Fixed interval in seconds
+    Interval(u64),
+    /// Cron expression
+    Cron(CronSchedule),
+    /// Specific times of the day
+    TimesOfDay(Vec<NaiveTime>),
+}
+
+impl EventSchedule {
+    /// Creates a schedule from the configuration of a timer trigger
+    pub fn from_timer(
+        interval: u64,
+        schedule: Option<&TimerSchedule>,
+        days: &[Weekday],
+        jitter: u64,
+    ) -> anyhow::Result<Self> {
+        let kind = match schedule {
+            None => {
+                if interval == 0 {
+                    bail!("timer interval must be greater than zero");
+                }
+
+                EventScheduleKind::Interval(interval)
+            }
+            Some(TimerSchedule::Cron { expression }) => EventScheduleKind::Cron(
+                expression
+                    .parse()
+                    .with_context(|| format!("invalid cron expression \"{expression}\""))?,
+            ),
+            Some(TimerSchedule::TimesOfDay { times }) => {
+                if times.is_empty() {
+                    bail!("times of day schedule requires at least one time");
+                }
+
+                let mut times = times.clone();
+                times.sort();
+                EventScheduleKind::TimesOfDay(times)
+            }
+        };
+
+        Ok(Self {
+            kind,
+            days: days.to_vec(),
+            jitter,
+        })
+    }
+
+    /// Finds the next run strictly after `after`, skipping any days that
+    /// are not allowed. Does not include jitter
+    pub fn next_run<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let mut after = after.clone();
+
+        // Each attempt skips at least one full day, a week covers every day
+        for _ in 0..8 {
+            let next = self.next_base_run(&after)?;
+            if self.days.is_empty() || self.days.contains(&next.weekday()) {
+                return Some(next);
+            }
+
+            // Continue searching from the start of the next day
+            let next_day = next.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            after = timezone.from_local_datetime(&next_day).earliest()? - TimeDelta::seconds(1);
+        }
+
+        None
+    }
+
+    /// Finds the next run of the base schedule strictly after `after`
+    fn next_base_run<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+
+        match &self.kind {
+            EventScheduleKind::Interval(interval) => {
+                // Intervals are aligned to the unix epoch
+                let interval = *interval as i64;
+                let next = (after.timestamp() / interval + 1) * interval;
+                DateTime::from_timestamp(next, 0).map(|next| next.with_timezone(&timezone))
+            }
+            EventScheduleKind::Cron(schedule) => schedule.next_after(after),
+            EventScheduleKind::TimesOfDay(times) => {
+                let today = after.date_naive();
+                let tomorrow = today.succ_opt()?;
+
+                [today, tomorrow]
+                    .into_iter()
+                    .flat_map(|date| times.iter().map(move |time| date.and_time(*time)))
+                    .filter_map(|time| timezone.from_local_datetime(&time).earliest())
+                    .find(|time| time > after)
+            }
+        }
+    }
+}
 
 pub struct ScheduledEvent {
     /// ID of the event to execute
     pub event_id: Uuid,
 
-    /// Interval the event executes at
+    /// Schedule the event executes on
     /// (For further scheduling)
-    pub interval_seconds: u64,
+    pub schedule: EventSchedule,
+
+    /// Whether the event is paused while the stream is offline
+    pub live_only: bool,
 
     /// Next instance the
     pub next_run: Instant,
+
+    /// Local time of the next run
+    /// (For persisting the schedule)
+    pub next_run_at: DateTime<Local>,
 }
 
 impl Eq for ScheduledEvent {}
@@ -51,9 +180,14 @@ pub struct SchedulerHandle(mpsc::Sender<Vec<SchedulerQueueEvent>>);
 pub struct SchedulerQueueEvent {
     /// ID of the event to execute
     pub event_id: Uuid,
-    /// Interval the event executes at
-    /// (For further scheduling)
-    pub interval_seconds: u64,
+    /// Schedule the event executes on
+    pub schedule: EventSchedule,
+    /// Whether the event is paused while the stream is offline
+    pub live_only: bool,
+    /// How runs missed while the app was closed are handled
+    pub missed_runs: MissedRunPolicy,
+    /// Stored state from a previous run of the scheduler
+    pub state: Option<TimerStateModel>,
 }
 
 impl SchedulerHandle {
@@ -62,21 +196,26 @@ impl SchedulerHandle {
     }
 }
 
-pub fn create_scheduler(event_tx: AppEventSender) -> SchedulerHandle {
+pub fn create_scheduler(db: DbPool, event_tx: AppEventSender, twitch: Twitch) -> SchedulerHandle {
     let (tx, rx) = mpsc::channel(5);
     let handle = SchedulerHandle(tx);
 
     tauri::async_runtime::spawn(SchedulerEventLoop {
+        db,
         rx,
         events: BinaryHeap::new(),
         current_sleep: None,
         event_tx,
+        twitch,
     });
 
     handle
 }
 
 struct SchedulerEventLoop {
+    /// Database for persisting the schedule state
+    db: DbPool,
+
     /// Receiver for the latest events list
     rx: mpsc::Receiver<Vec<SchedulerQueueEvent>>,
 
@@ -88,6 +227,9 @@ struct SchedulerEventLoop {
     current_sleep: Option<BoxFuture<'static, ()>>,
 
     event_tx: AppEventSender,
+
+    /// Twitch access for checking the live state
+    twitch: Twitch,
 }
 
 impl SchedulerEventLoop {
@@ -97,13 +239,97 @@ impl SchedulerEventLoop {
         }
     }
 
+    /// Persists the schedule state of an event in the background
+    fn save_state(
+        &self,
+        event_id: Uuid,
+        last_run: Option<DateTime<Local>>,
+        next_run: DateTime<Local>,
+    ) {
+        let db = self.db.clone();
+        let save = SaveTimerState {
+            event_id,
+            last_run: last_run.map(|last_run| last_run.with_timezone(&Utc)),
+            next_run: next_run.with_timezone(&Utc),
+        };
+
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = TimerStateModel::save(&db, save).await {
+                error!("failed to save timer state: {err:?}");
+            }
+        });
+    }
+
+    /// Schedules the next run of an event and persists it, `last_run` is
+    /// the time the event was last executed if it was executed
+    fn schedule_next(
+        &self,
+        event_id: Uuid,
+        schedule: EventSchedule,
+        live_only: bool,
+        last_run: Option<DateTime<Local>>,
+    ) -> Option<ScheduledEvent> {
+        let event = create_scheduled_event(event_id, schedule, live_only)?;
+        self.save_state(event_id, last_run, event.next_run_at);
+        Some(event)
+    }
+
+    /// Resumes an event from its stored state, handling any runs that
+    /// were missed while the app was closed
+    fn resume_event(
+        &self,
+        event: SchedulerQueueEvent,
+        now: DateTime<Local>,
+    ) -> Option<ScheduledEvent> {
+        let stored_next_run = event
+            .state
+            .as_ref()
+            .map(|state| state.next_run.with_timezone(&Local));
+
+        let missed_run = match stored_next_run {
+            // Resume from the stored next run
+            Some(next_run) if next_run > now => {
+                return Some(scheduled_event_at(
+                    event.event_id,
+                    event.schedule,
+                    event.live_only,
+                    next_run,
+                    now,
+                ));
+            }
+            Some(missed_run) => missed_run,
+            None => {
+                return self.schedule_next(event.event_id, event.schedule, event.live_only, None);
+            }
+        };
+
+        let runs = count_missed_runs(&event.schedule, event.missed_runs, missed_run, now);
+        let mut last_run = None;
+
+        if runs > 0 {
+            if event.live_only && !self.twitch.is_live() {
+                debug!("skipping missed timer runs while stream is offline");
+            } else {
+                debug!("running {runs} missed timer runs for {}", event.event_id);
+                for _ in 0..runs {
+                    Self::execute_event(event.event_id, self.event_tx.clone());
+                }
+                last_run = Some(now);
+            }
+        }
+
+        self.schedule_next(event.event_id, event.schedule, event.live_only, last_run)
+    }
+
     fn poll_inner(&mut self, cx: &mut std::task::Context<'_>) -> Poll<()> {
         // Accept messages to update the events list
         while let Poll::Ready(Some(events)) = self.rx.poll_recv(cx) {
+            let now = Local::now();
+
             // Create the scheduled events
             self.events = events
                 .into_iter()
-                .map(|event| create_scheduled_event(event.event_id, event.interval_seconds))
+                .filter_map(|event| self.resume_event(event, now))
                 .collect();
 
             // Clear sleep state
@@ -125,14 +351,23 @@ impl SchedulerEventLoop {
                 None => return Poll::Pending,
             };
 
-            // Trigger the event
-            Self::execute_event(event.event_id, self.event_tx.clone());
+            let mut last_run = None;
+
+            // Live only timers are paused while the stream is offline
+            if event.live_only && !self.twitch.is_live() {
+                debug!("skipping timer event while stream is offline");
+            } else {
+                // Trigger the event
+                Self::execute_event(event.event_id, self.event_tx.clone());
+                last_run = Some(Local::now());
+            }
 
             // Create the next iteration of the event
-            self.events.push(create_scheduled_event(
-                event.event_id,
-                event.interval_seconds,
-            ));
+            if let Some(next_event) =
+                self.schedule_next(event.event_id, event.schedule, event.live_only, last_run)
+            {
+                self.events.push(next_event);
+            }
 
             // Emit event
             return Poll::Ready(());
@@ -168,19 +403,253 @@ impl Future for SchedulerEventLoop {
     }
 }
 
-fn create_scheduled_event(event_id: Uuid, interval_seconds: u64) -> ScheduledEvent {
-    let next_run = get_next_interval_instant(interval_seconds);
+/// Creates the next scheduled run of an event, returns [None] if the
+/// schedule will never run again
+fn create_scheduled_event(
+    event_id: Uuid,
+    schedule: EventSchedule,
+    live_only: bool,
+) -> Option<ScheduledEvent> {
+    let now = Local::now();
+    let Some(mut next_run) = schedule.next_run(&now) else {
+        warn!("timer event {event_id} schedule has no future runs");
+        return None;
+    };
+
+    // Apply random jitter to the run
+    if schedule.jitter > 0 {
+        next_run += TimeDelta::seconds(rand::random_range(0..=schedule.jitter) as i64);
+    }
+
+    Some(scheduled_event_at(
+        event_id, schedule, live_only, next_run, now,
+    ))
+}
+
+/// Creates a scheduled event that runs at `next_run`
+fn scheduled_event_at(
+    event_id: Uuid,
+    schedule: EventSchedule,
+    live_only: bool,
+    next_run: DateTime<Local>,
+    now: DateTime<Local>,
+) -> ScheduledEvent {
+    let delay = (next_run - now).to_std().unwrap_or_default();
+
     ScheduledEvent {
         event_id,
-        interval_seconds,
-        next_run,
+        schedule,
+        live_only,
+        next_run: Instant::now() + delay,
+        next_run_at: next_run,
     }
 }
 
-/// Gets the next instant for a fixed interval in seconds
-fn get_next_interval_instant(interval_seconds: u64) -> Instant {
-    let now = Local::now();
-    let seconds_since_epoch = now.timestamp() as u64;
-    let next = (seconds_since_epoch / interval_seconds + 1) * interval_seconds;
-    Instant::now() + Duration::from_secs(next - seconds_since_epoch)
+/// Counts the number of runs that should be executed for runs missed
+/// between `missed_run` (The first missed run) and `now`
+fn count_missed_runs<Tz: TimeZone>(
+    schedule: &EventSchedule,
+    policy: MissedRunPolicy,
+    missed_run: DateTime<Tz>,
+    now: DateTime<Tz>,
+) -> usize {
+    match policy {
+        MissedRunPolicy::Skip => 0,
+        MissedRunPolicy::RunOnce => 1,
+        MissedRunPolicy::CatchUp => {
+            let mut runs = 1;
+            let mut run = missed_run;
+
+            while runs < MAX_CATCH_UP_RUNS {
+                match schedule.next_run(&run) {
+                    Some(next_run) if next_run <= now => {
+                        runs += 1;
+                        run = next_run;
+                    }
+                    _ => break,
+                }
+            }
+
+            runs
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+    use std::{collections::BinaryHeap, time::Duration};
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::{
+        count_missed_runs, EventSchedule, EventScheduleKind, SchedulerEventLoop, SchedulerHandle,
+        SchedulerQueueEvent, MAX_CATCH_UP_RUNS,
+    };
+    use crate::{
+        database::{
+            entity::events::{MissedRunPolicy, TimerSchedule},
+            mock_database,
+        },
+        events::{AppEvent, AppEventReceiver},
+        twitch::manager::Twitch,
+    };
+
+    /// Spawns a scheduler, returning its handle and the receiver
+    /// for the events it triggers
+    async fn spawn_scheduler(twitch: Twitch) -> (SchedulerHandle, AppEventReceiver) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (tx, rx) = mpsc::channel(5);
+
+        tokio::spawn(SchedulerEventLoop {
+            db: mock_database().await,
+            rx,
+            events: BinaryHeap::new(),
+            current_sleep: None,
+            event_tx,
+            twitch,
+        });
+
+        (SchedulerHandle(tx), event_rx)
+    }
+
+    /// Waits up to `duration` for the scheduler to trigger a timer
+    async fn recv_timer(event_rx: &mut AppEventReceiver, duration: Duration) -> Option<Uuid> {
+        let event = tokio::time::timeout(duration, event_rx.recv())
+            .await
+            .ok()
+            .flatten()?;
+
+        match event {
+            AppEvent::TimerCompleted(completed) => Some(completed.event_id),
+            _ => None,
+        }
+    }
+
+    /// Tests that live only timers are skipped while the stream is offline
+    /// and run once the stream goes live
+    #[tokio::test]
+    async fn test_live_only_timer() {
+        let twitch = Twitch::new(mpsc::unbounded_channel().0);
+        let (handle, mut event_rx) = spawn_scheduler(twitch.clone()).await;
+
+        let event_id = Uuid::new_v4();
+        handle
+            .update_events(vec![SchedulerQueueEvent {
+                event_id,
+                schedule: EventSchedule::from_timer(1, None, &[], 0).unwrap(),
+                live_only: true,
+                missed_runs: MissedRunPolicy::Skip,
+                state: None,
+            }])
+            .await
+            .unwrap();
+
+        // Offline runs are skipped
+        assert!(recv_timer(&mut event_rx, Duration::from_millis(1500))
+            .await
+            .is_none());
+
+        twitch.set_live(true);
+        assert_eq!(
+            recv_timer(&mut event_rx, Duration::from_secs(2)).await,
+            Some(event_id)
+        );
+    }
+
+    /// Tests that intervals are aligned to the interval
+    #[test]
+    fn test_next_run_interval() {
+        let schedule = EventSchedule::from_timer(60, None, &[], 0).unwrap();
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 30).unwrap();
+        let next = schedule.next_run(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 8, 1, 0).unwrap());
+    }
+
+    /// Tests that a zero interval is rejected
+    #[test]
+    fn test_zero_interval() {
+        assert!(EventSchedule::from_timer(0, None, &[], 0).is_err());
+    }
+
+    /// Tests running at specific times of the day
+    #[test]
+    fn test_next_run_times_of_day() {
+        let schedule = EventSchedule::from_timer(
+            0,
+            Some(&TimerSchedule::TimesOfDay {
+                times: vec![
+                    NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                    NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+                ],
+            }),
+            &[],
+            0,
+        )
+        .unwrap();
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let next = schedule.next_run(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 1, 18, 0, 0).unwrap());
+
+        let next = schedule.next_run(&next).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 2, 9, 30, 0).unwrap());
+    }
+
+    /// Tests that runs are moved to the next allowed day
+    #[test]
+    fn test_next_run_days() {
+        let schedule = EventSchedule {
+            kind: EventScheduleKind::TimesOfDay(vec![NaiveTime::from_hms_opt(12, 0, 0).unwrap()]),
+            days: vec![Weekday::Sat, Weekday::Sun],
+            jitter: 0,
+        };
+
+        // 2025-01-01 is a wednesday, next allowed day is saturday the 4th
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+        let next = schedule.next_run(&after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 1, 4, 12, 0, 0).unwrap());
+    }
+
+    /// Tests that cron expressions are validated
+    #[test]
+    fn test_invalid_cron() {
+        let schedule = EventSchedule::from_timer(
+            0,
+            Some(&TimerSchedule::Cron {
+                expression: "not a cron".to_string(),
+            }),
+            &[],
+            0,
+        );
+
+        assert!(schedule.is_err());
+    }
+
+    /// Tests counting missed runs for each policy
+    #[test]
+    fn test_count_missed_runs() {
+        let schedule = EventSchedule::from_timer(60, None, &[], 0).unwrap();
+        let missed_run = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 8, 3, 30).unwrap();
+
+        let count = |policy| count_missed_runs(&schedule, policy, missed_run, now);
+
+        assert_eq!(count(MissedRunPolicy::Skip), 0);
+        assert_eq!(count(MissedRunPolicy::RunOnce), 1);
+        // 8:00, 8:01, 8:02 and 8:03 were missed
+        assert_eq!(count(MissedRunPolicy::CatchUp), 4);
+    }
+
+    /// Tests that catching up is limited
+    #[test]
+    fn test_count_missed_runs_limit() {
+        let schedule = EventSchedule::from_timer(60, None, &[], 0).unwrap();
+        let missed_run = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 8, 0, 0).unwrap();
+
+        let count = count_missed_runs(&schedule, MissedRunPolicy::CatchUp, missed_run, now);
+        assert_eq!(count, MAX_CATCH_UP_RUNS);
+    }
 }
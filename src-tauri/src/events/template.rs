@@ -0,0 +1,742 @@
+//! # Template
+//!
+//! Template language for the chat messages sent by commands and events.
+//!
+//! Variables are written as `$(name)` with optional space separated arguments
+//! such as `$(random 1 10)`. Conditionals are written as `$(if name)` or
+//! `$(if name == value)` (Also `!=`, `>`, `>=`, `<`, `<=`) followed by an
+//! optional `$(else)` and closed by `$(endif)`.
+//!
+//! Unknown variables are left in the message unchanged
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use futures::future::BoxFuture;
+use log::error;
+use serde_json::Value;
+use twitch_api::types::SubscriptionTier;
+
+use super::{
+    TwitchEventUser,
+    counter::{add_counter, get_counter},
+    matching::{EventData, EventInputData},
+};
+use crate::{database::DbPool, twitch::manager::Twitch};
+
+/// Context available to templates while rendering
+pub struct TemplateContext<'a> {
+    pub db: &'a DbPool,
+    pub twitch: &'a Twitch,
+    /// User that triggered the template
+    pub user: Option<&'a TwitchEventUser>,
+    /// Arguments provided alongside the command
+    pub args: &'a [String],
+    /// Input data from the triggering event
+    pub input_data: &'a EventInputData,
+    /// Additional variables provided by the caller (i.e the remaining
+    /// cooldown for feedback messages), these take priority over the
+    /// built in variables
+    pub variables: &'a [(&'a str, String)],
+}
+
+/// Collects the arguments for an event template, chat messages provide
+/// the words after the first word as arguments
+pub fn event_template_args(input_data: &EventInputData) -> Vec<String> {
+    match input_data {
+        EventInputData::Chat { message, .. } => message
+            .split_whitespace()
+            .skip(1)
+            .map(|arg| arg.to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Renders the provided template
+pub async fn render_template(template: &str, ctx: &TemplateContext<'_>) -> String {
+    let nodes = parse_template(template);
+    let mut output = String::with_capacity(template.len());
+    render_nodes(&nodes, ctx, &mut output).await;
+    output
+}
+
+#[derive(Debug, PartialEq)]
+enum TemplateNode {
+    /// Plain text
+    Text(String),
+    /// Variable to replace with its value
+    Variable {
+        name: String,
+        args: Vec<String>,
+        /// Original text of the variable, used when the variable is unknown
+        raw: String,
+    },
+    /// Conditional section
+    Condition {
+        condition: TemplateCondition,
+        then: Vec<TemplateNode>,
+        otherwise: Vec<TemplateNode>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct TemplateCondition {
+    /// Name of the variable to check
+    name: String,
+    /// Comparison against the variable, when not set the
+    /// condition checks if the variable is truthy
+    comparison: Option<(ComparisonOp, String)>,
+}
+
+#[derive(Debug, PartialEq)]
+enum ComparisonOp {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+}
+
+/// Conditional section that has been opened but not yet closed while parsing
+struct OpenCondition {
+    condition: TemplateCondition,
+    /// Original text of the opening tag
+    raw: String,
+    /// Nodes before the condition was opened
+    parent: Vec<TemplateNode>,
+    /// Nodes of the "then" branch once `$(else)` is reached
+    then: Option<Vec<TemplateNode>>,
+}
+
+/// Parses a template into its nodes
+fn parse_template(template: &str) -> Vec<TemplateNode> {
+    let mut nodes: Vec<TemplateNode> = Vec::new();
+    let mut open: Vec<OpenCondition> = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("$(") {
+        let Some(length) = rest[start..].find(')') else {
+            break;
+        };
+        let end = start + length;
+
+        if start > 0 {
+            nodes.push(TemplateNode::Text(rest[..start].to_string()));
+        }
+
+        let raw = &rest[start..=end];
+        let mut parts = rest[start + 2..end].split_whitespace();
+        rest = &rest[end + 1..];
+
+        match parts.next() {
+            Some("if") => match parse_condition(parts) {
+                Some(condition) => open.push(OpenCondition {
+                    condition,
+                    raw: raw.to_string(),
+                    parent: std::mem::take(&mut nodes),
+                    then: None,
+                }),
+                None => nodes.push(TemplateNode::Text(raw.to_string())),
+            },
+            Some("else") => match open.last_mut() {
+                Some(condition) if condition.then.is_none() => {
+                    condition.then = Some(std::mem::take(&mut nodes));
+                }
+                _ => nodes.push(TemplateNode::Text(raw.to_string())),
+            },
+            Some("endif") => match open.pop() {
+                Some(condition) => {
+                    let branch = std::mem::replace(&mut nodes, condition.parent);
+                    let (then, otherwise) = match condition.then {
+                        Some(then) => (then, branch),
+                        None => (branch, Vec::new()),
+                    };
+
+                    nodes.push(TemplateNode::Condition {
+                        condition: condition.condition,
+                        then,
+                        otherwise,
+                    });
+                }
+                None => nodes.push(TemplateNode::Text(raw.to_string())),
+            },
+            Some(name) => nodes.push(TemplateNode::Variable {
+                name: name.to_string(),
+                args: parts.map(|arg| arg.to_string()).collect(),
+                raw: raw.to_string(),
+            }),
+            None => nodes.push(TemplateNode::Text(raw.to_string())),
+        }
+    }
+
+    if !rest.is_empty() {
+        nodes.push(TemplateNode::Text(rest.to_string()));
+    }
+
+    // Conditions that were never closed are treated as plain text
+    while let Some(condition) = open.pop() {
+        let branch = std::mem::replace(&mut nodes, condition.parent);
+        nodes.push(TemplateNode::Text(condition.raw));
+        if let Some(then) = condition.then {
+            nodes.extend(then);
+            nodes.push(TemplateNode::Text("$(else)".to_string()));
+        }
+        nodes.extend(branch);
+    }
+
+    nodes
+}
+
+/// Parses the parts of a condition after the `if`
+fn parse_condition<'a>(mut parts: impl Iterator<Item = &'a str>) -> Option<TemplateCondition> {
+    let name = parts.next()?.to_string();
+
+    let Some(op) = parts.next() else {
+        return Some(TemplateCondition {
+            name,
+            comparison: None,
+        });
+    };
+
+    let op = match op {
+        "==" => ComparisonOp::Equal,
+        "!=" => ComparisonOp::NotEqual,
+        ">" => ComparisonOp::Greater,
+        ">=" => ComparisonOp::GreaterEqual,
+        "<" => ComparisonOp::Less,
+        "<=" => ComparisonOp::LessEqual,
+        _ => return None,
+    };
+
+    let value = parts.collect::<Vec<_>>().join(" ");
+
+    Some(TemplateCondition {
+        name,
+        comparison: Some((op, value)),
+    })
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [TemplateNode],
+    ctx: &'a TemplateContext<'_>,
+    output: &'a mut String,
+) -> BoxFuture<'a, ()> {
+    Box::pin(async move {
+        for node in nodes {
+            match node {
+                TemplateNode::Text(text) => output.push_str(text),
+                TemplateNode::Variable { name, args, raw } => {
+                    match resolve_variable(ctx, name, args).await {
+                        Some(value) => output.push_str(&value),
+                        None => output.push_str(raw),
+                    }
+                }
+                TemplateNode::Condition {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let branch = if is_condition_met(ctx, condition).await {
+                        then
+                    } else {
+                        otherwise
+                    };
+
+                    render_nodes(branch, ctx, output).await;
+                }
+            }
+        }
+    })
+}
+
+/// Checks whether a template condition is met
+async fn is_condition_met(ctx: &TemplateContext<'_>, condition: &TemplateCondition) -> bool {
+    let value = resolve_variable(ctx, &condition.name, &[])
+        .await
+        .unwrap_or_default();
+
+    let (op, expected) = match &condition.comparison {
+        Some(comparison) => comparison,
+        // Check if the value is truthy
+        None => return !(value.is_empty() || value == "0" || value == "false"),
+    };
+
+    // Compare numerically when both values are numbers
+    let ordering = match (value.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(value), Ok(expected)) => value.partial_cmp(&expected),
+        _ => Some(value.to_lowercase().cmp(&expected.to_lowercase())),
+    };
+
+    let Some(ordering) = ordering else {
+        return false;
+    };
+
+    match op {
+        ComparisonOp::Equal => ordering.is_eq(),
+        ComparisonOp::NotEqual => ordering.is_ne(),
+        ComparisonOp::Greater => ordering.is_gt(),
+        ComparisonOp::GreaterEqual => ordering.is_ge(),
+        ComparisonOp::Less => ordering.is_lt(),
+        ComparisonOp::LessEqual => ordering.is_le(),
+    }
+}
+
+/// Resolves the value for a variable, [None] if the variable is unknown
+async fn resolve_variable(
+    ctx: &TemplateContext<'_>,
+    name: &str,
+    args: &[String],
+) -> Option<String> {
+    if let Some((_, value)) = ctx.variables.iter().find(|(variable, _)| *variable == name) {
+        return Some(value.clone());
+    }
+
+    let value = match name {
+        "user" => ctx
+            .user
+            .map(|user| user.name.to_string())
+            .unwrap_or_else(|| "Anonymous".to_string()),
+        "displayName" => ctx
+            .user
+            .map(|user| user.display_name.to_string())
+            .unwrap_or_else(|| "Anonymous".to_string()),
+        "touser" => ctx.args.first().cloned().unwrap_or_default(),
+        "args" => ctx.args.join(" "),
+        "random" => random_number(args),
+        "counter" => {
+            let name = args.first()?;
+            let amount = args
+                .get(1)
+                .and_then(|amount| amount.parse().ok())
+                .unwrap_or(1);
+
+            match add_counter(ctx.db, name, amount).await {
+                Ok(value) => value.to_string(),
+                Err(err) => {
+                    error!("failed to increment template counter: {err:?}");
+                    return None;
+                }
+            }
+        }
+        "count" => {
+            let name = args.first()?;
+            match get_counter(ctx.db, name).await {
+                Ok(value) => value.to_string(),
+                Err(err) => {
+                    error!("failed to get template counter: {err:?}");
+                    return None;
+                }
+            }
+        }
+        "time" => Local::now().format("%H:%M").to_string(),
+        "date" => Local::now().format("%Y-%m-%d").to_string(),
+        "uptime" => match ctx.twitch.session_started_at() {
+            Some(started_at) if ctx.twitch.is_live() => format_duration(Utc::now() - started_at),
+            _ => "offline".to_string(),
+        },
+        "followage" => follow_age(ctx).await,
+        name => {
+            // Command arguments by position, starting from 1
+            if let Ok(index) = name.parse::<usize>() {
+                let index = index.checked_sub(1)?;
+                return Some(ctx.args.get(index).cloned().unwrap_or_default());
+            }
+
+            if let Some(path) = name.strip_prefix("input.") {
+                return input_path_value(ctx.input_data, path);
+            }
+
+            return input_variable(ctx.input_data, name);
+        }
+    };
+
+    Some(value)
+}
+
+/// Produces a random number, `$(random)` is between 1 and 100, `$(random max)`
+/// is between 1 and max and `$(random min max)` is between min and max
+fn random_number(args: &[String]) -> String {
+    let mut values = args.iter().filter_map(|arg| arg.parse::<i64>().ok());
+
+    let (min, max) = match (values.next(), values.next()) {
+        (Some(min), Some(max)) => (min.min(max), min.max(max)),
+        (Some(max), None) => (1.min(max), 1.max(max)),
+        _ => (1, 100),
+    };
+
+    rand::random_range(min..=max).to_string()
+}
+
+/// Determines how long the user has been following the channel
+async fn follow_age(ctx: &TemplateContext<'_>) -> String {
+    let Some(user) = ctx.user else {
+        return "not following".to_string();
+    };
+
+    let follower = match ctx.twitch.get_follower_by_id(&user.id).await {
+        Ok(Some(follower)) => follower,
+        Ok(None) => return "not following".to_string(),
+        Err(err) => {
+            error!("failed to get follower for template: {err:?}");
+            return "unknown".to_string();
+        }
+    };
+
+    match DateTime::parse_from_rfc3339(follower.followed_at.as_str()) {
+        Ok(followed_at) => format_age(Utc::now() - followed_at.with_timezone(&Utc)),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// Formats a duration as hours, minutes and seconds (e.g "2h 5m")
+fn format_duration(duration: TimeDelta) -> String {
+    let hours = duration.num_hours();
+    let minutes = duration.num_minutes() % 60;
+    let seconds = duration.num_seconds() % 60;
+
+    match (hours, minutes) {
+        (0, 0) => format!("{seconds}s"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+/// Formats a long duration as years, months and days (e.g "1 year 2 months")
+fn format_age(duration: TimeDelta) -> String {
+    let total_days = duration.num_days().max(0);
+    let years = total_days / 365;
+    let months = (total_days % 365) / 30;
+    let days = (total_days % 365) % 30;
+
+    let parts: Vec<String> = [(years, "year"), (months, "month"), (days, "day")]
+        .into_iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| match value {
+            1 => format!("1 {unit}"),
+            value => format!("{value} {unit}s"),
+        })
+        .collect();
+
+    if parts.is_empty() {
+        return "0 days".to_string();
+    }
+
+    parts.join(" ")
+}
+
+/// Obtains a value from the event input data by its path (e.g `$(input.choices.0.title)`)
+fn input_path_value(input_data: &EventInputData, path: &str) -> Option<String> {
+    let input_data = serde_json::to_value(input_data).ok()?;
+    let value = path
+        .split('.')
+        .try_fold(&input_data, |value, key| match value {
+            Value::Array(values) => values.get(key.parse::<usize>().ok()?),
+            value => value.get(key),
+        })?;
+
+    Some(match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// Named variables provided by specific event input data
+fn input_variable(input_data: &EventInputData, name: &str) -> Option<String> {
+    let value = match (input_data, name) {
+        (EventInputData::Redeem { user_input, .. }, "userInput") => user_input.clone(),
+        (EventInputData::Redeem { reward_name, .. }, "rewardName") => reward_name.clone(),
+        (EventInputData::Redeem { cost, .. }, "rewardCost") => cost.to_string(),
+        (EventInputData::Bits { message, .. }, "userInput") => message.clone(),
+        (EventInputData::Bits { bits, .. }, "bits") => bits.to_string(),
+        (EventInputData::AdBreakBegin { duration_seconds }, "duration") => {
+            duration_seconds.to_string()
+        }
+        (EventInputData::Subscription { tier, .. }, "tier")
+        | (EventInputData::GiftedSubscription { tier, .. }, "tier")
+        | (EventInputData::ReSubscription { tier, .. }, "tier") => {
+            format_subscription_tier(tier).to_string()
+        }
+        (EventInputData::GiftedSubscription { total, .. }, "total") => total.to_string(),
+        (EventInputData::ReSubscription { message, .. }, "userInput") => message.clone(),
+        (
+            EventInputData::ReSubscription {
+                cumulative_months, ..
+            },
+            "cumulativeMonths",
+        ) => cumulative_months.to_string(),
+        (
+            EventInputData::ReSubscription {
+                duration_months, ..
+            },
+            "durationMonths",
+        ) => duration_months.to_string(),
+        (EventInputData::Chat { message, .. }, "userInput") => message.clone(),
+        (EventInputData::Chat { captures, .. }, name) => {
+            let name = name.strip_prefix("capture.")?;
+            captures.get(name)?.clone()
+        }
+        (EventInputData::Raid { viewers }, "viewers") => viewers.to_string(),
+        (EventInputData::ShoutoutReceive { viewer_count }, "viewers") => viewer_count.to_string(),
+        (EventInputData::HypeTrain { level, .. }, "level") => level.to_string(),
+        (EventInputData::HypeTrain { total, .. }, "total") => total.to_string(),
+        (EventInputData::HypeTrain { goal, .. }, "goal") => goal.to_string(),
+        (EventInputData::Poll { title, .. }, "title")
+        | (EventInputData::Prediction { title, .. }, "title") => title.clone(),
+        (EventInputData::Poll { winning_choice, .. }, "winner") => {
+            winning_choice.clone().unwrap_or_default()
+        }
+        (EventInputData::Poll { winning_votes, .. }, "votes") => winning_votes.to_string(),
+        (
+            EventInputData::Prediction {
+                winning_outcome, ..
+            },
+            "winner",
+        ) => winning_outcome.clone().unwrap_or_default(),
+        (EventInputData::Prediction { winning_points, .. }, "points") => winning_points.to_string(),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn format_subscription_tier(tier: &SubscriptionTier) -> &'static str {
+    match tier {
+        SubscriptionTier::Tier1 => "Tier 1",
+        SubscriptionTier::Tier2 => "Tier 2",
+        SubscriptionTier::Tier3 => "Tier 3",
+        SubscriptionTier::Prime => "Prime",
+        SubscriptionTier::Other(_) => "Other",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use tokio::sync::mpsc;
+    use twitch_api::types::{DisplayName, UserId, UserName};
+
+    use super::{TemplateContext, render_template};
+    use crate::{
+        database::{DbPool, entity::events::HypeTrainPhase, mock_database},
+        events::{TwitchEventUser, matching::EventInputData},
+        twitch::manager::Twitch,
+    };
+
+    fn mock_user() -> TwitchEventUser {
+        TwitchEventUser {
+            id: UserId::from_static("mock-user-id"),
+            name: UserName::from_static("mockuser"),
+            display_name: DisplayName::from_static("Mock User"),
+        }
+    }
+
+    async fn render(
+        db: &DbPool,
+        template: &str,
+        args: &[&str],
+        input_data: &EventInputData,
+    ) -> String {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let twitch = Twitch::new(tx);
+        let user = mock_user();
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+        let ctx = TemplateContext {
+            db,
+            twitch: &twitch,
+            user: Some(&user),
+            args: &args,
+            input_data,
+            variables: &[],
+        };
+
+        render_template(template, &ctx).await
+    }
+
+    /// Tests user and argument variables
+    #[tokio::test]
+    async fn test_render_variables() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        let output = render(
+            &db,
+            "$(user) ($(displayName)) hugs $(touser) - $(2) - $(args)",
+            &["@friend", "second"],
+            &input_data,
+        )
+        .await;
+
+        assert_eq!(
+            output,
+            "mockuser (Mock User) hugs @friend - second - @friend second"
+        );
+    }
+
+    /// Tests that caller provided variables are rendered and take priority
+    #[tokio::test]
+    async fn test_render_extra_variables() {
+        let db = mock_database().await;
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let twitch = Twitch::new(tx);
+        let user = mock_user();
+
+        let ctx = TemplateContext {
+            db: &db,
+            twitch: &twitch,
+            user: Some(&user),
+            args: &[],
+            input_data: &EventInputData::None,
+            variables: &[
+                ("remaining", "5".to_string()),
+                ("user", "other".to_string()),
+            ],
+        };
+
+        let output = render_template(
+            "$(user) wait $(remaining)s$(if remaining > 1) please$(endif)",
+            &ctx,
+        )
+        .await;
+        assert_eq!(output, "other wait 5s please");
+    }
+
+    /// Tests that unknown variables and unclosed tags are left unchanged
+    #[tokio::test]
+    async fn test_render_unknown() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        let output = render(&db, "$(unknown) $(3) $(user", &[], &input_data).await;
+        assert_eq!(output, "$(unknown)  $(user");
+    }
+
+    /// Tests conditionals with and without an else branch
+    #[tokio::test]
+    async fn test_render_conditionals() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+        let template =
+            "$(if 1)Hello $(1)$(else)Hello everyone$(endif)!$(if 1 == bob) Hi bob$(endif)";
+
+        let output = render(&db, template, &["bob"], &input_data).await;
+        assert_eq!(output, "Hello bob! Hi bob");
+
+        let output = render(&db, template, &[], &input_data).await;
+        assert_eq!(output, "Hello everyone!");
+    }
+
+    /// Tests numeric comparisons within conditionals
+    #[tokio::test]
+    async fn test_render_numeric_conditionals() {
+        let db = mock_database().await;
+        let input_data = EventInputData::Raid { viewers: 25 };
+        let template = "$(if viewers >= 10)Big raid$(else)Small raid$(endif)";
+
+        let output = render(&db, template, &[], &input_data).await;
+        assert_eq!(output, "Big raid");
+
+        let input_data = EventInputData::Raid { viewers: 5 };
+        let output = render(&db, template, &[], &input_data).await;
+        assert_eq!(output, "Small raid");
+    }
+
+    /// Tests that unclosed conditionals are left as text
+    #[tokio::test]
+    async fn test_render_unclosed_conditional() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        let output = render(&db, "$(if args)Hello $(user)", &[], &input_data).await;
+        assert_eq!(output, "$(if args)Hello mockuser");
+    }
+
+    /// Tests that counters are incremented and read
+    #[tokio::test]
+    async fn test_render_counter() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        let output = render(&db, "Deaths: $(counter deaths)", &[], &input_data).await;
+        assert_eq!(output, "Deaths: 1");
+
+        let output = render(&db, "Deaths: $(counter deaths 2)", &[], &input_data).await;
+        assert_eq!(output, "Deaths: 3");
+
+        let output = render(&db, "Deaths: $(count deaths)", &[], &input_data).await;
+        assert_eq!(output, "Deaths: 3");
+    }
+
+    /// Tests that counters in branches that are not taken are not incremented
+    #[tokio::test]
+    async fn test_render_counter_branch() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        render(&db, "$(if args)$(counter deaths)$(endif)", &[], &input_data).await;
+
+        let output = render(&db, "$(count deaths)", &[], &input_data).await;
+        assert_eq!(output, "0");
+    }
+
+    /// Tests random numbers stay within the range
+    #[tokio::test]
+    async fn test_render_random() {
+        let db = mock_database().await;
+        let input_data = EventInputData::None;
+
+        for _ in 0..20 {
+            let output = render(&db, "$(random 5 10)", &[], &input_data).await;
+            let value: i64 = output.parse().unwrap();
+            assert!((5..=10).contains(&value));
+        }
+    }
+
+    /// Tests named and path based event input variables
+    #[tokio::test]
+    async fn test_render_input_data() {
+        let db = mock_database().await;
+        let input_data = EventInputData::Chat {
+            message_id: "mock-message-id".into(),
+            message: "!hello world".to_string(),
+            fragments: Default::default(),
+            cheer: None,
+            captures: HashMap::from([("name".to_string(), "world".to_string())]),
+        };
+
+        let output = render(
+            &db,
+            "$(userInput) / $(capture.name) / $(input.message)",
+            &[],
+            &input_data,
+        )
+        .await;
+
+        assert_eq!(output, "!hello world / world / !hello world");
+    }
+
+    /// Tests named and path based hype train input variables
+    #[tokio::test]
+    async fn test_render_hype_train() {
+        let db = mock_database().await;
+        let input_data = EventInputData::HypeTrain {
+            phase: HypeTrainPhase::Progress,
+            level: 3,
+            total: 2500,
+            progress: 400,
+            goal: 1600,
+        };
+
+        let output = render(
+            &db,
+            "Level $(level): $(input.progress)/$(goal) ($(total) total)",
+            &[],
+            &input_data,
+        )
+        .await;
+
+        assert_eq!(output, "Level 3: 400/1600 (2500 total)");
+    }
+}
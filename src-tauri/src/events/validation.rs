@@ -0,0 +1,270 @@
+//! # Validation
+//!
+//! Checks applied to event configs when they are saved, invalid configs are
+//! reported to the user rather than failing each time the event is matched
+
+use anyhow::{Context, bail};
+use chrono::Local;
+
+use crate::{
+    database::entity::events::{
+        ChatPatternMatchType, EventCondition, EventConditionCheck, EventConfig, EventOutcome,
+        EventTrigger,
+    },
+    twitch::limits::{validate_poll, validate_prediction},
+};
+
+use super::{matching::compile_chat_pattern, scheduler::EventSchedule};
+
+/// Validates an event config before it is saved
+pub fn validate_event_config(config: &EventConfig) -> anyhow::Result<()> {
+    validate_trigger(&config.trigger)?;
+
+    for step in &config.outcomes {
+        validate_outcome(&step.outcome)?;
+    }
+
+    for condition in &config.conditions {
+        validate_condition(condition)
+            .with_context(|| format!("invalid condition on \"{}\"", condition.field))?;
+    }
+
+    Ok(())
+}
+
+/// Validates the trigger of an event
+fn validate_trigger(trigger: &EventTrigger) -> anyhow::Result<()> {
+    match trigger {
+        EventTrigger::ChatPattern {
+            pattern,
+            match_type,
+            case_sensitive,
+        } => {
+            if pattern.is_empty() {
+                bail!("chat pattern cannot be empty");
+            }
+
+            compile_chat_pattern(pattern, *match_type, *case_sensitive)?;
+        }
+        EventTrigger::Timer {
+            interval,
+            schedule,
+            days,
+            jitter,
+            ..
+        } => {
+            let schedule = EventSchedule::from_timer(*interval, schedule.as_ref(), days, *jitter)
+                .context("invalid timer schedule")?;
+
+            if schedule.next_run(&Local::now()).is_none() {
+                bail!("timer schedule will never run");
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Validates an outcome, including the outcomes within random outcomes
+fn validate_outcome(outcome: &EventOutcome) -> anyhow::Result<()> {
+    match outcome {
+        EventOutcome::CreatePoll(poll) => validate_poll(&poll.title, &poll.choices, poll.duration),
+        EventOutcome::CreatePrediction(prediction) => {
+            validate_prediction(&prediction.title, &prediction.outcomes, prediction.duration)
+        }
+        EventOutcome::Random(random) => {
+            if random.outcomes.is_empty() {
+                bail!("random outcome must have at least one outcome");
+            }
+
+            // Weights are summed by the random pick which fails on overflow
+            let total_weight = random
+                .outcomes
+                .iter()
+                .try_fold(0u32, |total, weighted| total.checked_add(weighted.weight))
+                .context("random outcome weights are too large")?;
+            if total_weight == 0 {
+                bail!("random outcome must have an outcome with a weight above zero");
+            }
+
+            random
+                .outcomes
+                .iter()
+                .try_for_each(|weighted| validate_outcome(&weighted.outcome))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Validates a condition on the event input data
+fn validate_condition(condition: &EventCondition) -> anyhow::Result<()> {
+    if condition.field.trim().is_empty() {
+        bail!("condition field cannot be empty");
+    }
+
+    match &condition.check {
+        EventConditionCheck::Matches { pattern } => {
+            compile_chat_pattern(pattern, ChatPatternMatchType::Regex, true)?;
+        }
+        EventConditionCheck::Between { range } if range.min > range.max => {
+            bail!("minimum cannot be greater than the maximum");
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveTime;
+
+    use super::{validate_condition, validate_outcome, validate_trigger};
+    use crate::database::entity::{
+        events::{
+            ChatPatternMatchType, EventCondition, EventConditionCheck, EventOutcome,
+            EventOutcomeCreatePoll, EventOutcomeRandom, EventOutcomeSendChat, EventTrigger,
+            MissedRunPolicy, TimerSchedule, WeightedEventOutcome,
+        },
+        shared::MinMax,
+    };
+
+    fn chat_pattern(pattern: &str, match_type: ChatPatternMatchType) -> EventTrigger {
+        EventTrigger::ChatPattern {
+            pattern: pattern.to_string(),
+            match_type,
+            case_sensitive: false,
+        }
+    }
+
+    /// Tests that chat patterns must compile to be saved
+    #[test]
+    fn test_validate_chat_pattern() {
+        let is_valid = |pattern: &str, match_type| {
+            validate_trigger(&chat_pattern(pattern, match_type)).is_ok()
+        };
+
+        assert!(is_valid("*bonk*", ChatPatternMatchType::Glob));
+        assert!(is_valid(r"(?<amount>\d+)", ChatPatternMatchType::Regex));
+        assert!(is_valid("(bonk", ChatPatternMatchType::Substring));
+
+        assert!(!is_valid("[bonk", ChatPatternMatchType::Glob));
+        assert!(!is_valid("(bonk", ChatPatternMatchType::Regex));
+        assert!(!is_valid("", ChatPatternMatchType::Substring));
+    }
+
+    /// Tests that timer schedules must be valid to be saved
+    #[test]
+    fn test_validate_timer_schedule() {
+        let is_valid = |interval, schedule| {
+            validate_trigger(&EventTrigger::Timer {
+                interval,
+                min_chat_messages: 0,
+                schedule,
+                days: Vec::new(),
+                jitter: 0,
+                missed_runs: MissedRunPolicy::Skip,
+            })
+            .is_ok()
+        };
+        let cron = |expression: &str| {
+            Some(TimerSchedule::Cron {
+                expression: expression.to_string(),
+            })
+        };
+
+        assert!(is_valid(60, None));
+        assert!(is_valid(0, cron("*/5 * * * *")));
+        assert!(is_valid(
+            0,
+            Some(TimerSchedule::TimesOfDay {
+                times: vec![NaiveTime::from_hms_opt(18, 0, 0).unwrap()],
+            })
+        ));
+
+        assert!(!is_valid(0, None));
+        assert!(!is_valid(0, cron("not a cron")));
+        assert!(!is_valid(0, cron("0 0 31 2 *")));
+        assert!(!is_valid(
+            0,
+            Some(TimerSchedule::TimesOfDay { times: Vec::new() })
+        ));
+    }
+
+    /// Tests that conditions with invalid patterns or ranges cannot be saved
+    #[test]
+    fn test_validate_condition() {
+        let is_valid = |field: &str, check| {
+            validate_condition(&EventCondition {
+                field: field.to_string(),
+                check,
+            })
+            .is_ok()
+        };
+        let pattern = |pattern: &str| EventConditionCheck::Matches {
+            pattern: pattern.to_string(),
+        };
+        let between = |min, max| EventConditionCheck::Between {
+            range: MinMax { min, max },
+        };
+
+        assert!(is_valid("userInput", pattern("^#[0-9a-f]{6}$")));
+        assert!(is_valid("bits", between(1.0, 1.0)));
+
+        assert!(!is_valid("userInput", pattern("([0-9")));
+        assert!(!is_valid("bits", between(5.0, 1.0)));
+        assert!(!is_valid(
+            " ",
+            EventConditionCheck::GreaterThan { value: 1.0 }
+        ));
+    }
+
+    /// Tests that polls are validated, including polls within random outcomes
+    #[test]
+    fn test_validate_outcome_poll() {
+        let poll = |choices: &[&str]| {
+            EventOutcome::CreatePoll(EventOutcomeCreatePoll {
+                title: "Question".to_string(),
+                choices: choices.iter().map(|choice| choice.to_string()).collect(),
+                duration: 60,
+            })
+        };
+        let random = |outcome| {
+            EventOutcome::Random(EventOutcomeRandom {
+                outcomes: vec![WeightedEventOutcome { weight: 1, outcome }],
+            })
+        };
+
+        assert!(validate_outcome(&poll(&["Yes", "No"])).is_ok());
+        assert!(validate_outcome(&random(poll(&["Yes", "No"]))).is_ok());
+
+        assert!(validate_outcome(&poll(&["Yes"])).is_err());
+        assert!(validate_outcome(&random(poll(&["Yes"]))).is_err());
+    }
+
+    /// Tests that random outcomes must have an outcome that can be picked
+    #[test]
+    fn test_validate_outcome_random() {
+        let random = |weights: &[u32]| {
+            EventOutcome::Random(EventOutcomeRandom {
+                outcomes: weights
+                    .iter()
+                    .map(|weight| WeightedEventOutcome {
+                        weight: *weight,
+                        outcome: EventOutcome::SendChatMessage(EventOutcomeSendChat {
+                            template: "Hello".to_string(),
+                        }),
+                    })
+                    .collect(),
+            })
+        };
+
+        assert!(validate_outcome(&random(&[1])).is_ok());
+        assert!(validate_outcome(&random(&[0, 3])).is_ok());
+
+        assert!(validate_outcome(&random(&[])).is_err());
+        assert!(validate_outcome(&random(&[0, 0])).is_err());
+        assert!(validate_outcome(&random(&[u32::MAX, 1])).is_err());
+    }
+}
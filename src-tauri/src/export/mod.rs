@@ -1,13 +1,18 @@
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::database::{
-    entity::{
-        command_alias::CommandAliasModel,
-        commands::{CommandConfig, CommandModel, CreateCommand},
-        events::{CreateEvent, EventConfig, EventModel},
+use crate::{
+    database::{
+        entity::{
+            command_alias::CommandAliasModel,
+            commands::{CommandConfig, CommandModel, CommandOutcome, CreateCommand},
+            events::{CreateEvent, EventConfig, EventModel, EventOutcome},
+            shared::ScriptPermissions,
+        },
+        DbPool,
     },
-    DbPool,
+    events::{arguments::validate_command_arguments, validation::validate_event_config},
 };
 
 #[derive(Serialize, Deserialize)]
@@ -50,7 +55,17 @@ pub async fn import_commands(
     db: &DbPool,
     commands: Vec<ExportedCommandModel>,
 ) -> anyhow::Result<()> {
-    for command in commands {
+    // Validate every command before importing so invalid files are not partially imported
+    for command in &commands {
+        validate_command_arguments(&command.config.arguments)
+            .with_context(|| format!("invalid command \"{}\"", command.name))?;
+    }
+
+    for mut command in commands {
+        if let CommandOutcome::Script { permissions, .. } = &mut command.config.outcome {
+            restrict_undeclared_permissions(permissions);
+        }
+
         CommandModel::create(
             db,
             CreateCommand {
@@ -88,7 +103,19 @@ pub async fn export_events(db: &DbPool, ids: &[Uuid]) -> anyhow::Result<Vec<Expo
 }
 
 /// Import a collection of events
-pub async fn import_events(db: &DbPool, events: Vec<ExportedEventModel>) -> anyhow::Result<()> {
+pub async fn import_events(db: &DbPool, mut events: Vec<ExportedEventModel>) -> anyhow::Result<()> {
+    // Validate every event before importing so invalid files are not partially imported
+    for event in &events {
+        validate_event_config(&event.config)
+            .with_context(|| format!("invalid event \"{}\"", event.name))?;
+    }
+
+    for event in &mut events {
+        for step in &mut event.config.outcomes {
+            restrict_outcome_permissions(&mut step.outcome);
+        }
+    }
+
     for event in events {
         EventModel::create(
             db,
@@ -103,3 +130,139 @@ pub async fn import_events(db: &DbPool, events: Vec<ExportedEventModel>) -> anyh
 
     Ok(())
 }
+
+/// Imported scripts that do not declare any permissions are denied all
+/// access instead of the unrestricted access given to local scripts
+fn restrict_undeclared_permissions(permissions: &mut Option<ScriptPermissions>) {
+    permissions.get_or_insert_with(ScriptPermissions::default);
+}
+
+/// Restricts the permissions of any scripts within the `outcome`
+fn restrict_outcome_permissions(outcome: &mut EventOutcome) {
+    match outcome {
+        EventOutcome::Script(script) => restrict_undeclared_permissions(&mut script.permissions),
+        EventOutcome::Random(random) => {
+            for weighted in &mut random.outcomes {
+                restrict_outcome_permissions(&mut weighted.outcome);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Permissions requested by the scripts of an event or command that
+/// is being imported
+#[derive(Serialize)]
+pub struct ImportScriptPermissions {
+    /// Name of the event or command
+    pub name: String,
+    /// Permissions each script will have once imported, scripts that do
+    /// not declare any permissions are denied all access
+    pub scripts: Vec<ScriptPermissions>,
+}
+
+/// Collects the permissions requested by the scripts of the commands,
+/// commands without scripts are excluded
+pub fn command_script_permissions(
+    commands: &[ExportedCommandModel],
+) -> Vec<ImportScriptPermissions> {
+    commands
+        .iter()
+        .filter_map(|command| match &command.config.outcome {
+            CommandOutcome::Script { permissions, .. } => Some(ImportScriptPermissions {
+                name: command.name.clone(),
+                scripts: vec![permissions.clone().unwrap_or_default()],
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collects the permissions requested by the scripts of the events,
+/// events without scripts are excluded
+pub fn event_script_permissions(events: &[ExportedEventModel]) -> Vec<ImportScriptPermissions> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let mut scripts = Vec::new();
+            for step in &event.config.outcomes {
+                collect_outcome_permissions(&step.outcome, &mut scripts);
+            }
+
+            (!scripts.is_empty()).then(|| ImportScriptPermissions {
+                name: event.name.clone(),
+                scripts,
+            })
+        })
+        .collect()
+}
+
+/// Collects the permissions of any scripts within the `outcome`
+fn collect_outcome_permissions(outcome: &EventOutcome, scripts: &mut Vec<ScriptPermissions>) {
+    match outcome {
+        EventOutcome::Script(script) => {
+            scripts.push(script.permissions.clone().unwrap_or_default())
+        }
+        EventOutcome::Random(random) => {
+            for weighted in &random.outcomes {
+                collect_outcome_permissions(&weighted.outcome, scripts);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{restrict_outcome_permissions, restrict_undeclared_permissions};
+    use crate::database::entity::{
+        events::{EventOutcome, EventOutcomeRandom, EventOutcomeScript, WeightedEventOutcome},
+        shared::ScriptPermissions,
+    };
+
+    fn script(permissions: Option<ScriptPermissions>) -> EventOutcome {
+        EventOutcome::Script(EventOutcomeScript {
+            script: String::new(),
+            permissions,
+            limits: Default::default(),
+        })
+    }
+
+    /// Tests that imported scripts without permissions are denied all access
+    /// while declared permissions are kept
+    #[test]
+    fn test_restrict_undeclared_permissions() {
+        let mut permissions = None;
+        restrict_undeclared_permissions(&mut permissions);
+        assert_eq!(permissions, Some(ScriptPermissions::default()));
+
+        let declared = ScriptPermissions {
+            twitch: true,
+            ..Default::default()
+        };
+        let mut permissions = Some(declared.clone());
+        restrict_undeclared_permissions(&mut permissions);
+        assert_eq!(permissions, Some(declared));
+    }
+
+    /// Tests that scripts nested within random outcomes are restricted
+    #[test]
+    fn test_restrict_random_outcome_permissions() {
+        let mut outcome = EventOutcome::Random(EventOutcomeRandom {
+            outcomes: vec![WeightedEventOutcome {
+                weight: 1,
+                outcome: script(None),
+            }],
+        });
+
+        restrict_outcome_permissions(&mut outcome);
+
+        let EventOutcome::Random(random) = outcome else {
+            panic!("expected random outcome");
+        };
+        assert!(matches!(
+            &random.outcomes[0].outcome,
+            EventOutcome::Script(script) if script.permissions == Some(ScriptPermissions::default())
+        ));
+    }
+}
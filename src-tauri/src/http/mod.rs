@@ -4,6 +4,7 @@
 
 use crate::{
     database::DbPool,
+    events::pause::ProcessingPause,
     overlay::{OverlayDataStore, OverlayMessageReceiver, OverlayMessageSender},
     storage::Storage,
     twitch::manager::Twitch,
@@ -35,6 +36,7 @@ pub struct HttpExtensions {
     pub twitch: Twitch,
     pub overlay_data: OverlayDataStore,
     pub storage: Storage,
+    pub pause: ProcessingPause,
 }
 
 pub async fn start_http_server(listener: TcpListener, extensions: HttpExtensions) {
@@ -47,8 +49,12 @@ pub async fn start_http_server(listener: TcpListener, extensions: HttpExtensions
         .layer(Extension(extensions.twitch))
         .layer(Extension(extensions.overlay_data))
         .layer(Extension(extensions.storage))
+        .layer(Extension(extensions.pause))
         .layer(CorsLayer::very_permissive());
 
+    // Peer addresses are used to restrict control routes to local tools
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+
     if let Err(cause) = axum::serve(listener, app)
         // Attach graceful shutdown to the shutdown receiver
         .with_graceful_shutdown(async move {
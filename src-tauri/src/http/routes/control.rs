@@ -0,0 +1,94 @@
+use axum::{
+    extract::ConnectInfo,
+    http::{header::ORIGIN, HeaderMap},
+    Extension, Json,
+};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use thiserror::Error;
+
+use crate::{
+    events::pause::{PauseSnapshot, ProcessingPause},
+    http::error::{HttpError, HttpResult},
+};
+
+#[derive(Deserialize)]
+pub struct SetPaused {
+    pub paused: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum ControlError {
+    /// Request was not sent by a local tool
+    #[error("control requests must be sent from this computer")]
+    NotLocal,
+}
+
+impl HttpError for ControlError {
+    fn status(&self) -> StatusCode {
+        StatusCode::FORBIDDEN
+    }
+}
+
+/// GET /control/pause
+///
+/// Get the current state of the event and command processing pause
+pub async fn get_pause(Extension(pause): Extension<ProcessingPause>) -> Json<PauseSnapshot> {
+    Json(pause.snapshot())
+}
+
+/// PUT /control/pause
+///
+/// Pause or resume all event and command processing, only
+/// allowed for local tools
+pub async fn set_pause(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(pause): Extension<ProcessingPause>,
+    Json(req): Json<SetPaused>,
+) -> HttpResult<PauseSnapshot> {
+    ensure_local_request(&addr, &headers)?;
+
+    pause.set_paused(req.paused);
+    Ok(Json(pause.snapshot()))
+}
+
+/// Ensures a control request was sent by a tool on this computer
+///
+/// The server is reachable from the local network and allows cross origin
+/// requests for the overlay, so requests from other machines and requests
+/// made by web pages (Which always include an origin) are rejected
+fn ensure_local_request(addr: &SocketAddr, headers: &HeaderMap) -> Result<(), ControlError> {
+    if !addr.ip().is_loopback() || headers.contains_key(ORIGIN) {
+        return Err(ControlError::NotLocal);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use axum::http::{header::ORIGIN, HeaderMap, HeaderValue};
+    use std::net::SocketAddr;
+
+    use super::ensure_local_request;
+
+    /// Tests that only loopback requests without an origin are allowed
+    #[test]
+    fn test_ensure_local_request() {
+        let local: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let local_v6: SocketAddr = "[::1]:5000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:5000".parse().unwrap();
+
+        let mut browser = HeaderMap::new();
+        browser.insert(ORIGIN, HeaderValue::from_static("https://example.com"));
+
+        assert!(ensure_local_request(&local, &HeaderMap::new()).is_ok());
+        assert!(ensure_local_request(&local_v6, &HeaderMap::new()).is_ok());
+
+        assert!(ensure_local_request(&remote, &HeaderMap::new()).is_err());
+        assert!(ensure_local_request(&local, &browser).is_err());
+        assert!(ensure_local_request(&remote, &browser).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use axum::{
 };
 
 mod calibration;
+mod control;
 mod data;
 mod items;
 mod oauth;
@@ -49,4 +50,9 @@ pub fn router() -> Router {
         .route("/sounds", get(sounds::all))
         .route("/sounds/query-by-name", post(sounds::query_by_name))
         .route("/sounds/query-by-id", post(sounds::query_by_id))
+        // Pausing event and command processing
+        .route(
+            "/control/pause",
+            get(control::get_pause).put(control::set_pause),
+        )
 }
@@ -1,7 +1,10 @@
 use anyhow::Context;
 use commands::events::update_scheduler_events;
 use database::{DbPool, clean_old_data, entity::app_data::AppDataModel};
-use events::{processing::process_events, scheduler::create_scheduler};
+use events::{
+    pause::ProcessingPause, processing::process_events, queue::EventQueue,
+    scheduler::create_scheduler,
+};
 use http::{HttpExtensions, ServerPort, create_http_socket};
 use log::error;
 use overlay::{OverlayDataStore, create_overlay_channel};
@@ -36,7 +39,9 @@ mod twitch;
 pub fn run() {
     env_logger::init();
 
-    use crate::commands::{calibration, commands, data, events, items, sounds, test, twitch};
+    use crate::commands::{
+        calibration, commands, data, events, items, key_value, sounds, test, twitch,
+    };
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -74,6 +79,9 @@ pub fn run() {
             twitch::logout,
             twitch::get_redeems_list,
             twitch::refresh_redeems_list,
+            twitch::create_redeem,
+            twitch::update_redeem,
+            twitch::set_redeem_paused,
             // Item manipulation commands
             items::get_item_by_id,
             items::get_items,
@@ -102,6 +110,15 @@ pub fn run() {
             commands::delete_command_executions,
             commands::export_commands,
             commands::import_commands,
+            commands::get_import_commands_permissions,
+            commands::get_counters,
+            commands::set_counter,
+            commands::delete_counter,
+            // Key value commands
+            key_value::get_key_values,
+            key_value::set_key_value,
+            key_value::delete_key_value,
+            key_value::delete_key_values_by_prefix,
             // Event commands
             events::get_events,
             events::get_event_by_id,
@@ -116,6 +133,13 @@ pub fn run() {
             events::delete_event_logs,
             events::export_events,
             events::import_events,
+            events::get_import_events_permissions,
+            events::get_event_queue,
+            events::skip_queued_event,
+            events::clear_event_queue,
+            events::set_event_queue_paused,
+            events::get_processing_paused,
+            events::set_processing_paused,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -137,11 +161,17 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
     let http_port = block_on(AppDataModel::get_http_port(&db))
         .unwrap_or(database::entity::app_data::default_http_port());
 
+    let script_runtime_config = block_on(AppDataModel::get_main_config(&db))
+        .map(|main_config| main_config.script_runtime)
+        .unwrap_or_default();
+
     let (event_tx, event_rx) = mpsc::unbounded_channel();
     let (overlay_tx, overlay_rx) = create_overlay_channel();
 
     let twitch = Twitch::new(event_tx.clone());
     let overlay_data = OverlayDataStore::new(handle.clone());
+    let event_queue = EventQueue::new(handle.clone());
+    let pause = ProcessingPause::new(handle.clone(), event_tx.clone(), event_queue.clone());
 
     let script_handle = create_script_executor(
         app_data_path.join("modules"),
@@ -150,10 +180,11 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
             overlay_sender: overlay_tx.clone(),
             twitch: twitch.clone(),
         },
+        script_runtime_config,
     );
 
     // Create background event scheduler
-    let scheduler_handle = create_scheduler(event_tx);
+    let scheduler_handle = create_scheduler(db.clone(), event_tx, twitch.clone());
 
     let storage = Storage::new_fs(handle)?;
 
@@ -175,6 +206,12 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
     // Provide access to the scheduler
     app.manage(scheduler_handle);
 
+    // Provide access to the event queue
+    app.manage(event_queue.clone());
+
+    // Provide access to the processing pause switch
+    app.manage(pause.clone());
+
     // Provide access to twitch manager and event sender
     app.manage(overlay_tx.clone());
     app.manage(twitch.clone());
@@ -203,6 +240,8 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
         script_handle,
         overlay_tx.clone(),
         handle.clone(),
+        event_queue,
+        pause.clone(),
         event_rx,
     ));
 
@@ -219,6 +258,7 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
                     twitch,
                     overlay_data,
                     storage,
+                    pause,
                 },
             ));
         }
@@ -1,3 +1,5 @@
 pub mod module_loader;
 mod ops;
+mod permissions;
 pub mod runtime;
+mod watchdog;
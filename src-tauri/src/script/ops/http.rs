@@ -2,12 +2,13 @@
 //!
 //! API for performing HTTP requests from within the JS runtime
 
-use deno_core::op2;
+use crate::script::permissions::{check_http_access, http_redirect_policy};
+use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, DurationMilliSeconds, Map, serde_as};
-use std::time::Duration;
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
 /// Request structure from JS to perform an HTTP request
 #[serde_as]
@@ -77,9 +78,20 @@ pub enum HttpResponseBody {
 /// Operation for performing a GET request to a specific URL from JS
 #[op2]
 #[serde]
-pub async fn op_http_request(#[serde] req: HttpRequest) -> Result<HttpResponse, JsErrorBox> {
-    // Get or create HTTP client
-    let client = reqwest::Client::new();
+pub async fn op_http_request(
+    state: Rc<RefCell<OpState>>,
+    #[serde] req: HttpRequest,
+) -> Result<HttpResponse, JsErrorBox> {
+    check_http_access(&state, &req.url)?;
+
+    // Create HTTP client, redirects are checked against the script permissions
+    let client = reqwest::Client::builder()
+        .redirect(http_redirect_policy(&state)?)
+        .build()
+        .map_err(|err| {
+            log::error!("failed to create http client: {err}");
+            JsErrorBox::generic("failed to create http client")
+        })?;
 
     let mut builder = client.request(req.method, req.url);
 
@@ -1,7 +1,11 @@
 use crate::{
-    database::entity::key_value::{CreateKeyValue, KeyValueModel, KeyValueType},
-    script::runtime::ScriptRuntimeDataExt,
+    database::entity::key_value::{CreateKeyValue, KeyValueModel, KeyValueNamespace, KeyValueType},
+    script::{
+        permissions::{check_kv_access, restricted_context},
+        runtime::{RuntimeExecutionContext, ScriptRuntimeDataExt},
+    },
 };
+use chrono::{DateTime, TimeDelta, Utc};
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
 use std::{cell::RefCell, rc::Rc};
@@ -12,6 +16,7 @@ pub async fn op_kv_get(
     state: Rc<RefCell<OpState>>,
     #[string] key: String,
 ) -> Result<Option<String>, JsErrorBox> {
+    check_kv_access(&state, &key)?;
     let db = state.db()?;
     let key_value = KeyValueModel::get_by_key(&db, &key).await.map_err(|err| {
         log::error!("failed to load key from database: {err}");
@@ -27,6 +32,7 @@ pub async fn op_kv_remove(
     state: Rc<RefCell<OpState>>,
     #[string] key: String,
 ) -> Result<(), JsErrorBox> {
+    check_kv_access(&state, &key)?;
     let db = state.db()?;
     KeyValueModel::delete_by_key(&db, &key)
         .await
@@ -43,17 +49,137 @@ pub async fn op_kv_set(
     #[string] ty: String,
     #[string] key: String,
     #[string] value: String,
+    #[serde] ttl: Option<u64>,
 ) -> Result<(), JsErrorBox> {
+    check_kv_access(&state, &key)?;
     let db = state.db()?;
-    let ty = serde_json::from_str::<KeyValueType>(&format!("\"{ty}\"")).map_err(|err| {
-        log::error!("failed to parse key value data: {err}");
-        JsErrorBox::generic("failed to parse key value data")
+    let ty = parse_key_value_type(&ty)?;
+    let expires_at = ttl_expires_at(ttl);
+    KeyValueModel::create(
+        &db,
+        CreateKeyValue {
+            key,
+            value,
+            ty,
+            expires_at,
+        },
+    )
+    .await
+    .map_err(|err| {
+        log::error!("failed to create kv in database: {err}");
+        JsErrorBox::generic("failed to kv in database")
     })?;
-    KeyValueModel::create(&db, CreateKeyValue { key, value, ty })
+    Ok(())
+}
+
+/// Atomically adds `amount` to a number key value, returning the new value
+#[op2]
+#[serde]
+pub async fn op_kv_increment(
+    state: Rc<RefCell<OpState>>,
+    #[string] key: String,
+    #[serde] amount: i64,
+) -> Result<i64, JsErrorBox> {
+    check_kv_access(&state, &key)?;
+    let db = state.db()?;
+    KeyValueModel::increment(&db, &key, amount)
         .await
         .map_err(|err| {
-            log::error!("failed to create kv in database: {err}");
-            JsErrorBox::generic("failed to kv in database")
-        })?;
-    Ok(())
+            log::error!("failed to increment kv in database: {err}");
+            JsErrorBox::generic("failed to increment kv in database")
+        })
+}
+
+/// Replaces a key value only when its current value matches `expected`,
+/// returns whether the value was replaced
+#[op2]
+pub async fn op_kv_compare_and_set(
+    state: Rc<RefCell<OpState>>,
+    #[string] ty: String,
+    #[string] key: String,
+    #[serde] expected: Option<String>,
+    #[string] value: String,
+    #[serde] ttl: Option<u64>,
+) -> Result<bool, JsErrorBox> {
+    check_kv_access(&state, &key)?;
+    let db = state.db()?;
+    let ty = parse_key_value_type(&ty)?;
+    let expires_at = ttl_expires_at(ttl);
+    KeyValueModel::compare_and_set(
+        &db,
+        expected.as_deref(),
+        CreateKeyValue {
+            key,
+            value,
+            ty,
+            expires_at,
+        },
+    )
+    .await
+    .map_err(|err| {
+        log::error!("failed to update kv in database: {err}");
+        JsErrorBox::generic("failed to update kv in database")
+    })
+}
+
+/// List all key values whose key starts with `prefix`
+#[op2]
+#[serde]
+pub async fn op_kv_list(
+    state: Rc<RefCell<OpState>>,
+    #[string] prefix: String,
+) -> Result<Vec<KeyValueModel>, JsErrorBox> {
+    check_kv_access(&state, &prefix)?;
+    let db = state.db()?;
+    KeyValueModel::get_by_key_prefix(&db, &prefix)
+        .await
+        .map_err(|err| {
+            log::error!("failed to load keys from database: {err}");
+            JsErrorBox::generic("failed to load keys from database")
+        })
+}
+
+/// Remove all key values whose key starts with `prefix`, returns
+/// the number of removed key values
+#[op2]
+#[serde]
+pub async fn op_kv_remove_prefix(
+    state: Rc<RefCell<OpState>>,
+    #[string] prefix: String,
+) -> Result<u64, JsErrorBox> {
+    check_kv_access(&state, &prefix)?;
+    let db = state.db()?;
+    KeyValueModel::delete_by_key_prefix(&db, &prefix)
+        .await
+        .map_err(|err| {
+            log::error!("failed to delete keys from database: {err}");
+            JsErrorBox::generic("failed to delete keys from database")
+        })
+}
+
+/// Get the key prefix for the namespace of the event or command
+/// the script is running for
+///
+/// Restricted scripts always use the context of their runtime, the
+/// context provided by JS is only used by unrestricted scripts which
+/// can already access any key
+#[op2]
+#[string]
+pub fn op_kv_namespace(state: &mut OpState, #[serde] ctx: RuntimeExecutionContext) -> String {
+    let ctx = restricted_context(state).unwrap_or(ctx);
+    KeyValueNamespace::from(ctx).prefix()
+}
+
+fn parse_key_value_type(ty: &str) -> Result<KeyValueType, JsErrorBox> {
+    serde_json::from_str::<KeyValueType>(&format!("\"{ty}\"")).map_err(|err| {
+        log::error!("failed to parse key value data: {err}");
+        JsErrorBox::generic("failed to parse key value data")
+    })
+}
+
+/// Converts a time to live in milliseconds into the time it expires at,
+/// times too far in the future are treated as never expiring
+fn ttl_expires_at(ttl: Option<u64>) -> Option<DateTime<Utc>> {
+    let ttl = TimeDelta::try_milliseconds(i64::try_from(ttl?).ok()?)?;
+    Utc::now().checked_add_signed(ttl)
 }
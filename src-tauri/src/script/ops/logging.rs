@@ -4,16 +4,19 @@
 
 use std::{cell::RefCell, rc::Rc};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use deno_core::{op2, OpState};
 use deno_error::JsErrorBox;
 use log::error;
 
 use crate::{
-    database::entity::{
-        command_log::{CommandLogsModel, CreateCommandLog},
-        event_log::{CreateEventLog, EventLogsModel},
-        shared::LoggingLevelDb,
+    database::{
+        entity::{
+            command_log::{CommandLogsModel, CreateCommandLog},
+            event_log::{CreateEventLog, EventLogsModel},
+            shared::LoggingLevelDb,
+        },
+        DbPool, DbResult,
     },
     script::runtime::{RuntimeExecutionContext, ScriptRuntimeDataExt},
 };
@@ -48,34 +51,7 @@ pub fn op_log(
         let created_at = Utc::now();
 
         tokio::spawn(async move {
-            let result = match ctx {
-                RuntimeExecutionContext::Event { event_id } => {
-                    EventLogsModel::create(
-                        &db,
-                        CreateEventLog {
-                            event_id,
-                            level,
-                            message,
-                            created_at,
-                        },
-                    )
-                    .await
-                }
-                RuntimeExecutionContext::Command { command_id } => {
-                    CommandLogsModel::create(
-                        &db,
-                        CreateCommandLog {
-                            command_id,
-                            level,
-                            message,
-                            created_at,
-                        },
-                    )
-                    .await
-                }
-            };
-
-            if let Err(err) = result {
+            if let Err(err) = persist_log(&db, ctx, level, message, created_at).await {
                 error!("failed to persist log: {:?}", err);
             }
         });
@@ -83,3 +59,39 @@ pub fn op_log(
 
     Ok(())
 }
+
+/// Stores a log message against the event or command the execution belongs to
+pub async fn persist_log(
+    db: &DbPool,
+    ctx: RuntimeExecutionContext,
+    level: LoggingLevelDb,
+    message: String,
+    created_at: DateTime<Utc>,
+) -> DbResult<()> {
+    match ctx {
+        RuntimeExecutionContext::Event { event_id } => {
+            EventLogsModel::create(
+                db,
+                CreateEventLog {
+                    event_id,
+                    level,
+                    message,
+                    created_at,
+                },
+            )
+            .await
+        }
+        RuntimeExecutionContext::Command { command_id } => {
+            CommandLogsModel::create(
+                db,
+                CreateCommandLog {
+                    command_id,
+                    level,
+                    message,
+                    created_at,
+                },
+            )
+            .await
+        }
+    }
+}
@@ -1,9 +1,19 @@
-use crate::{script::runtime::ScriptRuntimeDataExt, twitch::manager::TWITCH_CLIENT_ID};
+use crate::{
+    script::{permissions::check_twitch_access, runtime::ScriptRuntimeDataExt},
+    twitch::{
+        limits::{
+            validate_create_reward, validate_poll, validate_prediction, validate_reward_id,
+            validate_update_reward,
+        },
+        manager::{CreateReward, TWITCH_CLIENT_ID, UpdateReward},
+    },
+};
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
 use serde::Serialize;
 use std::{cell::RefCell, rc::Rc};
 use twitch_api::{
+    helix::points::CustomRewardRedemptionStatus,
     twitch_oauth2::{AccessToken, ClientId},
     types::UserId,
 };
@@ -21,6 +31,7 @@ pub struct TwitchCredentials {
 pub async fn op_twitch_get_credentials(
     state: Rc<RefCell<OpState>>,
 ) -> Result<Option<TwitchCredentials>, JsErrorBox> {
+    check_twitch_access(&state)?;
     let twitch = state.twitch()?;
     let token = match twitch.get_user_token() {
         Some(value) => value,
@@ -33,3 +44,114 @@ pub async fn op_twitch_get_credentials(
         client_id: TWITCH_CLIENT_ID.to_owned(),
     }))
 }
+
+/// Create a twitch poll, returns the ID of the created poll
+#[op2]
+#[string]
+pub async fn op_twitch_create_poll(
+    state: Rc<RefCell<OpState>>,
+    #[string] title: String,
+    #[serde] choices: Vec<String>,
+    duration: u32,
+) -> Result<String, JsErrorBox> {
+    check_twitch_access(&state)?;
+    validate_poll(&title, &choices, duration)
+        .map_err(|err| JsErrorBox::generic(err.to_string()))?;
+    let twitch = state.twitch()?;
+    let poll = twitch
+        .create_poll(&title, &choices, duration)
+        .await
+        .map_err(|err| {
+            log::error!("failed to create poll: {err:?}");
+            JsErrorBox::generic("failed to create poll")
+        })?;
+
+    Ok(poll.id.to_string())
+}
+
+/// Create a twitch prediction, returns the ID of the created prediction
+#[op2]
+#[string]
+pub async fn op_twitch_create_prediction(
+    state: Rc<RefCell<OpState>>,
+    #[string] title: String,
+    #[serde] outcomes: Vec<String>,
+    duration: u32,
+) -> Result<String, JsErrorBox> {
+    check_twitch_access(&state)?;
+    validate_prediction(&title, &outcomes, duration)
+        .map_err(|err| JsErrorBox::generic(err.to_string()))?;
+    let twitch = state.twitch()?;
+    let prediction = twitch
+        .create_prediction(&title, &outcomes, duration)
+        .await
+        .map_err(|err| {
+            log::error!("failed to create prediction: {err:?}");
+            JsErrorBox::generic("failed to create prediction")
+        })?;
+
+    Ok(prediction.id.to_string())
+}
+
+/// Update the status of a channel point redemption, used to fulfil or
+/// refund (cancel) redemptions
+#[op2]
+pub async fn op_twitch_update_redemption_status(
+    state: Rc<RefCell<OpState>>,
+    #[string] reward_id: String,
+    #[string] redemption_id: String,
+    #[serde] status: CustomRewardRedemptionStatus,
+) -> Result<(), JsErrorBox> {
+    check_twitch_access(&state)?;
+    let twitch = state.twitch()?;
+    twitch
+        .update_redemption_status(&reward_id, &redemption_id, status)
+        .await
+        .map_err(|err| {
+            log::error!("failed to update redemption status: {err:?}");
+            JsErrorBox::generic("failed to update redemption status")
+        })?;
+
+    Ok(())
+}
+
+/// Create a custom channel point reward, returns the ID of the created reward
+#[op2]
+#[string]
+pub async fn op_twitch_create_reward(
+    state: Rc<RefCell<OpState>>,
+    #[serde] create: CreateReward,
+) -> Result<String, JsErrorBox> {
+    check_twitch_access(&state)?;
+    validate_create_reward(&create).map_err(|err| JsErrorBox::generic(err.to_string()))?;
+    let twitch = state.twitch()?;
+    let reward = twitch.create_reward(create).await.map_err(|err| {
+        log::error!("failed to create reward: {err:?}");
+        JsErrorBox::generic("failed to create reward")
+    })?;
+
+    Ok(reward.id.to_string())
+}
+
+/// Update a custom channel point reward
+#[op2]
+pub async fn op_twitch_update_reward(
+    state: Rc<RefCell<OpState>>,
+    #[string] reward_id: String,
+    #[serde] update: UpdateReward,
+) -> Result<(), JsErrorBox> {
+    check_twitch_access(&state)?;
+    validate_reward_id(&reward_id)
+        .and_then(|_| validate_update_reward(&update))
+        .map_err(|err| JsErrorBox::generic(err.to_string()))?;
+    let twitch = state.twitch()?;
+    twitch
+        .update_reward(&reward_id, update)
+        .await
+        .map_err(|err| {
+            log::error!("failed to update reward: {err:?}");
+            JsErrorBox::generic("failed to update reward")
+        })?;
+
+    Ok(())
+}
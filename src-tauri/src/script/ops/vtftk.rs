@@ -4,7 +4,7 @@ use crate::{
         sounds::SoundModel,
     },
     overlay::OverlayMessage,
-    script::runtime::ScriptRuntimeDataExt,
+    script::{permissions::check_overlay_access, runtime::ScriptRuntimeDataExt},
 };
 use deno_core::{OpState, op2};
 use deno_error::JsErrorBox;
@@ -18,6 +18,7 @@ pub async fn op_vtftk_emit_overlay_message(
     state: Rc<RefCell<OpState>>,
     #[serde] message: OverlayMessage,
 ) -> Result<(), JsErrorBox> {
+    check_overlay_access(&state)?;
     let overlay_sender = state.overlay_sender()?;
 
     overlay_sender
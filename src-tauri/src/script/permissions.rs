@@ -0,0 +1,265 @@
+//! # Permissions
+//!
+//! Enforcement of the permissions declared by scripts. The access granted to
+//! the script running on a runtime is stored in the runtime [OpState] and
+//! checked by the ops before performing any privileged action.
+//!
+//! Scripts with declared permissions only share a runtime with scripts of the
+//! same event or command that declare the same permissions, so the access of
+//! one script cannot be used by another
+
+use crate::{
+    database::entity::{key_value::KeyValueNamespace, shared::ScriptPermissions},
+    script::runtime::{RuntimeExecutionContext, ScriptRuntimeDataExt},
+};
+use deno_core::OpState;
+use deno_error::JsErrorBox;
+use reqwest::{Url, redirect::Policy};
+use std::{cell::RefCell, rc::Rc};
+
+/// Host used by the Twitch API helpers, allowed for scripts with Twitch access
+const TWITCH_API_HOST: &str = "api.twitch.tv";
+
+/// Maximum number of redirects followed for a script HTTP request
+const MAX_HTTP_REDIRECTS: usize = 10;
+
+/// Access granted to the script running on a runtime, runtimes without
+/// a [ScriptAccess] in their [OpState] are unrestricted
+#[derive(Clone)]
+pub struct ScriptAccess {
+    /// Context of the script the access belongs to
+    pub ctx: RuntimeExecutionContext,
+    /// Permissions declared by the script
+    pub permissions: ScriptPermissions,
+}
+
+impl ScriptAccess {
+    /// Checks if the script can send HTTP requests to `host`
+    pub fn can_access_host(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+
+        if self.permissions.twitch && host == TWITCH_API_HOST {
+            return true;
+        }
+
+        self.permissions
+            .http_hosts
+            .iter()
+            .any(|allowed| is_host_match(&allowed.to_lowercase(), &host))
+    }
+
+    /// Checks if the script can access the key value `key`
+    pub fn can_access_key(&self, key: &str) -> bool {
+        // Scripts can always access their own namespace
+        if is_key_in_namespace(&KeyValueNamespace::from(self.ctx).prefix(), key) {
+            return true;
+        }
+
+        self.permissions
+            .kv_namespaces
+            .iter()
+            .any(|namespace| namespace == "*" || is_key_in_namespace(namespace, key))
+    }
+}
+
+/// Checks if `host` matches the `allowed` host pattern
+fn is_host_match(allowed: &str, host: &str) -> bool {
+    match allowed.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        None => allowed == host,
+    }
+}
+
+/// Checks if `key` is within the dot separated `namespace`
+fn is_key_in_namespace(namespace: &str, key: &str) -> bool {
+    let namespace = namespace.strip_suffix('.').unwrap_or(namespace);
+
+    !namespace.is_empty()
+        && key
+            .strip_prefix(namespace)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Runs `check` against the access of the current script, passing when
+/// the script is unrestricted
+fn check_access<F>(
+    state: &Rc<RefCell<OpState>>,
+    check: F,
+    message: String,
+) -> Result<(), JsErrorBox>
+where
+    F: FnOnce(&ScriptAccess) -> bool,
+{
+    let state = state.try_borrow_state()?;
+    let Some(access) = state.try_borrow::<ScriptAccess>() else {
+        return Ok(());
+    };
+
+    if !check(access) {
+        return Err(JsErrorBox::generic(format!("permission denied: {message}")));
+    }
+
+    Ok(())
+}
+
+/// Ensures the current script can send HTTP requests to `url`
+pub fn check_http_access(state: &Rc<RefCell<OpState>>, url: &str) -> Result<(), JsErrorBox> {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+
+    check_access(
+        state,
+        |access| access.can_access_host(&host),
+        format!("script is not allowed to send requests to \"{host}\""),
+    )
+}
+
+/// Creates the redirect policy for HTTP requests sent by the current script,
+/// restricted scripts can only be redirected to hosts they are allowed to access
+pub fn http_redirect_policy(state: &Rc<RefCell<OpState>>) -> Result<Policy, JsErrorBox> {
+    let access = state
+        .try_borrow_state()?
+        .try_borrow::<ScriptAccess>()
+        .cloned();
+    let Some(access) = access else {
+        return Ok(Policy::limited(MAX_HTTP_REDIRECTS));
+    };
+
+    Ok(Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_HTTP_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+
+        let host = attempt.url().host_str().unwrap_or_default().to_string();
+        if access.can_access_host(&host) {
+            attempt.follow()
+        } else {
+            attempt.error(format!(
+                "permission denied: script is not allowed to send requests to \"{host}\""
+            ))
+        }
+    }))
+}
+
+/// Gets the context of the script running on a restricted runtime, None
+/// when the runtime is unrestricted
+pub fn restricted_context(state: &OpState) -> Option<RuntimeExecutionContext> {
+    state.try_borrow::<ScriptAccess>().map(|access| access.ctx)
+}
+
+/// Ensures the current script can access Twitch
+pub fn check_twitch_access(state: &Rc<RefCell<OpState>>) -> Result<(), JsErrorBox> {
+    check_access(
+        state,
+        |access| access.permissions.twitch,
+        "script is not allowed to access twitch".to_string(),
+    )
+}
+
+/// Ensures the current script can access the key value `key`
+pub fn check_kv_access(state: &Rc<RefCell<OpState>>, key: &str) -> Result<(), JsErrorBox> {
+    check_access(
+        state,
+        |access| access.can_access_key(key),
+        format!("script is not allowed to access the key \"{key}\""),
+    )
+}
+
+/// Ensures the current script can emit overlay messages
+pub fn check_overlay_access(state: &Rc<RefCell<OpState>>) -> Result<(), JsErrorBox> {
+    check_access(
+        state,
+        |access| access.permissions.overlay,
+        "script is not allowed to emit overlay messages".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::ScriptAccess;
+    use crate::{
+        database::entity::{key_value::KeyValueNamespace, shared::ScriptPermissions},
+        script::runtime::RuntimeExecutionContext,
+    };
+
+    fn access(permissions: ScriptPermissions) -> ScriptAccess {
+        ScriptAccess {
+            ctx: RuntimeExecutionContext::Event {
+                event_id: Uuid::nil(),
+            },
+            permissions,
+        }
+    }
+
+    /// Tests matching exact and wildcard hosts
+    #[test]
+    fn test_can_access_host() {
+        let access = access(ScriptPermissions {
+            http_hosts: vec!["example.com".to_string(), "*.Test.com".to_string()],
+            ..Default::default()
+        });
+
+        assert!(access.can_access_host("example.com"));
+        assert!(access.can_access_host("EXAMPLE.com"));
+        assert!(access.can_access_host("api.test.com"));
+        assert!(access.can_access_host("a.b.test.com"));
+
+        assert!(!access.can_access_host("api.example.com"));
+        assert!(!access.can_access_host("test.com"));
+        assert!(!access.can_access_host("eviltest.com"));
+        assert!(!access.can_access_host("example.com.evil.com"));
+        assert!(!access.can_access_host(""));
+    }
+
+    /// Tests that twitch access allows the twitch API host
+    #[test]
+    fn test_twitch_api_host() {
+        assert!(!access(ScriptPermissions::default()).can_access_host("api.twitch.tv"));
+
+        let access = access(ScriptPermissions {
+            twitch: true,
+            ..Default::default()
+        });
+        assert!(access.can_access_host("api.twitch.tv"));
+        assert!(!access.can_access_host("twitch.tv"));
+    }
+
+    /// Tests key value namespace access
+    #[test]
+    fn test_can_access_key() {
+        let access = access(ScriptPermissions {
+            kv_namespaces: vec!["shared".to_string(), "points.".to_string()],
+            ..Default::default()
+        });
+
+        // Own namespace is always accessible
+        let own = KeyValueNamespace::from(access.ctx);
+        assert!(access.can_access_key(&own.key("value")));
+
+        assert!(access.can_access_key("shared.value"));
+        assert!(access.can_access_key("points.user.1"));
+
+        assert!(!access.can_access_key("shared"));
+        assert!(!access.can_access_key("sharedvalue"));
+        assert!(!access.can_access_key("counter.deaths"));
+        assert!(!access.can_access_key(&format!("event.{}.value", Uuid::new_v4())));
+    }
+
+    /// Tests that the wildcard namespace allows all keys
+    #[test]
+    fn test_can_access_all_keys() {
+        let access = access(ScriptPermissions {
+            kv_namespaces: vec!["*".to_string()],
+            ..Default::default()
+        });
+
+        assert!(access.can_access_key("counter.deaths"));
+        assert!(access.can_access_key("anything"));
+    }
+}
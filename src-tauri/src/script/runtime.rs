@@ -1,21 +1,43 @@
 use crate::{
-    database::DbPool,
-    events::matching::{EventData, EventInputData},
+    database::{
+        entity::{
+            app_data::{ScriptIsolation, ScriptRuntimeConfig},
+            key_value::KeyValueNamespace,
+            shared::{LoggingLevelDb, ScriptLimits, ScriptPermissions},
+        },
+        DbPool,
+    },
+    events::{
+        arguments::CommandArguments,
+        matching::{EventData, EventInputData},
+    },
     overlay::OverlayMessageSender,
-    script::ops::{
-        core::{op_sleep, op_uuid_v4},
-        http::op_http_request,
-        kv::{op_kv_get, op_kv_remove, op_kv_set},
-        logging::op_log,
-        twitch::op_twitch_get_credentials,
-        vtftk::{
-            op_vtftk_emit_overlay_message, op_vtftk_get_items_by_ids, op_vtftk_get_items_by_names,
-            op_vtftk_get_sounds_by_ids, op_vtftk_get_sounds_by_names,
+    script::{
+        ops::{
+            core::{op_sleep, op_uuid_v4},
+            http::op_http_request,
+            kv::{
+                op_kv_compare_and_set, op_kv_get, op_kv_increment, op_kv_list, op_kv_namespace,
+                op_kv_remove, op_kv_remove_prefix, op_kv_set,
+            },
+            logging::op_log,
+            twitch::{
+                op_twitch_create_poll, op_twitch_create_prediction, op_twitch_create_reward,
+                op_twitch_get_credentials, op_twitch_update_redemption_status,
+                op_twitch_update_reward,
+            },
+            vtftk::{
+                op_vtftk_emit_overlay_message, op_vtftk_get_items_by_ids,
+                op_vtftk_get_items_by_names, op_vtftk_get_sounds_by_ids,
+                op_vtftk_get_sounds_by_names,
+            },
         },
+        permissions::ScriptAccess,
     },
     twitch::manager::Twitch,
 };
-use anyhow::Context;
+use anyhow::{anyhow, Context};
+use chrono::Utc;
 use deno_core::{
     scope,
     serde_v8::to_v8,
@@ -23,14 +45,22 @@ use deno_core::{
     JsRuntime, OpState, PollEventLoopOptions, RuntimeOptions,
 };
 use deno_error::JsErrorBox;
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
 use std::{
-    cell::{Ref, RefCell},
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
     future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
     path::PathBuf,
     pin::Pin,
     rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::Poll,
+    time::{Duration, Instant},
 };
 use tokio::{
     sync::{mpsc, oneshot},
@@ -39,8 +69,9 @@ use tokio::{
 use twitch_api::types::{DisplayName, UserId, UserName};
 use uuid::Uuid;
 
-use super::module_loader::AppModuleLoader;
+use super::{module_loader::AppModuleLoader, ops::logging::persist_log, watchdog::ScriptWatchdog};
 
+#[derive(Clone)]
 pub struct ScriptRuntimeData {
     /// Sender handle for sending messages to the overlay
     pub overlay_sender: OverlayMessageSender,
@@ -64,10 +95,20 @@ deno_core::extension!(
         op_log,
         // Twitch
         op_twitch_get_credentials,
+        op_twitch_create_poll,
+        op_twitch_create_prediction,
+        op_twitch_update_redemption_status,
+        op_twitch_create_reward,
+        op_twitch_update_reward,
         // KV
         op_kv_get,
         op_kv_set,
         op_kv_remove,
+        op_kv_increment,
+        op_kv_compare_and_set,
+        op_kv_list,
+        op_kv_remove_prefix,
+        op_kv_namespace,
         // VTFTK Sounds
         op_vtftk_get_sounds_by_names,
         op_vtftk_get_sounds_by_ids,
@@ -84,13 +125,17 @@ deno_core::extension!(
     docs = "Extension providing APIs to the JS runtime"
 );
 
+/// Maximum number of runtimes each worker keeps when using per script isolation,
+/// the least recently used idle runtime is removed when exceeded
+const MAX_SCRIPT_RUNTIMES: usize = 16;
+
 /// Snapshot of the script engine runtime, see [build.rs](../../build.rs)
 static SCRIPT_RUNTIME_SNAPSHOT: &[u8] =
     include_bytes!(concat!(env!("OUT_DIR"), "/SCRIPT_RUNTIME_SNAPSHOT.bin"));
 
 /// Context passed to the JS runtime that is tracked
 /// across async calls for handling logging sources
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RuntimeExecutionContext {
     /// Runtime execution started from a event
     Event { event_id: Uuid },
@@ -98,6 +143,17 @@ pub enum RuntimeExecutionContext {
     Command { command_id: Uuid },
 }
 
+impl From<RuntimeExecutionContext> for KeyValueNamespace {
+    fn from(ctx: RuntimeExecutionContext) -> Self {
+        match ctx {
+            RuntimeExecutionContext::Event { event_id } => KeyValueNamespace::Event(event_id),
+            RuntimeExecutionContext::Command { command_id } => {
+                KeyValueNamespace::Command(command_id)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ScriptExecutorMessage {
     /// Tell the executor to run the event callbacks in the provided code
@@ -107,6 +163,10 @@ pub enum ScriptExecutorMessage {
         ctx: RuntimeExecutionContext,
         /// The script code to run
         script: String,
+        /// Permissions declared by the script, None for unrestricted access
+        permissions: Option<ScriptPermissions>,
+        /// Execution limits declared by the script
+        limits: ScriptLimits,
         /// Data for the event
         data: EventData,
         /// Channel to send back the result
@@ -120,6 +180,10 @@ pub enum ScriptExecutorMessage {
         ctx: RuntimeExecutionContext,
         /// The script code to run
         script: String,
+        /// Permissions declared by the script, None for unrestricted access
+        permissions: Option<ScriptPermissions>,
+        /// Execution limits declared by the script
+        limits: ScriptLimits,
         /// Context for the command run
         cmd_ctx: CommandContext,
         /// Channel to send back the result
@@ -130,8 +194,37 @@ pub enum ScriptExecutorMessage {
 /// Handle for accessing the script executor
 #[derive(Clone)]
 pub struct ScriptExecutorHandle {
+    /// Workers in the runtime pool
+    workers: Arc<[ScriptWorkerHandle]>,
+    /// How runtimes are shared between scripts, determines
+    /// which worker a script is run on
+    isolation: ScriptIsolation,
+}
+
+/// Handle to a single worker in the runtime pool
+struct ScriptWorkerHandle {
     /// Channel for sending the execute message
     tx: mpsc::Sender<ScriptExecutorMessage>,
+    /// Number of executions sent to the worker that have not completed
+    active: Arc<AtomicUsize>,
+}
+
+/// Guard tracking an active execution on a worker
+struct ActiveExecutionGuard<'a> {
+    active: &'a AtomicUsize,
+}
+
+impl<'a> ActiveExecutionGuard<'a> {
+    fn new(active: &'a AtomicUsize) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self { active }
+    }
+}
+
+impl Drop for ActiveExecutionGuard<'_> {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl ScriptExecutorHandle {
@@ -141,38 +234,82 @@ impl ScriptExecutorHandle {
         &self,
         ctx: RuntimeExecutionContext,
         script: String,
+        permissions: Option<ScriptPermissions>,
+        limits: ScriptLimits,
         data: EventData,
     ) -> anyhow::Result<()> {
-        let (tx, rx) = oneshot::channel();
-
-        self.tx
-            .send(ScriptExecutorMessage::EventScript {
-                ctx,
-                script,
-                data,
-                tx,
-            })
-            .await
-            .context("executor is not running")?;
-
-        rx.await.context("executor closed without response")?
+        let worker = self.select_worker(ctx, &permissions)?;
+        Self::dispatch(worker, |tx| ScriptExecutorMessage::EventScript {
+            ctx,
+            script,
+            permissions,
+            limits,
+            data,
+            tx,
+        })
+        .await
     }
 
     pub async fn execute_command(
         &self,
         ctx: RuntimeExecutionContext,
         script: String,
+        permissions: Option<ScriptPermissions>,
+        limits: ScriptLimits,
         cmd_ctx: CommandContext,
     ) -> anyhow::Result<()> {
+        let worker = self.select_worker(ctx, &permissions)?;
+        Self::dispatch(worker, |tx| ScriptExecutorMessage::CommandScript {
+            ctx,
+            script,
+            permissions,
+            limits,
+            cmd_ctx,
+            tx,
+        })
+        .await
+    }
+
+    /// Selects the worker to run a script on
+    ///
+    /// Scripts with their own runtime are always run on the same worker so the
+    /// state of their runtime is kept between executions, other scripts are
+    /// run on the least busy worker
+    fn select_worker(
+        &self,
+        ctx: RuntimeExecutionContext,
+        permissions: &Option<ScriptPermissions>,
+    ) -> anyhow::Result<&ScriptWorkerHandle> {
+        let key = RuntimeKey::for_script(self.isolation, ctx, permissions.clone(), 0);
+
+        let worker = match key {
+            RuntimeKey::Script(..) if !self.workers.is_empty() => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+
+                let index = (hasher.finish() % self.workers.len() as u64) as usize;
+                self.workers.get(index)
+            }
+            _ => self
+                .workers
+                .iter()
+                .min_by_key(|worker| worker.active.load(Ordering::SeqCst)),
+        };
+
+        worker.context("executor has no workers")
+    }
+
+    /// Sends an execute message to the `worker` and waits for the result
+    async fn dispatch<F>(worker: &ScriptWorkerHandle, create_message: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(oneshot::Sender<anyhow::Result<()>>) -> ScriptExecutorMessage,
+    {
+        let _active = ActiveExecutionGuard::new(&worker.active);
         let (tx, rx) = oneshot::channel();
 
-        self.tx
-            .send(ScriptExecutorMessage::CommandScript {
-                ctx,
-                script,
-                cmd_ctx,
-                tx,
-            })
+        worker
+            .tx
+            .send(create_message(tx))
             .await
             .context("executor is not running")?;
 
@@ -180,79 +317,585 @@ impl ScriptExecutorHandle {
     }
 }
 
-fn spawn_script_promise(
-    js_runtime: &mut JsRuntime,
-    global_promise: anyhow::Result<v8::Global<v8::Value>>,
-    tx: oneshot::Sender<anyhow::Result<()>>,
-    local_set: &mut LocalSet,
-) {
-    let global_promise = match global_promise {
-        Ok(value) => value,
-        Err(err) => {
-            _ = tx.send(Err(err));
-            return;
+#[cfg(test)]
+impl ScriptExecutorHandle {
+    /// Creates a handle without any workers, for tests that
+    /// do not execute scripts
+    pub fn mock() -> Self {
+        Self {
+            workers: Arc::from(Vec::new()),
+            isolation: ScriptIsolation::default(),
         }
-    };
+    }
+}
 
-    let resolve = js_runtime.resolve(global_promise);
-    local_set.spawn_local(async move {
-        let result = resolve.await;
-        _ = tx.send(result.map(|_| ()).map_err(anyhow::Error::new));
-    });
+/// Limits applied to a single script execution
+#[derive(Debug, Clone, Copy)]
+struct ExecutionLimits {
+    /// Maximum time the execution can take to complete, includes time
+    /// spent waiting on async operations such as HTTP requests or sleeps
+    wall_clock: Duration,
+    /// Maximum total time the execution can spend running JS code
+    cpu_time: Duration,
+}
+
+impl ExecutionLimits {
+    /// Resolves the `limits` declared by a script, limits that are
+    /// not declared use the defaults from the `config`
+    fn resolve(limits: ScriptLimits, config: &ScriptRuntimeConfig) -> Self {
+        let wall_clock = limits.wall_clock_limit.unwrap_or(config.wall_clock_limit);
+        let cpu_time = limits.cpu_time_limit.unwrap_or(config.cpu_time_limit);
+
+        Self {
+            wall_clock: Duration::from_millis(wall_clock as u64),
+            cpu_time: Duration::from_millis(cpu_time as u64),
+        }
+    }
+}
+
+/// Script execution waiting for its promise to resolve
+struct PendingExecution {
+    /// Context of the execution for logging
+    ctx: RuntimeExecutionContext,
+    /// Channel to send back the result
+    tx: oneshot::Sender<anyhow::Result<()>>,
+    /// Limits for the execution
+    limits: ExecutionLimits,
+    /// Time spent running JS code for the execution so far
+    cpu_time: Duration,
+}
+
+impl PendingExecution {
+    /// CPU time the execution can use before it is terminated
+    fn remaining_cpu_time(&self) -> Duration {
+        self.limits.cpu_time.saturating_sub(self.cpu_time)
+    }
+}
+
+type PendingExecutions = Rc<RefCell<HashMap<u64, PendingExecution>>>;
+
+/// Gets the maximum time a poll of a runtime with the `pending` executions can
+/// run JS code for, the most CPU time any of the executions have remaining
+fn poll_cpu_time_limit(
+    pending: &HashMap<u64, PendingExecution>,
+    default_limit: Duration,
+) -> Duration {
+    pending
+        .values()
+        .map(PendingExecution::remaining_cpu_time)
+        .max()
+        .unwrap_or(default_limit)
+}
+
+/// Charges the `elapsed` time a runtime spent running JS code to its `pending`
+/// executions, returning the executions that exceeded their CPU time limit
+///
+/// The runtime does not report which execution the code belonged to so
+/// the time is split evenly between the executions
+fn charge_cpu_time(pending: &mut HashMap<u64, PendingExecution>, elapsed: Duration) -> Vec<u64> {
+    let share = elapsed
+        .checked_div(pending.len() as u32)
+        .unwrap_or_default();
+
+    pending
+        .iter_mut()
+        .filter_map(|(id, pending)| {
+            pending.cpu_time += share;
+            (pending.cpu_time > pending.limits.cpu_time).then_some(*id)
+        })
+        .collect()
 }
 
-/// Creates a dedicated thread for receiving script execution requests. The
-/// thread will process the script execution requests providing the responses
+/// Creates the pool of dedicated threads for receiving script execution requests.
+/// The threads will process the script execution requests providing the responses
 ///
 /// The JS runtime is !Send and thus it cannot be shared across tokio async tasks
-/// so here its provided a dedicated single threaded runtime and its own thread
+/// so here each worker is provided a dedicated single threaded runtime and its own thread
+///
+/// The `config` is only read here, changes to it apply once the app is restarted
 pub fn create_script_executor(
     modules_path: PathBuf,
     runtime_data: ScriptRuntimeData,
+    config: ScriptRuntimeConfig,
 ) -> ScriptExecutorHandle {
-    let (tx, rx) = mpsc::channel::<ScriptExecutorMessage>(5);
+    let pool_size = config.pool_size.max(1);
+    let isolation = config.isolation;
+
+    let workers = (0..pool_size)
+        .map(|_| {
+            let (tx, rx) = mpsc::channel::<ScriptExecutorMessage>(5);
+            let modules_path = modules_path.clone();
+            let runtime_data = runtime_data.clone();
+            let config = config.clone();
+
+            std::thread::spawn(move || {
+                // Create a new tokio runtime in the dedicated thread
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to create script async runtime");
+
+                runtime.block_on(ScriptExecutorFuture::new(
+                    modules_path,
+                    runtime_data,
+                    config,
+                    rx,
+                ));
+            });
+
+            ScriptWorkerHandle {
+                tx,
+                active: Default::default(),
+            }
+        })
+        .collect();
+
+    ScriptExecutorHandle { workers, isolation }
+}
+
+/// Key identifying a runtime within a worker
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RuntimeKey {
+    /// Runtime shared by all scripts
+    Shared,
+    /// Runtime for a specific script, scripts of the same event or command
+    /// only share a runtime when they declare the same permissions
+    Script(RuntimeExecutionContext, Option<ScriptPermissions>),
+    /// Runtime for a single execution
+    Execution(u64),
+}
+
+impl RuntimeKey {
+    /// Gets the key for the runtime a script runs on, `execution` is
+    /// the ID of the execution
+    fn for_script(
+        isolation: ScriptIsolation,
+        ctx: RuntimeExecutionContext,
+        permissions: Option<ScriptPermissions>,
+        execution: u64,
+    ) -> Self {
+        // Scripts with declared permissions never share a runtime with other scripts
+        match isolation {
+            ScriptIsolation::Shared if permissions.is_none() => RuntimeKey::Shared,
+            ScriptIsolation::Shared | ScriptIsolation::PerScript => {
+                RuntimeKey::Script(ctx, permissions)
+            }
+            ScriptIsolation::PerExecution => RuntimeKey::Execution(execution),
+        }
+    }
+}
+
+/// Reason a script execution was terminated
+#[derive(Debug, Clone, Copy)]
+enum TerminationReason {
+    /// Script spent too long running JS code
+    CpuTime,
+    /// Script used more memory than the runtime allows
+    Memory,
+    /// Script took too long to complete
+    WallClock,
+}
+
+/// JS runtime along with the executions running on it
+struct ScriptRuntimeInstance {
+    /// Local set for spawned promise tasks
+    ///
+    /// Declared before the runtime so the tasks referencing
+    /// the runtime are dropped first
+    local_set: LocalSet,
+
+    /// JS runtime
+    runtime: JsRuntime,
+
+    /// Executions waiting for their promise to resolve
+    pending: PendingExecutions,
+
+    /// Set when the runtime reaches its memory limit
+    memory_exceeded: Rc<Cell<bool>>,
 
-    std::thread::spawn(move || {
-        // Create a new tokio runtime in the dedicated thread
-        let runtime = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .expect("failed to create script async runtime");
+    /// Executions that exceeded their wall clock limit, the runtime
+    /// must be reset to stop them
+    timed_out: Rc<RefCell<Vec<u64>>>,
 
-        // Create runtime
-        let js_runtime = JsRuntime::new(RuntimeOptions {
+    /// Last time an execution was started on the runtime
+    last_used: Instant,
+}
+
+impl ScriptRuntimeInstance {
+    /// Creates a new JS runtime from the runtime snapshot
+    fn new(modules_path: PathBuf, runtime_data: ScriptRuntimeData, memory_limit_mb: usize) -> Self {
+        let heap_limit = memory_limit_mb.max(1) * 1024 * 1024;
+
+        let mut runtime = JsRuntime::new(RuntimeOptions {
             startup_snapshot: Some(SCRIPT_RUNTIME_SNAPSHOT),
             extensions: vec![api_extension::init(runtime_data)],
             module_loader: Some(Rc::new(AppModuleLoader {
                 module_root: modules_path,
             })),
+            create_params: Some(v8::CreateParams::default().heap_limits(0, heap_limit)),
 
             ..Default::default()
         });
 
-        runtime.block_on(ScriptExecutorFuture::new(js_runtime, rx));
-    });
+        let memory_exceeded = Rc::new(Cell::new(false));
+        let isolate = runtime.v8_isolate().thread_safe_handle();
+
+        // Terminate execution instead of crashing when the heap limit is reached
+        runtime.add_near_heap_limit_callback({
+            let memory_exceeded = memory_exceeded.clone();
+
+            move |current_limit, _initial_limit| {
+                memory_exceeded.set(true);
+                isolate.terminate_execution();
+
+                // Raise the limit to give the terminated execution room to unwind
+                current_limit * 2
+            }
+        });
+
+        Self {
+            local_set: LocalSet::new(),
+            runtime,
+            pending: Default::default(),
+            memory_exceeded,
+            timed_out: Default::default(),
+            last_used: Instant::now(),
+        }
+    }
 
-    ScriptExecutorHandle { tx }
+    /// Stores the access of the scripts that run on the runtime in its op
+    /// state so the ops can enforce their permissions
+    ///
+    /// Only set when the runtime is created, runtimes are keyed so that every
+    /// script running on a runtime has the same access
+    fn set_access(&mut self, access: ScriptAccess) {
+        self.runtime.op_state().borrow_mut().put(access);
+    }
+
+    /// Runs JS code on the runtime while marked as busy on the watchdog, terminating
+    /// the code if it runs for longer than `limit`
+    ///
+    /// Returns the time spent running and the reason if the execution was terminated
+    fn run<F, R>(
+        &mut self,
+        watchdog: &ScriptWatchdog,
+        limit: Duration,
+        run: F,
+    ) -> (R, Duration, Option<TerminationReason>)
+    where
+        F: FnOnce(&mut JsRuntime) -> R,
+    {
+        let start = Instant::now();
+        let result = {
+            let _guard = watchdog.enter(self.runtime.v8_isolate().thread_safe_handle(), limit);
+            run(&mut self.runtime)
+        };
+        let elapsed = start.elapsed();
+
+        let reason = if self.memory_exceeded.replace(false) {
+            Some(TerminationReason::Memory)
+        } else if watchdog.take_terminated() {
+            Some(TerminationReason::CpuTime)
+        } else {
+            None
+        };
+
+        (result, elapsed, reason)
+    }
 }
 
-struct ScriptExecutorFuture {
-    /// JS runtime task
-    runtime: JsRuntime,
+/// Logs a script execution error and stores it in the logs for
+/// the event or command that the script belongs to
+fn log_script_error(db: &DbPool, ctx: RuntimeExecutionContext, message: String) {
+    error!("{ctx:?}: {message}");
+
+    let db = db.clone();
+    let created_at = Utc::now();
+
+    tokio::spawn(async move {
+        if let Err(err) = persist_log(&db, ctx, LoggingLevelDb::Error, message, created_at).await {
+            error!("failed to persist script error log: {:?}", err);
+        }
+    });
+}
 
+/// Worker in the runtime pool, runs scripts on its own thread
+struct ScriptExecutorFuture {
     /// Channel to receive execute messages from
     rx: mpsc::Receiver<ScriptExecutorMessage>,
 
-    /// Local set for spawned promise tasks
-    local_set: LocalSet,
+    /// Runtimes available on the worker
+    runtimes: HashMap<RuntimeKey, ScriptRuntimeInstance>,
+
+    /// ID to assign the next execution
+    next_execution_id: u64,
+
+    /// Watchdog for terminating scripts that exceed their CPU time
+    watchdog: ScriptWatchdog,
+
+    /// Path to the modules directory, used when creating runtimes
+    modules_path: PathBuf,
+
+    /// Data for the runtime, used when creating runtimes
+    runtime_data: ScriptRuntimeData,
+
+    /// Runtime pool configuration
+    config: ScriptRuntimeConfig,
 }
 
 impl ScriptExecutorFuture {
-    pub fn new(runtime: JsRuntime, rx: mpsc::Receiver<ScriptExecutorMessage>) -> Self {
+    pub fn new(
+        modules_path: PathBuf,
+        runtime_data: ScriptRuntimeData,
+        config: ScriptRuntimeConfig,
+        rx: mpsc::Receiver<ScriptExecutorMessage>,
+    ) -> Self {
         Self {
-            runtime,
             rx,
-            local_set: LocalSet::new(),
+            runtimes: HashMap::new(),
+            next_execution_id: 0,
+            watchdog: ScriptWatchdog::new(),
+            modules_path,
+            runtime_data,
+            config,
+        }
+    }
+
+    /// Creates the runtime for `key` with the provided `access` if
+    /// it does not already exist
+    fn ensure_runtime(&mut self, key: &RuntimeKey, access: Option<ScriptAccess>) {
+        if self.runtimes.contains_key(key) {
+            return;
+        }
+
+        if matches!(key, RuntimeKey::Script(..)) {
+            self.evict_script_runtime();
+        }
+
+        let mut instance = ScriptRuntimeInstance::new(
+            self.modules_path.clone(),
+            self.runtime_data.clone(),
+            self.config.memory_limit_mb,
+        );
+
+        if let Some(access) = access {
+            instance.set_access(access);
+        }
+
+        self.runtimes.insert(key.clone(), instance);
+    }
+
+    /// Removes the least recently used idle per script runtime when the
+    /// maximum number of per script runtimes has been reached
+    fn evict_script_runtime(&mut self) {
+        let script_runtimes = self
+            .runtimes
+            .keys()
+            .filter(|key| matches!(key, RuntimeKey::Script(..)))
+            .count();
+
+        if script_runtimes < MAX_SCRIPT_RUNTIMES {
+            return;
+        }
+
+        let evict = self
+            .runtimes
+            .iter()
+            .filter(|(key, instance)| {
+                matches!(key, RuntimeKey::Script(..)) && instance.pending.borrow().is_empty()
+            })
+            .min_by_key(|(_, instance)| instance.last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = evict {
+            self.runtimes.remove(&key);
+        }
+    }
+
+    /// Runs a script on its runtime, tracking its promise until it resolves
+    fn run_script<F>(
+        &mut self,
+        ctx: RuntimeExecutionContext,
+        permissions: Option<ScriptPermissions>,
+        limits: ScriptLimits,
+        tx: oneshot::Sender<anyhow::Result<()>>,
+        run: F,
+    ) where
+        F: FnOnce(&mut JsRuntime) -> anyhow::Result<v8::Global<v8::Value>>,
+    {
+        let id = self.next_execution_id;
+        self.next_execution_id = self.next_execution_id.wrapping_add(1);
+
+        let limits = ExecutionLimits::resolve(limits, &self.config);
+
+        let access = permissions
+            .clone()
+            .map(|permissions| ScriptAccess { ctx, permissions });
+
+        let key = RuntimeKey::for_script(self.config.isolation, ctx, permissions, id);
+
+        self.ensure_runtime(&key, access);
+
+        let instance = self
+            .runtimes
+            .get_mut(&key)
+            .expect("runtime should be present");
+        instance.last_used = Instant::now();
+
+        let (result, cpu_time, reason) = instance.run(&self.watchdog, limits.cpu_time, run);
+
+        // Script was terminated while starting
+        if let Some(reason) = reason {
+            let message = self.termination_message(reason, &limits);
+            log_script_error(&self.runtime_data.db, ctx, message.clone());
+            _ = tx.send(Err(anyhow!(message)));
+
+            self.remove_terminated_runtime(key, reason, &[id]);
+            return;
+        }
+
+        let global_promise = match result {
+            Ok(value) => value,
+            Err(err) => {
+                _ = tx.send(Err(err));
+                return;
+            }
+        };
+
+        instance.pending.borrow_mut().insert(
+            id,
+            PendingExecution {
+                ctx,
+                tx,
+                limits,
+                cpu_time,
+            },
+        );
+
+        let resolve = instance.runtime.resolve(global_promise);
+        let pending = instance.pending.clone();
+        let timed_out = instance.timed_out.clone();
+
+        instance.local_set.spawn_local(async move {
+            let result = match tokio::time::timeout(limits.wall_clock, resolve).await {
+                Ok(result) => result.map(|_| ()).map_err(anyhow::Error::new),
+                Err(_) => {
+                    // Execution is still running on the runtime, the worker
+                    // resets the runtime to stop it and reports the timeout
+                    timed_out.borrow_mut().push(id);
+                    return;
+                }
+            };
+
+            if let Some(pending) = pending.borrow_mut().remove(&id) {
+                _ = pending.tx.send(result);
+            }
+        });
+    }
+
+    /// Polls the promises and event loops of all the runtimes, removing
+    /// runtimes where a script was terminated while running
+    fn poll_runtimes(&mut self, cx: &mut std::task::Context<'_>) {
+        let mut terminated = Vec::new();
+        let default_cpu_time = Duration::from_millis(self.config.cpu_time_limit as u64);
+
+        for (key, instance) in self.runtimes.iter_mut() {
+            // Poll the promises local set
+            _ = Pin::new(&mut instance.local_set).poll(cx);
+
+            let timed_out = std::mem::take(&mut *instance.timed_out.borrow_mut());
+            if !timed_out.is_empty() {
+                terminated.push((key.clone(), TerminationReason::WallClock, timed_out));
+                continue;
+            }
+
+            // Poll event loop for any promises
+            let limit = poll_cpu_time_limit(&instance.pending.borrow(), default_cpu_time);
+            let (_, elapsed, reason) = instance.run(&self.watchdog, limit, |runtime| {
+                runtime.poll_event_loop(cx, PollEventLoopOptions::default())
+            });
+
+            let exceeded = charge_cpu_time(&mut instance.pending.borrow_mut(), elapsed);
+
+            if let Some(reason) = reason {
+                // With a single pending execution it must be the one that was terminated,
+                // otherwise the culprit is unknown and all executions are stopped
+                let pending = instance.pending.borrow();
+                let culprits = if pending.len() == 1 {
+                    pending.keys().copied().collect()
+                } else {
+                    Vec::new()
+                };
+
+                terminated.push((key.clone(), reason, culprits));
+            } else if !exceeded.is_empty() {
+                terminated.push((key.clone(), TerminationReason::CpuTime, exceeded));
+            }
+        }
+
+        for (key, reason, culprits) in terminated {
+            self.remove_terminated_runtime(key, reason, &culprits);
+        }
+
+        // Remove per execution runtimes once the execution is complete
+        self.runtimes.retain(|key, instance| {
+            !matches!(key, RuntimeKey::Execution(_)) || !instance.pending.borrow().is_empty()
+        });
+    }
+
+    /// Removes a runtime after a script on it was terminated, stopping any
+    /// executions still pending on the runtime. The `culprits` are the
+    /// executions that were terminated, when empty the culprit is unknown
+    ///
+    /// A new runtime is created for the key when next used
+    fn remove_terminated_runtime(
+        &mut self,
+        key: RuntimeKey,
+        reason: TerminationReason,
+        culprits: &[u64],
+    ) {
+        let Some(mut instance) = self.runtimes.remove(&key) else {
+            return;
+        };
+
+        warn!("removing script runtime after terminated execution");
+
+        // Stop the executions still on the runtime from running any further
+        instance.runtime.v8_isolate().terminate_execution();
+
+        let pending: Vec<(u64, PendingExecution)> = instance.pending.borrow_mut().drain().collect();
+
+        drop(instance);
+
+        for (id, pending) in pending {
+            let message = self.termination_message(reason, &pending.limits);
+            let message = if culprits.contains(&id) {
+                message
+            } else if culprits.is_empty() {
+                format!("script runtime was restarted: {message}")
+            } else {
+                "script runtime was restarted after another script was terminated".to_string()
+            };
+
+            log_script_error(&self.runtime_data.db, pending.ctx, message.clone());
+            _ = pending.tx.send(Err(anyhow!(message)));
+        }
+    }
+
+    fn termination_message(&self, reason: TerminationReason, limits: &ExecutionLimits) -> String {
+        match reason {
+            TerminationReason::CpuTime => format!(
+                "script was terminated after exceeding the cpu time limit of {}ms",
+                limits.cpu_time.as_millis()
+            ),
+            TerminationReason::Memory => format!(
+                "script was terminated after exceeding the memory limit of {}MB",
+                self.config.memory_limit_mb
+            ),
+            TerminationReason::WallClock => format!(
+                "script timed out after exceeding the time limit of {}ms",
+                limits.wall_clock.as_millis()
+            ),
         }
     }
 }
@@ -264,15 +907,7 @@ impl Future for ScriptExecutorFuture {
         let this = self.get_mut();
 
         // Initial pass when not messages are available
-        {
-            // Poll the promises local set
-            _ = Pin::new(&mut this.local_set).poll(cx);
-
-            // Poll event loop for any promises
-            let _ = this
-                .runtime
-                .poll_event_loop(cx, PollEventLoopOptions::default());
-        }
+        this.poll_runtimes(cx);
 
         // Poll incoming script execute messages
         while let Poll::Ready(msg) = this.rx.poll_recv(cx) {
@@ -285,30 +920,27 @@ impl Future for ScriptExecutorFuture {
                 ScriptExecutorMessage::EventScript {
                     ctx,
                     script,
+                    permissions,
+                    limits,
                     data,
                     tx,
-                } => {
-                    let result = execute_script(&mut this.runtime, ctx, script, data);
-                    spawn_script_promise(&mut this.runtime, result, tx, &mut this.local_set)
-                }
+                } => this.run_script(ctx, permissions, limits, tx, |runtime| {
+                    execute_script(runtime, ctx, script, data)
+                }),
                 ScriptExecutorMessage::CommandScript {
                     ctx,
                     script,
+                    permissions,
+                    limits,
                     cmd_ctx,
                     tx,
-                } => {
-                    let result = execute_command(&mut this.runtime, ctx, script, cmd_ctx);
-                    spawn_script_promise(&mut this.runtime, result, tx, &mut this.local_set)
-                }
+                } => this.run_script(ctx, permissions, limits, tx, |runtime| {
+                    execute_command(runtime, ctx, script, cmd_ctx)
+                }),
             }
 
-            // Poll the promises local set
-            _ = Pin::new(&mut this.local_set).poll(cx);
-
-            // Poll the event loop
-            let _ = this
-                .runtime
-                .poll_event_loop(cx, PollEventLoopOptions::default());
+            // Poll the runtimes
+            this.poll_runtimes(cx);
         }
 
         Poll::Pending
@@ -323,6 +955,7 @@ pub struct CommandContext {
     pub message: String,
     pub user: CommandContextUser,
     pub args: Vec<String>,
+    pub arguments: CommandArguments,
     pub input_data: EventInputData,
 }
 
@@ -492,3 +1125,344 @@ impl ScriptRuntimeDataExt for Rc<RefCell<OpState>> {
         Ok(data.twitch.clone())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        charge_cpu_time, create_script_executor, poll_cpu_time_limit, ExecutionLimits,
+        PendingExecution, RuntimeExecutionContext, ScriptExecutorHandle, ScriptRuntimeData,
+        ScriptWorkerHandle,
+    };
+    use crate::{
+        database::{
+            entity::{
+                app_data::{ScriptIsolation, ScriptRuntimeConfig},
+                shared::{ScriptLimits, ScriptPermissions},
+            },
+            mock_database,
+        },
+        events::matching::{EventData, EventInputData},
+        twitch::manager::Twitch,
+    };
+    use std::{collections::HashMap, sync::atomic::Ordering, time::Duration};
+    use tokio::sync::{broadcast, mpsc, oneshot};
+    use uuid::Uuid;
+
+    async fn executor(config: ScriptRuntimeConfig) -> ScriptExecutorHandle {
+        let runtime_data = ScriptRuntimeData {
+            overlay_sender: broadcast::channel(16).0,
+            db: mock_database().await,
+            twitch: Twitch::new(mpsc::unbounded_channel().0),
+        };
+
+        create_script_executor(std::env::temp_dir(), runtime_data, config)
+    }
+
+    async fn execute(
+        handle: &ScriptExecutorHandle,
+        script: &str,
+        limits: ScriptLimits,
+    ) -> anyhow::Result<()> {
+        let ctx = RuntimeExecutionContext::Event {
+            event_id: Uuid::new_v4(),
+        };
+
+        execute_with_ctx(handle, ctx, script, limits).await
+    }
+
+    async fn execute_with_ctx(
+        handle: &ScriptExecutorHandle,
+        ctx: RuntimeExecutionContext,
+        script: &str,
+        limits: ScriptLimits,
+    ) -> anyhow::Result<()> {
+        handle
+            .execute(
+                ctx,
+                script.to_string(),
+                None,
+                limits,
+                EventData {
+                    user: None,
+                    input_data: EventInputData::None,
+                },
+            )
+            .await
+    }
+
+    fn pending(cpu_time: Duration, limit: Duration) -> PendingExecution {
+        PendingExecution {
+            ctx: RuntimeExecutionContext::Event {
+                event_id: Uuid::nil(),
+            },
+            tx: oneshot::channel().0,
+            limits: ExecutionLimits {
+                wall_clock: Duration::from_secs(60),
+                cpu_time: limit,
+            },
+            cpu_time,
+        }
+    }
+
+    /// Creates a handle with `workers` workers that are not running
+    fn handle_with_workers(workers: usize, isolation: ScriptIsolation) -> ScriptExecutorHandle {
+        let workers = (0..workers)
+            .map(|_| ScriptWorkerHandle {
+                tx: mpsc::channel(1).0,
+                active: Default::default(),
+            })
+            .collect();
+
+        ScriptExecutorHandle { workers, isolation }
+    }
+
+    /// Gets the index of the worker selected for a script
+    fn selected_worker(
+        handle: &ScriptExecutorHandle,
+        ctx: RuntimeExecutionContext,
+        permissions: Option<ScriptPermissions>,
+    ) -> usize {
+        let worker = handle.select_worker(ctx, &permissions).unwrap();
+        handle
+            .workers
+            .iter()
+            .position(|other| std::ptr::eq(other, worker))
+            .unwrap()
+    }
+
+    /// Marks every worker other than `index` as busy
+    fn set_least_busy(handle: &ScriptExecutorHandle, index: usize) {
+        for (other, worker) in handle.workers.iter().enumerate() {
+            worker
+                .active
+                .store(usize::from(other != index), Ordering::SeqCst);
+        }
+    }
+
+    /// Tests that the default config runs every script on one shared runtime
+    #[test]
+    fn test_default_config() {
+        let config = ScriptRuntimeConfig::default();
+        assert_eq!(config.pool_size, 1);
+        assert_eq!(config.isolation, ScriptIsolation::Shared);
+    }
+
+    /// Tests that scripts with their own runtime are always run on the same
+    /// worker regardless of how busy the workers are
+    #[test]
+    fn test_select_worker_per_script() {
+        let handle = handle_with_workers(4, ScriptIsolation::PerScript);
+        let ctx = RuntimeExecutionContext::Event {
+            event_id: Uuid::new_v4(),
+        };
+
+        let pinned = selected_worker(&handle, ctx, None);
+
+        for index in 0..4 {
+            set_least_busy(&handle, index);
+            assert_eq!(selected_worker(&handle, ctx, None), pinned);
+        }
+
+        // Scripts are spread across the workers
+        let workers: Vec<usize> = (0..32)
+            .map(|_| {
+                let ctx = RuntimeExecutionContext::Command {
+                    command_id: Uuid::new_v4(),
+                };
+                selected_worker(&handle, ctx, None)
+            })
+            .collect();
+        assert!(workers.iter().any(|worker| *worker != workers[0]));
+    }
+
+    /// Tests that scripts using the shared runtime are run on the least busy
+    /// worker while scripts with permissions are pinned to a worker
+    #[test]
+    fn test_select_worker_shared() {
+        let handle = handle_with_workers(4, ScriptIsolation::Shared);
+        let ctx = RuntimeExecutionContext::Event {
+            event_id: Uuid::new_v4(),
+        };
+
+        for index in 0..4 {
+            set_least_busy(&handle, index);
+            assert_eq!(selected_worker(&handle, ctx, None), index);
+        }
+
+        let permissions = Some(ScriptPermissions::default());
+        let pinned = selected_worker(&handle, ctx, permissions.clone());
+        for index in 0..4 {
+            set_least_busy(&handle, index);
+            assert_eq!(selected_worker(&handle, ctx, permissions.clone()), pinned);
+        }
+    }
+
+    /// Tests that per execution runtimes are run on the least busy worker
+    #[test]
+    fn test_select_worker_per_execution() {
+        let handle = handle_with_workers(4, ScriptIsolation::PerExecution);
+        let ctx = RuntimeExecutionContext::Event {
+            event_id: Uuid::new_v4(),
+        };
+
+        for index in 0..4 {
+            set_least_busy(&handle, index);
+            assert_eq!(selected_worker(&handle, ctx, None), index);
+        }
+    }
+
+    /// Tests that globals are kept between executions of a script
+    /// when using per script isolation with multiple workers
+    #[tokio::test]
+    async fn test_per_script_globals() {
+        let handle = executor(ScriptRuntimeConfig {
+            pool_size: 4,
+            isolation: ScriptIsolation::PerScript,
+            ..Default::default()
+        })
+        .await;
+        let ctx = RuntimeExecutionContext::Event {
+            event_id: Uuid::new_v4(),
+        };
+
+        let script = r#"
+            globalThis.count = (globalThis.count ?? 0) + 1;
+            if (globalThis.count !== 3) throw new Error("count " + globalThis.count);
+        "#;
+
+        for _ in 0..2 {
+            _ = execute_with_ctx(&handle, ctx, script, ScriptLimits::default()).await;
+        }
+
+        execute_with_ctx(&handle, ctx, script, ScriptLimits::default())
+            .await
+            .unwrap();
+    }
+
+    /// Tests that limits declared by a script override the config defaults
+    #[test]
+    fn test_resolve_limits() {
+        let config = ScriptRuntimeConfig::default();
+
+        let limits = ExecutionLimits::resolve(ScriptLimits::default(), &config);
+        assert_eq!(limits.wall_clock, Duration::from_millis(120_000));
+        assert_eq!(limits.cpu_time, Duration::from_millis(5_000));
+
+        let limits = ExecutionLimits::resolve(
+            ScriptLimits {
+                wall_clock_limit: Some(1_000),
+                cpu_time_limit: Some(100),
+            },
+            &config,
+        );
+        assert_eq!(limits.wall_clock, Duration::from_millis(1_000));
+        assert_eq!(limits.cpu_time, Duration::from_millis(100));
+    }
+
+    /// Tests that run time is split between pending executions and
+    /// accumulates until an execution exceeds its limit
+    #[test]
+    fn test_charge_cpu_time() {
+        let mut pending = HashMap::from([
+            (1, pending(Duration::ZERO, Duration::from_millis(100))),
+            (2, pending(Duration::ZERO, Duration::from_millis(300))),
+        ]);
+
+        assert_eq!(
+            poll_cpu_time_limit(&pending, Duration::from_secs(5)),
+            Duration::from_millis(300)
+        );
+
+        assert!(charge_cpu_time(&mut pending, Duration::from_millis(160)).is_empty());
+        assert_eq!(pending[&1].cpu_time, Duration::from_millis(80));
+        assert_eq!(
+            poll_cpu_time_limit(&pending, Duration::from_secs(5)),
+            Duration::from_millis(220)
+        );
+
+        assert_eq!(
+            charge_cpu_time(&mut pending, Duration::from_millis(80)),
+            vec![1]
+        );
+
+        // Runtimes without pending executions use the default
+        assert_eq!(
+            poll_cpu_time_limit(&HashMap::new(), Duration::from_secs(5)),
+            Duration::from_secs(5)
+        );
+    }
+
+    /// Tests that a script blocking the runtime is terminated
+    #[tokio::test]
+    async fn test_cpu_time_limit() {
+        let handle = executor(ScriptRuntimeConfig::default()).await;
+        let limits = ScriptLimits {
+            cpu_time_limit: Some(200),
+            ..Default::default()
+        };
+
+        let err = execute(&handle, "while (true) {}", limits)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cpu time limit of 200ms"));
+
+        // Runtime is replaced for later scripts
+        execute(&handle, "return;", ScriptLimits::default())
+            .await
+            .unwrap();
+    }
+
+    /// Tests that CPU time is counted across awaits rather than
+    /// only while the runtime is continuously blocked
+    #[tokio::test]
+    async fn test_cpu_time_limit_across_awaits() {
+        let handle = executor(ScriptRuntimeConfig::default()).await;
+        let limits = ScriptLimits {
+            cpu_time_limit: Some(300),
+            ..Default::default()
+        };
+
+        let script = r#"
+            for (let i = 0; i < 20; i++) {
+                const end = Date.now() + 50;
+                while (Date.now() < end) {}
+                await api.core.sleep(1);
+            }
+        "#;
+
+        let err = execute(&handle, script, limits).await.unwrap_err();
+        assert!(err.to_string().contains("cpu time limit of 300ms"));
+    }
+
+    /// Tests that a script taking too long is timed out and
+    /// its runtime is reset
+    #[tokio::test]
+    async fn test_wall_clock_limit() {
+        let handle = executor(ScriptRuntimeConfig {
+            wall_clock_limit: 200,
+            ..Default::default()
+        })
+        .await;
+
+        let err = execute(
+            &handle,
+            "await api.core.sleep(60000);",
+            ScriptLimits::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("time limit of 200ms"));
+
+        // Script limit overrides the default
+        execute(
+            &handle,
+            "await api.core.sleep(300);",
+            ScriptLimits {
+                wall_clock_limit: Some(1_000),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! # Watchdog
+//!
+//! Background thread that terminates scripts which run JS code for longer
+//! than they are allowed to (i.e `while(true){}`).
+//!
+//! Each script worker marks when it starts and stops running JS code on one of
+//! its runtimes along with how long that run is allowed to take. The worker
+//! provides the CPU time remaining for the executions on the runtime, so time
+//! is counted across every run rather than only a single continuous block. If
+//! a run takes too long the watchdog terminates it using V8's `terminate_execution`
+
+use deno_core::v8::IsolateHandle;
+use log::warn;
+use parking_lot::Mutex;
+use std::{
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Interval between each watchdog check
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Handle to the watchdog, the watchdog thread stops once
+/// the handle is dropped
+pub struct ScriptWatchdog {
+    inner: Arc<ScriptWatchdogInner>,
+}
+
+struct ScriptWatchdogInner {
+    /// Isolate currently running JS code, None when idle
+    busy: Mutex<Option<BusyIsolate>>,
+    /// Whether the watchdog terminated execution
+    terminated: AtomicBool,
+}
+
+struct BusyIsolate {
+    /// Handle to the isolate running JS code
+    isolate: IsolateHandle,
+    /// Time the isolate started running
+    since: Instant,
+    /// Maximum time the isolate can run for
+    limit: Duration,
+}
+
+/// Guard for a section of JS execution, marks the runtime
+/// as idle when dropped
+pub struct ScriptWatchdogGuard<'a> {
+    watchdog: &'a ScriptWatchdog,
+}
+
+impl ScriptWatchdog {
+    /// Creates a watchdog, spawning the thread that performs the checks
+    pub fn new() -> Self {
+        let inner = Arc::new(ScriptWatchdogInner {
+            busy: Mutex::new(None),
+            terminated: AtomicBool::new(false),
+        });
+
+        let weak_inner = Arc::downgrade(&inner);
+        std::thread::spawn(move || run_watchdog(weak_inner));
+
+        Self { inner }
+    }
+
+    /// Marks the `isolate` as busy running JS code until the returned
+    /// guard is dropped, terminating it if it runs for longer than `limit`
+    pub fn enter(&self, isolate: IsolateHandle, limit: Duration) -> ScriptWatchdogGuard<'_> {
+        *self.inner.busy.lock() = Some(BusyIsolate {
+            isolate,
+            since: Instant::now(),
+            limit,
+        });
+        ScriptWatchdogGuard { watchdog: self }
+    }
+
+    /// Checks if the watchdog terminated execution, resetting the flag
+    pub fn take_terminated(&self) -> bool {
+        self.inner.terminated.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Drop for ScriptWatchdogGuard<'_> {
+    fn drop(&mut self) {
+        *self.watchdog.inner.busy.lock() = None;
+    }
+}
+
+fn run_watchdog(inner: Weak<ScriptWatchdogInner>) {
+    loop {
+        std::thread::sleep(WATCHDOG_INTERVAL);
+
+        // Watchdog handle was dropped
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+
+        let busy = &mut *inner.busy.lock();
+        let exceeded = busy
+            .as_ref()
+            .is_some_and(|busy| Instant::now().duration_since(busy.since) > busy.limit);
+
+        if !exceeded {
+            continue;
+        }
+
+        // Clear the busy state so the execution is only terminated once
+        let Some(busy) = busy.take() else {
+            continue;
+        };
+
+        warn!("script exceeded cpu time limit, terminating execution");
+
+        inner.terminated.store(true, Ordering::SeqCst);
+        busy.isolate.terminate_execution();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScriptWatchdog;
+    use deno_core::{JsRuntime, RuntimeOptions};
+    use std::time::Duration;
+
+    /// Tests that code running for longer than the limit is terminated
+    #[test]
+    fn test_terminates_over_limit() {
+        let watchdog = ScriptWatchdog::new();
+        let mut runtime = JsRuntime::new(RuntimeOptions::default());
+
+        let result = {
+            let isolate = runtime.v8_isolate().thread_safe_handle();
+            let _guard = watchdog.enter(isolate, Duration::from_millis(100));
+            runtime.execute_script("<test>", "while (true) {}")
+        };
+
+        assert!(result.is_err());
+        assert!(watchdog.take_terminated());
+        assert!(!watchdog.take_terminated());
+    }
+
+    /// Tests that code completing within the limit is not terminated
+    #[test]
+    fn test_within_limit() {
+        let watchdog = ScriptWatchdog::new();
+        let mut runtime = JsRuntime::new(RuntimeOptions::default());
+
+        let result = {
+            let isolate = runtime.v8_isolate().thread_safe_handle();
+            let _guard = watchdog.enter(isolate, Duration::from_secs(5));
+            runtime.execute_script(
+                "<test>",
+                "let total = 0; for (let i = 0; i < 1000; i++) total += i;",
+            )
+        };
+
+        assert!(result.is_ok());
+
+        // Idle runtimes are never terminated
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!watchdog.take_terminated());
+    }
+}
@@ -3,12 +3,15 @@
 //! Tray menu logic for the minimize to tray functionality
 
 use anyhow::Context;
+use log::error;
 use tauri::{
-    App, AppHandle, Manager,
-    menu::{IconMenuItem, Menu, MenuItem},
+    App, AppHandle, Listener, Manager,
+    menu::{CheckMenuItem, IconMenuItem, Menu, MenuItem},
     tray::TrayIconBuilder,
 };
 
+use crate::events::pause::{PAUSE_CHANGED_EVENT, PauseSnapshot, ProcessingPause};
+
 #[cfg(not(debug_assertions))]
 const TRAY_NAME: &str = "VTFTK - VTuber Fun Toolkit";
 #[cfg(debug_assertions)]
@@ -23,8 +26,19 @@ pub fn create_tray_menu(app: &mut App) -> anyhow::Result<()> {
 
     let title_i = IconMenuItem::new(app, TRAY_NAME, false, Some(icon.clone()), None::<&str>)?;
     let open_i = MenuItem::with_id(app, "open", "Open", true, None::<&str>)?;
+    let pause_i = CheckMenuItem::with_id(app, "pause", "Pause VTFTK", true, false, None::<&str>)?;
     let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&title_i, &open_i, &quit_i])?;
+    let menu = Menu::with_items(app, &[&title_i, &open_i, &pause_i, &quit_i])?;
+
+    // Keep the pause item in sync when paused from elsewhere
+    app.listen_any(PAUSE_CHANGED_EVENT, {
+        let pause_i = pause_i.clone();
+        move |event| {
+            if let Ok(snapshot) = serde_json::from_str::<PauseSnapshot>(event.payload()) {
+                _ = pause_i.set_checked(snapshot.paused);
+            }
+        }
+    });
 
     TrayIconBuilder::new()
         .icon(icon)
@@ -34,6 +48,10 @@ pub fn create_tray_menu(app: &mut App) -> anyhow::Result<()> {
         .on_menu_event(move |app, event| {
             if event.id() == open_i.id() {
                 handle_open_clicked(app).expect("failed to open")
+            } else if event.id == pause_i.id() {
+                if let Err(err) = handle_pause_clicked(app) {
+                    error!("failed to toggle pause: {err:?}");
+                }
             } else if event.id == quit_i.id() {
                 handle_quit_clicked(app).expect("failed to quit")
             }
@@ -64,6 +82,16 @@ fn handle_open_clicked(app: &AppHandle) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Handles "Pause VTFTK" being clicked in the tray menu, toggles
+/// the pausing of event and command processing
+fn handle_pause_clicked(app: &AppHandle) -> anyhow::Result<()> {
+    let pause = app
+        .try_state::<ProcessingPause>()
+        .context("pause state not available")?;
+    pause.set_paused(!pause.is_paused());
+    Ok(())
+}
+
 /// Handles "Quit" being clicked in the tray menu, closes the
 /// application
 fn handle_quit_clicked(app: &AppHandle) -> anyhow::Result<()> {
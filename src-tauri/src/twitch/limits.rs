@@ -0,0 +1,261 @@
+//! # Limits
+//!
+//! Limits enforced by the Twitch API, requests are checked against these
+//! before being sent so that invalid configs can be reported when saved
+
+use anyhow::bail;
+use std::ops::RangeInclusive;
+
+use super::manager::{CreateReward, UpdateReward};
+
+/// Maximum length of a poll title
+const POLL_TITLE_MAX_LENGTH: usize = 60;
+/// Number of choices a poll can have
+const POLL_CHOICES: RangeInclusive<usize> = 2..=5;
+/// Maximum length of a poll choice title
+const POLL_CHOICE_MAX_LENGTH: usize = 25;
+/// Duration a poll can run for (Seconds)
+const POLL_DURATION: RangeInclusive<u32> = 15..=1800;
+
+/// Maximum length of a prediction title
+const PREDICTION_TITLE_MAX_LENGTH: usize = 45;
+/// Number of outcomes a prediction can have
+const PREDICTION_OUTCOMES: RangeInclusive<usize> = 2..=10;
+/// Maximum length of a prediction outcome title
+const PREDICTION_OUTCOME_MAX_LENGTH: usize = 25;
+/// Duration a prediction can accept predictions for (Seconds)
+const PREDICTION_DURATION: RangeInclusive<u32> = 30..=1800;
+
+/// Maximum length of a reward title
+const REWARD_TITLE_MAX_LENGTH: usize = 45;
+/// Maximum length of a reward prompt
+const REWARD_PROMPT_MAX_LENGTH: usize = 200;
+/// Minimum cost of a reward in channel points
+const REWARD_MIN_COST: u32 = 1;
+/// Maximum global cooldown of a reward, 7 days (Seconds)
+const REWARD_MAX_COOLDOWN: u32 = 604_800;
+
+/// Ensures a poll can be created with the provided details
+pub fn validate_poll(title: &str, choices: &[String], duration: u32) -> anyhow::Result<()> {
+    validate_title("poll title", title, POLL_TITLE_MAX_LENGTH)?;
+    validate_count("poll choices", choices.len(), POLL_CHOICES)?;
+
+    for choice in choices {
+        validate_title("poll choice", choice, POLL_CHOICE_MAX_LENGTH)?;
+    }
+
+    validate_duration("poll duration", duration, POLL_DURATION)
+}
+
+/// Ensures a prediction can be created with the provided details
+pub fn validate_prediction(title: &str, outcomes: &[String], duration: u32) -> anyhow::Result<()> {
+    validate_title("prediction title", title, PREDICTION_TITLE_MAX_LENGTH)?;
+    validate_count("prediction outcomes", outcomes.len(), PREDICTION_OUTCOMES)?;
+
+    for outcome in outcomes {
+        validate_title("prediction outcome", outcome, PREDICTION_OUTCOME_MAX_LENGTH)?;
+    }
+
+    validate_duration("prediction duration", duration, PREDICTION_DURATION)
+}
+
+/// Ensures a reward can be created with the provided details
+pub fn validate_create_reward(create: &CreateReward) -> anyhow::Result<()> {
+    validate_title("reward title", &create.title, REWARD_TITLE_MAX_LENGTH)?;
+    validate_reward_cost(create.cost)?;
+    validate_reward_details(create.prompt.as_deref(), create.cooldown)
+}
+
+/// Ensures the changes can be applied to a reward, only the
+/// fields that are changed are checked
+pub fn validate_update_reward(update: &UpdateReward) -> anyhow::Result<()> {
+    if let Some(title) = update.title.as_deref() {
+        validate_title("reward title", title, REWARD_TITLE_MAX_LENGTH)?;
+    }
+
+    if let Some(cost) = update.cost {
+        validate_reward_cost(cost)?;
+    }
+
+    validate_reward_details(update.prompt.as_deref(), update.cooldown)
+}
+
+/// Ensures a reward ID was provided
+pub fn validate_reward_id(reward_id: &str) -> anyhow::Result<()> {
+    if reward_id.trim().is_empty() {
+        bail!("reward id cannot be empty");
+    }
+
+    Ok(())
+}
+
+fn validate_reward_cost(cost: u32) -> anyhow::Result<()> {
+    if cost < REWARD_MIN_COST {
+        bail!("reward cost must be at least {REWARD_MIN_COST}");
+    }
+
+    Ok(())
+}
+
+fn validate_reward_details(prompt: Option<&str>, cooldown: Option<u32>) -> anyhow::Result<()> {
+    if prompt.is_some_and(|prompt| prompt.chars().count() > REWARD_PROMPT_MAX_LENGTH) {
+        bail!("reward prompt cannot be longer than {REWARD_PROMPT_MAX_LENGTH} characters");
+    }
+
+    if cooldown.is_some_and(|cooldown| cooldown > REWARD_MAX_COOLDOWN) {
+        bail!("reward cooldown cannot be longer than {REWARD_MAX_COOLDOWN} seconds");
+    }
+
+    Ok(())
+}
+
+fn validate_title(name: &str, value: &str, max_length: usize) -> anyhow::Result<()> {
+    let length = value.trim().chars().count();
+    if length == 0 {
+        bail!("{name} cannot be empty");
+    }
+
+    if length > max_length {
+        bail!("{name} \"{value}\" cannot be longer than {max_length} characters");
+    }
+
+    Ok(())
+}
+
+fn validate_count(name: &str, count: usize, range: RangeInclusive<usize>) -> anyhow::Result<()> {
+    if !range.contains(&count) {
+        bail!(
+            "{name} must have between {} and {} entries, got {count}",
+            range.start(),
+            range.end()
+        );
+    }
+
+    Ok(())
+}
+
+fn validate_duration(name: &str, duration: u32, range: RangeInclusive<u32>) -> anyhow::Result<()> {
+    if !range.contains(&duration) {
+        bail!(
+            "{name} must be between {} and {} seconds, got {duration}",
+            range.start(),
+            range.end()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        validate_create_reward, validate_poll, validate_prediction, validate_reward_id,
+        validate_update_reward,
+    };
+    use crate::twitch::manager::{CreateReward, UpdateReward};
+
+    fn entries(count: usize) -> Vec<String> {
+        (1..=count).map(|index| format!("Choice {index}")).collect()
+    }
+
+    /// Tests the number of poll choices, their lengths and the poll duration
+    #[test]
+    fn test_validate_poll() {
+        assert!(validate_poll("Question", &entries(2), 60).is_ok());
+        assert!(validate_poll("Question", &entries(5), 15).is_ok());
+        assert!(validate_poll("Question", &entries(5), 1800).is_ok());
+
+        assert!(validate_poll("Question", &entries(1), 60).is_err());
+        assert!(validate_poll("Question", &entries(6), 60).is_err());
+        assert!(validate_poll("Question", &entries(2), 14).is_err());
+        assert!(validate_poll("Question", &entries(2), 1801).is_err());
+        assert!(validate_poll("", &entries(2), 60).is_err());
+        assert!(validate_poll(&"a".repeat(61), &entries(2), 60).is_err());
+        assert!(validate_poll("Question", &["Yes".to_string(), " ".to_string()], 60).is_err());
+        assert!(validate_poll("Question", &["Yes".to_string(), "a".repeat(26)], 60).is_err());
+    }
+
+    /// Tests the number of prediction outcomes, their lengths and the prediction duration
+    #[test]
+    fn test_validate_prediction() {
+        assert!(validate_prediction("Question", &entries(2), 30).is_ok());
+        assert!(validate_prediction("Question", &entries(10), 1800).is_ok());
+
+        assert!(validate_prediction("Question", &entries(1), 60).is_err());
+        assert!(validate_prediction("Question", &entries(11), 60).is_err());
+        assert!(validate_prediction("Question", &entries(2), 29).is_err());
+        assert!(validate_prediction("Question", &entries(2), 1801).is_err());
+        assert!(validate_prediction(&"a".repeat(46), &entries(2), 60).is_err());
+        assert!(validate_prediction("Question", &["Yes".to_string(), "a".repeat(26)], 60).is_err());
+    }
+
+    /// Tests the reward title, cost and cooldown limits when creating a reward
+    #[test]
+    fn test_validate_create_reward() {
+        let reward = |title: &str, cost, cooldown| CreateReward {
+            title: title.to_string(),
+            cost,
+            prompt: None,
+            cooldown,
+            enabled: None,
+        };
+
+        assert!(validate_create_reward(&reward("Hydrate", 1, None)).is_ok());
+        assert!(validate_create_reward(&reward("Hydrate", 100, Some(0))).is_ok());
+        assert!(validate_create_reward(&reward(&"a".repeat(45), 100, Some(604_800))).is_ok());
+
+        assert!(validate_create_reward(&reward("Hydrate", 0, None)).is_err());
+        assert!(validate_create_reward(&reward(" ", 100, None)).is_err());
+        assert!(validate_create_reward(&reward(&"a".repeat(46), 100, None)).is_err());
+        assert!(validate_create_reward(&reward("Hydrate", 100, Some(604_801))).is_err());
+    }
+
+    /// Tests that only the changed fields of a reward are checked
+    #[test]
+    fn test_validate_update_reward() {
+        assert!(validate_update_reward(&UpdateReward::default()).is_ok());
+        assert!(
+            validate_update_reward(&UpdateReward {
+                paused: Some(true),
+                ..Default::default()
+            })
+            .is_ok()
+        );
+
+        assert!(
+            validate_update_reward(&UpdateReward {
+                cost: Some(0),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            validate_update_reward(&UpdateReward {
+                title: Some("a".repeat(46)),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            validate_update_reward(&UpdateReward {
+                cooldown: Some(604_801),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            validate_update_reward(&UpdateReward {
+                prompt: Some("a".repeat(201)),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    /// Tests that a reward ID is required
+    #[test]
+    fn test_validate_reward_id() {
+        assert!(validate_reward_id("mock-reward-id").is_ok());
+        assert!(validate_reward_id("").is_err());
+    }
+}
@@ -7,9 +7,11 @@ use crate::{
     events::{AppEvent, AppEventSender},
 };
 use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
 use log::{debug, error, info};
 use parking_lot::RwLock;
+use serde::Deserialize;
 use std::sync::Arc;
 use tokio::join;
 use twitch_api::{
@@ -19,7 +21,17 @@ use twitch_api::{
             ChannelEmote, SendChatMessageBody, SendChatMessageRequest, SendChatMessageResponse,
         },
         moderation::Moderator,
-        points::CustomReward,
+        points::{
+            CreateCustomRewardBody, CreateCustomRewardRequest, CustomReward,
+            CustomRewardRedemptionStatus, UpdateCustomRewardBody, UpdateCustomRewardRequest,
+            UpdateCustomRewardResponse, UpdateRedemptionStatusBody,
+            UpdateRedemptionStatusInformation, UpdateRedemptionStatusRequest,
+        },
+        polls::{CreatePollBody, CreatePollRequest, NewPollChoice, Poll},
+        predictions::{
+            CreatePredictionBody, CreatePredictionRequest, NewPredictionOutcome, Prediction,
+        },
+        streams::{GetStreamsRequest, Stream},
         Scope,
     },
     twitch_oauth2::{types::ClientIdRef, AccessToken, ImplicitUserTokenBuilder, UserToken},
@@ -63,6 +75,14 @@ pub const TWITCH_REQUIRED_SCOPES: &[Scope] = &[
     Scope::ChannelManageBroadcast,
     // Scope to read ad break messages
     Scope::ChannelReadAds,
+    // Scope to read hype train events
+    Scope::ChannelReadHypeTrain,
+    // Allow creating polls and reading poll events
+    Scope::ChannelManagePolls,
+    // Allow creating predictions and reading prediction events
+    Scope::ChannelManagePredictions,
+    // Allow fulfilling and refunding channel point redemptions
+    Scope::ChannelManageRedemptions,
 ];
 
 #[derive(Clone)]
@@ -73,9 +93,30 @@ pub struct Twitch {
 struct TwitchInner {
     helix_client: HelixClient<'static, reqwest::Client>,
     state: RwLock<TwitchManagerState>,
+    stream: RwLock<StreamState>,
     tx: AppEventSender,
 }
 
+/// Live state of the broadcaster stream, tracked separately from the
+/// authentication state so it can be updated by stream events
+struct StreamState {
+    /// Whether the broadcaster is currently live
+    live: bool,
+
+    /// Start of the current stream session, when the broadcaster is
+    /// offline this is the time the app authenticated
+    session_started_at: DateTime<Utc>,
+}
+
+impl Default for StreamState {
+    fn default() -> Self {
+        Self {
+            live: false,
+            session_started_at: Utc::now(),
+        }
+    }
+}
+
 pub struct TwitchManagerStateAuthenticated {
     /// Token for the authenticated user
     token: UserToken,
@@ -93,6 +134,43 @@ pub struct TwitchManagerStateAuthenticated {
     vips: Option<Arc<[Vip]>>,
 }
 
+/// Details for creating a new custom channel point reward
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateReward {
+    /// Title of the reward
+    pub title: String,
+    /// Cost of the reward in channel points
+    pub cost: u32,
+    /// Prompt shown to the viewer when redeeming
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Global cooldown in seconds, zero disables the cooldown
+    #[serde(default)]
+    pub cooldown: Option<u32>,
+    /// Whether the reward is enabled, rewards are enabled by default
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Changes to apply to an existing custom channel point reward, only
+/// the fields that are specified are changed
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct UpdateReward {
+    /// New title of the reward
+    pub title: Option<String>,
+    /// New cost of the reward in channel points
+    pub cost: Option<u32>,
+    /// New prompt shown to the viewer when redeeming
+    pub prompt: Option<String>,
+    /// New global cooldown in seconds, zero disables the cooldown
+    pub cooldown: Option<u32>,
+    /// Whether the reward is enabled (Disabled rewards are hidden from viewers)
+    pub enabled: Option<bool>,
+    /// Whether the reward is paused (Paused rewards are visible but cannot be redeemed)
+    pub paused: Option<bool>,
+}
+
 #[derive(Default)]
 #[allow(clippy::large_enum_variant)]
 enum TwitchManagerState {
@@ -109,6 +187,7 @@ impl Twitch {
             _inner: Arc::new(TwitchInner {
                 helix_client: HelixClient::default(),
                 state: Default::default(),
+                stream: Default::default(),
                 tx,
             }),
         }
@@ -249,6 +328,185 @@ impl Twitch {
         Ok(response.pop())
     }
 
+    /// Creates a new poll on the channel, duration is in seconds
+    pub async fn create_poll(
+        &self,
+        title: &str,
+        choices: &[String],
+        duration: u32,
+    ) -> anyhow::Result<Poll> {
+        // Obtain twitch access token
+        let token = self.get_user_token().context("not authenticated")?;
+
+        // Get broadcaster user ID
+        let user_id = token.user_id.clone();
+
+        let choices: Vec<NewPollChoice> = choices
+            .iter()
+            .map(|choice| NewPollChoice::new(choice.as_str()))
+            .collect();
+
+        // Create poll request
+        let request = CreatePollRequest::new();
+        let body = CreatePollBody::new(user_id, title, duration as i64, &choices);
+
+        // Send request and get response
+        let response: Poll = self
+            .helix_client()
+            .req_post(request, body, &token)
+            .await?
+            .data;
+
+        Ok(response)
+    }
+
+    /// Creates a new prediction on the channel, duration is in seconds
+    pub async fn create_prediction(
+        &self,
+        title: &str,
+        outcomes: &[String],
+        duration: u32,
+    ) -> anyhow::Result<Prediction> {
+        // Obtain twitch access token
+        let token = self.get_user_token().context("not authenticated")?;
+
+        // Get broadcaster user ID
+        let user_id = token.user_id.clone();
+
+        let outcomes: Vec<NewPredictionOutcome> = outcomes
+            .iter()
+            .map(|outcome| NewPredictionOutcome::new(outcome.as_str()))
+            .collect();
+
+        // Create prediction request
+        let request = CreatePredictionRequest::new();
+        let body = CreatePredictionBody::new(user_id, title, &outcomes, duration as i64);
+
+        // Send request and get response
+        let response: Prediction = self
+            .helix_client()
+            .req_post(request, body, &token)
+            .await?
+            .data;
+
+        Ok(response)
+    }
+
+    /// Creates a new custom channel point reward owned by the app, the
+    /// rewards list is reloaded after creation
+    pub async fn create_reward(&self, create: CreateReward) -> anyhow::Result<CustomReward> {
+        // Obtain twitch access token
+        let token = self.get_user_token().context("not authenticated")?;
+
+        // Get broadcaster user ID
+        let user_id = token.user_id.clone();
+
+        // Create reward request
+        let request = CreateCustomRewardRequest::broadcaster_id(user_id);
+        let mut body = CreateCustomRewardBody::new(create.title.as_str(), create.cost as usize);
+        body.prompt = create.prompt.as_deref().map(Into::into);
+        body.is_enabled = create.enabled;
+
+        if let Some(cooldown) = create.cooldown {
+            body.is_global_cooldown_enabled = Some(cooldown > 0);
+            body.global_cooldown_seconds = Some(cooldown as usize);
+        }
+
+        // Send request and get response
+        let response: CustomReward = self
+            .helix_client()
+            .req_post(request, body, &token)
+            .await?
+            .data;
+
+        self.reload_rewards_list().await;
+
+        Ok(response)
+    }
+
+    /// Updates an existing custom channel point reward, Twitch only allows
+    /// updating rewards that were created by this app
+    pub async fn update_reward(&self, reward_id: &str, update: UpdateReward) -> anyhow::Result<()> {
+        // Obtain twitch access token
+        let token = self.get_user_token().context("not authenticated")?;
+
+        // Get broadcaster user ID
+        let user_id = token.user_id.clone();
+
+        // Create update reward request
+        let request = UpdateCustomRewardRequest::new(user_id, reward_id);
+        let mut body = UpdateCustomRewardBody::default();
+        body.title = update.title.as_deref().map(Into::into);
+        body.cost = update.cost.map(|cost| cost as usize);
+        body.prompt = update.prompt.as_deref().map(Into::into);
+        body.is_enabled = update.enabled;
+        body.is_paused = update.paused;
+
+        if let Some(cooldown) = update.cooldown {
+            body.is_global_cooldown_enabled = Some(cooldown > 0);
+            body.global_cooldown_seconds = Some(cooldown as usize);
+        }
+
+        // Send request and get response
+        let _response: UpdateCustomRewardResponse = self
+            .helix_client()
+            .req_patch(request, body, &token)
+            .await?
+            .data;
+
+        self.reload_rewards_list().await;
+
+        Ok(())
+    }
+
+    /// Pauses or unpauses a custom channel point reward
+    pub async fn set_reward_paused(&self, reward_id: &str, paused: bool) -> anyhow::Result<()> {
+        self.update_reward(
+            reward_id,
+            UpdateReward {
+                paused: Some(paused),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Reloads the rewards list after a change, failing to reload the
+    /// list is logged rather than failing the change itself
+    async fn reload_rewards_list(&self) {
+        if let Err(err) = self.load_rewards_list().await {
+            error!("failed to reload rewards list: {err:?}");
+        }
+    }
+
+    /// Updates the status of a channel point redemption, Twitch only allows
+    /// updating redemptions for rewards that were created by this app
+    pub async fn update_redemption_status(
+        &self,
+        reward_id: &str,
+        redemption_id: &str,
+        status: CustomRewardRedemptionStatus,
+    ) -> anyhow::Result<()> {
+        // Obtain twitch access token
+        let token = self.get_user_token().context("not authenticated")?;
+
+        // Get broadcaster user ID
+        let user_id = token.user_id.clone();
+
+        // Create update redemption request
+        let request = UpdateRedemptionStatusRequest::new(user_id, reward_id, redemption_id);
+        let body = UpdateRedemptionStatusBody::status(status);
+
+        // Send request and get response
+        let _response: UpdateRedemptionStatusInformation = self
+            .helix_client()
+            .req_patch(request, body, &token)
+            .await?
+            .data;
+
+        Ok(())
+    }
+
     pub fn get_user_token(&self) -> Option<UserToken> {
         let lock = &*self._inner.state.read();
         match lock {
@@ -278,16 +536,20 @@ impl Twitch {
                 vips: None,
                 rewards: None,
             });
+
+            // Start a new session for the authenticated user
+            *self._inner.stream.write() = StreamState::default();
         }
 
         // Tell the app we are authenticated
         _ = self._inner.tx.send(AppEvent::TwitchClientLoggedIn);
 
         // Load initial moderator and VIP lists
-        let (rewards_result, vips_result, mods_result) = join!(
+        let (rewards_result, vips_result, mods_result, live_result) = join!(
             self.load_rewards_list(),
             self.load_vip_list(),
-            self.load_moderator_list()
+            self.load_moderator_list(),
+            self.load_stream_status()
         );
 
         if let Err(err) = rewards_result {
@@ -301,12 +563,17 @@ impl Twitch {
         if let Err(err) = mods_result {
             error!("failed to load mods: {:?}", err);
         }
+
+        if let Err(err) = live_result {
+            error!("failed to load stream status: {:?}", err);
+        }
     }
 
     pub fn reset(&self) {
         {
             let lock = &mut *self._inner.state.write();
             *lock = TwitchManagerState::Initial;
+            *self._inner.stream.write() = StreamState::default();
         }
 
         // Tell the app we are authenticated
@@ -428,6 +695,70 @@ impl Twitch {
         }
     }
 
+    /// Whether the broadcaster is currently live
+    pub fn is_live(&self) -> bool {
+        self._inner.stream.read().live
+    }
+
+    /// Updates the current live state of the broadcaster, going live
+    /// starts a new stream session
+    pub fn set_live(&self, live: bool) {
+        let stream = &mut *self._inner.stream.write();
+        if live && !stream.live {
+            stream.session_started_at = Utc::now();
+        }
+
+        stream.live = live;
+    }
+
+    /// Start of the most recent stream session regardless of authentication,
+    /// while offline this is the start of the previous stream or the time
+    /// the app connected when no stream has started since
+    pub fn last_session_started_at(&self) -> DateTime<Utc> {
+        self._inner.stream.read().session_started_at
+    }
+
+    /// Start of the current stream session
+    pub fn session_started_at(&self) -> Option<DateTime<Utc>> {
+        let state = &*self._inner.state.read();
+        match state {
+            TwitchManagerState::Initial => None,
+            TwitchManagerState::Authenticated(_) => {
+                Some(self._inner.stream.read().session_started_at)
+            }
+        }
+    }
+
+    pub async fn load_stream_status(&self) -> anyhow::Result<()> {
+        let stream = self.request_stream_status().await?;
+
+        let state = &mut *self._inner.stream.write();
+        state.live = stream.is_some();
+
+        // Use the actual stream start time when already live
+        if let Some(started_at) =
+            stream.and_then(|stream| DateTime::parse_from_rfc3339(stream.started_at.as_str()).ok())
+        {
+            state.session_started_at = started_at.with_timezone(&Utc);
+        }
+
+        Ok(())
+    }
+
+    async fn request_stream_status(&self) -> anyhow::Result<Option<Stream>> {
+        let user_token = self.get_user_token().context("not authenticated")?;
+        let user_id = user_token.user_id.clone();
+
+        let request = GetStreamsRequest::user_ids(vec![user_id]);
+        let streams: Vec<Stream> = self
+            .helix_client()
+            .req_get(request, &user_token)
+            .await?
+            .data;
+
+        Ok(streams.into_iter().next())
+    }
+
     async fn request_moderator_list(&self) -> anyhow::Result<Vec<Moderator>> {
         let user_token = self.get_user_token().context("not authenticated")?;
         let user_id = user_token.user_id.clone();
@@ -476,3 +807,23 @@ impl Twitch {
         &self._inner.helix_client
     }
 }
+
+#[cfg(test)]
+mod test {
+    use tokio::sync::mpsc;
+
+    use super::Twitch;
+
+    /// Tests that stream events toggle the live state
+    #[test]
+    fn test_set_live() {
+        let twitch = Twitch::new(mpsc::unbounded_channel().0);
+        assert!(!twitch.is_live());
+
+        twitch.set_live(true);
+        assert!(twitch.is_live());
+
+        twitch.set_live(false);
+        assert!(!twitch.is_live());
+    }
+}
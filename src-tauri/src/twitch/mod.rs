@@ -1,5 +1,6 @@
 use twitch_api::HelixClient;
 
+pub mod limits;
 pub mod manager;
 pub mod websocket;
 
@@ -5,8 +5,9 @@
 use super::TwitchClient;
 use crate::events::{
     AppEvent, AppEventSender, TwitchEventAdBreakBegin, TwitchEventChatMsg, TwitchEventCheerBits,
-    TwitchEventFollow, TwitchEventGiftSub, TwitchEventRaid, TwitchEventReSub, TwitchEventRedeem,
-    TwitchEventShoutoutReceive, TwitchEventSub,
+    TwitchEventFollow, TwitchEventGiftSub, TwitchEventHypeTrain, TwitchEventPoll,
+    TwitchEventPollChoice, TwitchEventPrediction, TwitchEventPredictionOutcome, TwitchEventRaid,
+    TwitchEventReSub, TwitchEventRedeem, TwitchEventShoutoutReceive, TwitchEventSub,
 };
 use anyhow::Context;
 use futures::{
@@ -28,6 +29,7 @@ use twitch_api::{
         Event, EventSubscription, Transport,
     },
     twitch_oauth2::{TwitchToken, UserToken},
+    types::{PollChoice, PredictionOutcome},
     HelixClient,
 };
 
@@ -85,6 +87,29 @@ async fn websocket_connect(connect_url: &str) -> tungstenite::Result<WsStream> {
         .map(|(socket, _)| socket)
 }
 
+fn map_poll_choices(choices: Vec<PollChoice>) -> Vec<TwitchEventPollChoice> {
+    choices
+        .into_iter()
+        .map(|choice| TwitchEventPollChoice {
+            id: choice.id,
+            title: choice.title,
+            votes: choice.votes.unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn map_prediction_outcomes(outcomes: Vec<PredictionOutcome>) -> Vec<TwitchEventPredictionOutcome> {
+    outcomes
+        .into_iter()
+        .map(|outcome| TwitchEventPredictionOutcome {
+            id: outcome.id,
+            title: outcome.title,
+            users: outcome.users.unwrap_or_default(),
+            channel_points: outcome.channel_points.unwrap_or_default(),
+        })
+        .collect()
+}
+
 fn map_message<E: EventSubscription + Clone>(
     message: eventsub::Message<E>,
 ) -> anyhow::Result<E::Payload> {
@@ -369,6 +394,149 @@ impl WebsocketClient {
                     .context("failed to send event")?;
             }
 
+            // Hype train started
+            Event::ChannelHypeTrainBeginV2(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::HypeTrainBegin(TwitchEventHypeTrain {
+                        level: msg.level,
+                        total: msg.total,
+                        progress: msg.progress,
+                        goal: msg.goal,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Hype train received a contribution
+            Event::ChannelHypeTrainProgressV2(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::HypeTrainProgress(TwitchEventHypeTrain {
+                        level: msg.level,
+                        total: msg.total,
+                        progress: msg.progress,
+                        goal: msg.goal,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Hype train ended
+            Event::ChannelHypeTrainEndV2(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::HypeTrainEnd(TwitchEventHypeTrain {
+                        level: msg.level,
+                        total: msg.total,
+                        progress: 0,
+                        goal: 0,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Poll started
+            Event::ChannelPollBeginV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PollBegin(TwitchEventPoll {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        choices: map_poll_choices(msg.choices),
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Poll received votes
+            Event::ChannelPollProgressV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PollProgress(TwitchEventPoll {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        choices: map_poll_choices(msg.choices),
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Poll ended
+            Event::ChannelPollEndV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PollEnd(TwitchEventPoll {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        choices: map_poll_choices(msg.choices),
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Prediction started
+            Event::ChannelPredictionBeginV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PredictionBegin(TwitchEventPrediction {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        outcomes: map_prediction_outcomes(msg.outcomes),
+                        winning_outcome_id: None,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Prediction received predictions
+            Event::ChannelPredictionProgressV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PredictionProgress(TwitchEventPrediction {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        outcomes: map_prediction_outcomes(msg.outcomes),
+                        winning_outcome_id: None,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Prediction locked
+            Event::ChannelPredictionLockV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PredictionLock(TwitchEventPrediction {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        outcomes: map_prediction_outcomes(msg.outcomes),
+                        winning_outcome_id: None,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Prediction resolved or canceled
+            Event::ChannelPredictionEndV1(payload) => {
+                let msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::PredictionEnd(TwitchEventPrediction {
+                        id: msg.id.to_string(),
+                        title: msg.title,
+                        outcomes: map_prediction_outcomes(msg.outcomes),
+                        winning_outcome_id: msg.winning_outcome_id,
+                    }))
+                    .context("failed to send event")?;
+            }
+
+            // Stream went live
+            Event::StreamOnlineV1(payload) => {
+                let _msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::StreamOnline)
+                    .context("failed to send event")?;
+            }
+
+            // Stream went offline
+            Event::StreamOfflineV1(payload) => {
+                let _msg = map_message(payload.message)?;
+                self.tx
+                    .send(AppEvent::StreamOffline)
+                    .context("failed to send event")?;
+            }
+
             _ => {}
         }
 
@@ -406,12 +574,16 @@ impl WebsocketClient {
     ) -> anyhow::Result<()> {
         use eventsub::channel::{
             ChannelAdBreakBeginV1, ChannelChatMessageV1, ChannelCheerV1, ChannelFollowV2,
+            ChannelHypeTrainBeginV2, ChannelHypeTrainEndV2, ChannelHypeTrainProgressV2,
             ChannelModeratorAddV1, ChannelModeratorRemoveV1, ChannelPointsCustomRewardAddV1,
             ChannelPointsCustomRewardRedemptionAddV1, ChannelPointsCustomRewardRemoveV1,
-            ChannelPointsCustomRewardUpdateV1, ChannelShoutoutReceiveV1, ChannelSubscribeV1,
-            ChannelSubscriptionGiftV1, ChannelSubscriptionMessageV1, ChannelVipAddV1,
-            ChannelVipRemoveV1,
+            ChannelPointsCustomRewardUpdateV1, ChannelPollBeginV1, ChannelPollEndV1,
+            ChannelPollProgressV1, ChannelPredictionBeginV1, ChannelPredictionEndV1,
+            ChannelPredictionLockV1, ChannelPredictionProgressV1, ChannelShoutoutReceiveV1,
+            ChannelSubscribeV1, ChannelSubscriptionGiftV1, ChannelSubscriptionMessageV1,
+            ChannelVipAddV1, ChannelVipRemoveV1,
         };
+        use eventsub::stream::{StreamOfflineV1, StreamOnlineV1};
 
         let user_id = token.user_id.clone();
         let transport = eventsub::Transport::websocket(session_id);
@@ -480,6 +652,54 @@ impl WebsocketClient {
                 user_id.clone(),
                 user_id.clone(),
             ))),
+            // Subscribe to hype train started
+            Box::new(EventSub(ChannelHypeTrainBeginV2::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to hype train progress
+            Box::new(EventSub(ChannelHypeTrainProgressV2::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to hype train ended
+            Box::new(EventSub(ChannelHypeTrainEndV2::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to poll started
+            Box::new(EventSub(ChannelPollBeginV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to poll progress
+            Box::new(EventSub(ChannelPollProgressV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to poll ended
+            Box::new(EventSub(ChannelPollEndV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to prediction started
+            Box::new(EventSub(ChannelPredictionBeginV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to prediction progress
+            Box::new(EventSub(ChannelPredictionProgressV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to prediction locked
+            Box::new(EventSub(ChannelPredictionLockV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to prediction ended
+            Box::new(EventSub(ChannelPredictionEndV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to stream going live
+            Box::new(EventSub(StreamOnlineV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
+            // Subscribe to stream going offline
+            Box::new(EventSub(StreamOfflineV1::broadcaster_user_id(
+                user_id.clone(),
+            ))),
         ];
 
         let mut subscriptions = subscriptions.into_iter().peekable();
@@ -11,6 +11,7 @@ import type {
   ExecutionsQuery,
   CommandExecution,
   CommandWithAliases,
+  ImportScriptPermissions,
 } from "$lib/api/types";
 
 import { invoke } from "@tauri-apps/api/core";
@@ -52,6 +53,15 @@ export function exportCommands(commandIds: CommandId[]) {
   return invoke<void>("export_commands", { commandIds });
 }
 
+/**
+ * Get the permissions the scripts of the commands will have once imported
+ */
+export function getImportCommandsPermissions(commands: unknown[]) {
+  return invoke<ImportScriptPermissions[]>("get_import_commands_permissions", {
+    commands,
+  });
+}
+
 export async function importCommands(commands: unknown[]) {
   await invoke("import_commands", { commands });
   invalidateCommandsList();
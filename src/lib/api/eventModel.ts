@@ -11,6 +11,7 @@ import type {
   EventExecution,
   VEvent as Event,
   ExecutionsQuery,
+  ImportScriptPermissions,
 } from "$lib/api/types";
 
 import { invoke } from "@tauri-apps/api/core";
@@ -57,6 +58,15 @@ export function exportEvents(eventIds: EventId[]) {
   return invoke<void>("export_events", { eventIds });
 }
 
+/**
+ * Get the permissions the scripts of the events will have once imported
+ */
+export function getImportEventsPermissions(events: unknown[]) {
+  return invoke<ImportScriptPermissions[]>("get_import_events_permissions", {
+    events,
+  });
+}
+
 export async function importEvents(events: unknown[]) {
   await invoke("import_events", { events });
   invalidateEventsList();
@@ -2,7 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { createQuery, type CreateQueryResult } from "@tanstack/svelte-query";
 
-import type { CustomReward } from "./types";
+import type { CreateReward, CustomReward, UpdateReward } from "./types";
 
 import { queryClient } from "./client";
 
@@ -31,6 +31,38 @@ export async function refreshRedeemsList() {
   queryClient.invalidateQueries({ queryKey: GET_REDEEMS_LIST_KEY });
 }
 
+function invalidateRedeemsList() {
+  queryClient.invalidateQueries({ queryKey: GET_REDEEMS_LIST_KEY });
+}
+
+/**
+ * Create a new custom channel point reward owned by VTFTK
+ */
+export async function createRedeem(
+  create: CreateReward,
+): Promise<CustomReward> {
+  const reward = await invoke<CustomReward>("create_redeem", { create });
+  invalidateRedeemsList();
+  return reward;
+}
+
+/**
+ * Update a custom channel point reward, only rewards created by
+ * VTFTK can be updated
+ */
+export async function updateRedeem(rewardId: string, update: UpdateReward) {
+  await invoke<void>("update_redeem", { rewardId, update });
+  invalidateRedeemsList();
+}
+
+/**
+ * Pause or unpause a custom channel point reward
+ */
+export async function setRedeemPaused(rewardId: string, paused: boolean) {
+  await invoke<void>("set_redeem_paused", { rewardId, paused });
+  invalidateRedeemsList();
+}
+
 /**
  * Create a query to fetch the runtime app data
  */
@@ -82,8 +82,45 @@ export type CommandConfig = {
   outcome: CommandOutcome;
   cooldown: CommandCooldown;
   require_role: MinimumRequiredRole;
+  match_mode?: CommandMatchMode;
+  arguments?: CommandArgument[];
 };
 
+export enum CommandArgumentType {
+  User = "User",
+  Integer = "Integer",
+  Choice = "Choice",
+  Rest = "Rest",
+}
+
+export type CommandArgument = {
+  // Name of the argument, used as the key for the parsed value
+  name: string;
+  // Whether the argument can be omitted
+  optional?: boolean;
+} & (
+  | { type: CommandArgumentType.User }
+  | {
+      type: CommandArgumentType.Integer;
+      min?: number | null;
+      max?: number | null;
+    }
+  | { type: CommandArgumentType.Choice; choices: string[] }
+  | { type: CommandArgumentType.Rest }
+);
+
+export enum CommandMatchMode {
+  CaseInsensitive = "CaseInsensitive",
+  CaseSensitive = "CaseSensitive",
+  Fuzzy = "Fuzzy",
+}
+
+export const COMMAND_MATCH_MODE_VALUES = [
+  CommandMatchMode.CaseInsensitive,
+  CommandMatchMode.CaseSensitive,
+  CommandMatchMode.Fuzzy,
+] as const;
+
 export type UpdateCommand = {
   commandId: CommandId;
   update: Partial<{
@@ -124,7 +161,8 @@ export type CreateEvent = {
 
 export type EventConfig = {
   trigger: EventTrigger;
-  outcome: EventOutcome;
+  outcomes: EventOutcomeStep[];
+  outcome_mode: EventOutcomeMode;
   cooldown: VEventCooldown;
   require_role: MinimumRequiredRole;
   outcome_delay: number;
@@ -502,12 +540,21 @@ export enum EventTriggerType {
   Timer = "Timer",
   AdBreakBegin = "AdBreakBegin",
   ShoutoutReceive = "ShoutoutReceive",
+  HypeTrain = "HypeTrain",
+  Poll = "Poll",
+  Prediction = "Prediction",
+  StreamOnline = "StreamOnline",
+  StreamOffline = "StreamOffline",
+  ChatPattern = "ChatPattern",
+  FirstMessage = "FirstMessage",
+  ReturningViewer = "ReturningViewer",
 }
 
 export type EventTrigger =
   | {
       type: EventTriggerType.Redeem;
       reward_id: string;
+      update_redemption?: boolean;
     }
   | { type: EventTriggerType.Command; message: string }
   | { type: EventTriggerType.Follow }
@@ -522,6 +569,10 @@ export type EventTrigger =
       type: EventTriggerType.Timer;
       interval: number;
       min_chat_messages: number;
+      schedule?: TimerSchedule | null;
+      days?: Weekday[];
+      jitter?: number;
+      missed_runs?: MissedRunPolicy;
     }
   | {
       type: EventTriggerType.AdBreakBegin;
@@ -529,8 +580,91 @@ export type EventTrigger =
   | {
       type: EventTriggerType.ShoutoutReceive;
       min_viewers: number;
+    }
+  | {
+      type: EventTriggerType.HypeTrain;
+      phase: HypeTrainPhase;
+      min_level?: number;
+    }
+  | { type: EventTriggerType.Poll; phase: PollPhase }
+  | { type: EventTriggerType.Prediction; phase: PredictionPhase }
+  | { type: EventTriggerType.StreamOnline }
+  | { type: EventTriggerType.StreamOffline }
+  | {
+      type: EventTriggerType.ChatPattern;
+      pattern: string;
+      match_type?: ChatPatternMatchType;
+      case_sensitive?: boolean;
+    }
+  | { type: EventTriggerType.FirstMessage; scope?: FirstMessageScope }
+  | { type: EventTriggerType.ReturningViewer; days: number };
+
+export enum TimerScheduleType {
+  Cron = "Cron",
+  TimesOfDay = "TimesOfDay",
+}
+
+/**
+ * Calendar based schedule for a timer, evaluated in local time
+ */
+export type TimerSchedule =
+  | { type: TimerScheduleType.Cron; expression: string }
+  | {
+      type: TimerScheduleType.TimesOfDay;
+      /** Times of the day to run at (i.e "09:30:00") */
+      times: string[];
     };
 
+export enum Weekday {
+  Mon = "Mon",
+  Tue = "Tue",
+  Wed = "Wed",
+  Thu = "Thu",
+  Fri = "Fri",
+  Sat = "Sat",
+  Sun = "Sun",
+}
+
+/**
+ * How a timer handles runs that were missed while the app was closed
+ */
+export enum MissedRunPolicy {
+  Skip = "Skip",
+  RunOnce = "RunOnce",
+  CatchUp = "CatchUp",
+}
+
+export enum HypeTrainPhase {
+  Begin = "Begin",
+  Progress = "Progress",
+  End = "End",
+}
+
+export enum PollPhase {
+  Begin = "Begin",
+  Progress = "Progress",
+  End = "End",
+}
+
+export enum PredictionPhase {
+  Begin = "Begin",
+  Progress = "Progress",
+  Lock = "Lock",
+  End = "End",
+}
+
+export enum ChatPatternMatchType {
+  Substring = "Substring",
+  Glob = "Glob",
+  Regex = "Regex",
+  Emote = "Emote",
+}
+
+export enum FirstMessageScope {
+  Session = "Session",
+  Ever = "Ever",
+}
+
 export enum ThrowableDataType {
   Throw = "Throw",
   Barrage = "Barrage",
@@ -580,6 +714,10 @@ export enum EventOutcomeType {
   SendChatMessage = "SendChatMessage",
   Script = "Script",
   ChannelEmotes = "ChannelEmotes",
+  Random = "Random",
+  CreatePoll = "CreatePoll",
+  CreatePrediction = "CreatePrediction",
+  ToggleReward = "ToggleReward",
 }
 
 export type EventOutcomeBits = {
@@ -597,10 +735,26 @@ export type EventOutcomeThrowable = {
 export type EventOutcomeTriggerHotkey = { hotkey_id: Uuid };
 export type EventOutcomePlaySound = { sound_id: Uuid };
 export type EventOutcomeSendChatMessage = { template: string };
-export type EventOutcomeScript = { script: string };
+export type EventOutcomeScript = {
+  script: string;
+  permissions?: ScriptPermissions | null;
+  limits?: ScriptLimits;
+};
 export type EventOutcomeChannelEmotes = {
   amount: ThrowableData;
 };
+export type EventOutcomeRandom = { outcomes: WeightedEventOutcome[] };
+export type WeightedEventOutcome = { weight: number; outcome: EventOutcome };
+export type EventOutcomeCreatePoll = {
+  title: string;
+  choices: string[];
+  duration: number;
+};
+export type EventOutcomeCreatePrediction = {
+  title: string;
+  outcomes: string[];
+  duration: number;
+};
 export type EventOutcome =
   | ({ type: EventOutcomeType.ThrowBits } & EventOutcomeBits)
   | ({ type: EventOutcomeType.Throwable } & EventOutcomeThrowable)
@@ -608,7 +762,21 @@ export type EventOutcome =
   | ({ type: EventOutcomeType.PlaySound } & EventOutcomePlaySound)
   | ({ type: EventOutcomeType.SendChatMessage } & EventOutcomeSendChatMessage)
   | ({ type: EventOutcomeType.Script } & EventOutcomeScript)
-  | ({ type: EventOutcomeType.ChannelEmotes } & EventOutcomeChannelEmotes);
+  | ({ type: EventOutcomeType.ChannelEmotes } & EventOutcomeChannelEmotes)
+  | ({ type: EventOutcomeType.Random } & EventOutcomeRandom)
+  | ({ type: EventOutcomeType.CreatePoll } & EventOutcomeCreatePoll)
+  | ({ type: EventOutcomeType.CreatePrediction } & EventOutcomeCreatePrediction)
+  | ({ type: EventOutcomeType.ToggleReward } & EventOutcomeToggleReward);
+
+export type EventOutcomeStep = {
+  delay: number;
+  outcome: EventOutcome;
+};
+
+export enum EventOutcomeMode {
+  Sequential = "Sequential",
+  Parallel = "Parallel",
+}
 
 export type EventOutcomeVariant<T extends EventOutcomeType> = Extract<
   EventOutcome,
@@ -618,13 +786,86 @@ export type EventOutcomeVariant<T extends EventOutcomeType> = Extract<
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 export type CustomReward = any;
 
+/**
+ * Details for creating a new custom channel point reward
+ */
+export type CreateReward = {
+  /** Title of the reward (Max 45 characters) */
+  title: string;
+  /** Cost of the reward in channel points (Min 1) */
+  cost: number;
+  /** Prompt shown to the viewer when redeeming (Max 200 characters) */
+  prompt?: string | null;
+  /** Global cooldown in seconds, zero disables the cooldown (Max 7 days) */
+  cooldown?: number | null;
+  /** Whether the reward is enabled, rewards are enabled by default */
+  enabled?: boolean | null;
+};
+
+/**
+ * Changes to apply to an existing custom channel point reward, only
+ * the fields that are specified are changed
+ */
+export type UpdateReward = {
+  title?: string | null;
+  cost?: number | null;
+  prompt?: string | null;
+  cooldown?: number | null;
+  /** Whether the reward is enabled (Hidden from viewers when disabled) */
+  enabled?: boolean | null;
+  /** Whether the reward is paused (Visible but cannot be redeemed) */
+  paused?: boolean | null;
+};
+
+/**
+ * Change to make to a channel point reward
+ */
+export enum ToggleRewardAction {
+  Enable = "Enable",
+  Disable = "Disable",
+  Pause = "Pause",
+  Unpause = "Unpause",
+}
+
+export type EventOutcomeToggleReward = {
+  reward_id: string;
+  action: ToggleRewardAction;
+};
+
 export enum CommandOutcomeType {
   Template = "Template",
   Script = "Script",
 }
 
 export type CommandOutcomeTemplate = { message: string };
-export type CommandOutcomeScript = { script: string };
+export type CommandOutcomeScript = {
+  script: string;
+  permissions?: ScriptPermissions | null;
+  limits?: ScriptLimits;
+};
+
+// Permissions requested by a script, scripts without permissions are
+// unrestricted when written locally and denied all access when imported
+export type ScriptPermissions = {
+  http_hosts: string[];
+  twitch: boolean;
+  kv_namespaces: string[];
+  overlay: boolean;
+};
+
+// Execution limits for a script in milliseconds, limits that are not
+// set use the defaults from the script runtime settings
+export type ScriptLimits = {
+  wall_clock_limit?: number | null;
+  cpu_time_limit?: number | null;
+};
+
+// Permissions the scripts of an event or command will have once
+// imported, scripts without declared permissions are denied all access
+export type ImportScriptPermissions = {
+  name: string;
+  scripts: ScriptPermissions[];
+};
 
 export type CommandOutcome =
   | ({ type: CommandOutcomeType.Template } & CommandOutcomeTemplate)
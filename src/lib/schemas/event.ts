@@ -1,17 +1,55 @@
 import { z } from "zod";
 import { minMax } from "$lib/utils/validation";
 import {
+  Weekday,
+  PollPhase,
+  HypeTrainPhase,
+  PredictionPhase,
+  MissedRunPolicy,
+  EventOutcomeMode,
   EventOutcomeType,
   EventTriggerType,
   ThrowableDataType,
+  FirstMessageScope,
+  TimerScheduleType,
+  ToggleRewardAction,
   MinimumRequiredRole,
+  ChatPatternMatchType,
+  type EventOutcome,
   MINIMUM_REQUIRED_ROLE_VALUES,
 } from "$lib/api/types";
 
+export const scriptPermissionsSchema = z.object({
+  http_hosts: z.array(z.string()),
+  twitch: z.boolean(),
+  kv_namespaces: z.array(z.string()),
+  overlay: z.boolean(),
+});
+
+// Limits that are not set use the defaults from the script runtime settings
+export const scriptLimitsSchema = z.object({
+  wall_clock_limit: z.number().int().min(1).nullish(),
+  cpu_time_limit: z.number().int().min(1).nullish(),
+});
+
+const timerScheduleSchema = z.discriminatedUnion("type", [
+  z.object({
+    type: z.literal(TimerScheduleType.Cron),
+    expression: z.string().min(1, "Cron expression is required"),
+  }),
+  z.object({
+    type: z.literal(TimerScheduleType.TimesOfDay),
+    times: z.array(z.string()).min(1, "At least one time is required"),
+  }),
+]);
+
+export type TimerScheduleSchema = z.infer<typeof timerScheduleSchema>;
+
 export const eventTriggerSchema = z.discriminatedUnion("type", [
   z.object({
     type: z.literal(EventTriggerType.Redeem),
     reward_id: z.string(),
+    update_redemption: z.boolean().optional(),
   }),
   z.object({
     type: z.literal(EventTriggerType.Command),
@@ -38,6 +76,10 @@ export const eventTriggerSchema = z.discriminatedUnion("type", [
     type: z.literal(EventTriggerType.Timer),
     interval: z.number(),
     min_chat_messages: z.number(),
+    schedule: timerScheduleSchema.nullish(),
+    days: z.array(z.enum(Weekday)).optional(),
+    jitter: z.number().min(0).optional(),
+    missed_runs: z.enum(MissedRunPolicy).optional(),
   }),
   z.object({
     type: z.literal(EventTriggerType.AdBreakBegin),
@@ -46,6 +88,39 @@ export const eventTriggerSchema = z.discriminatedUnion("type", [
     type: z.literal(EventTriggerType.ShoutoutReceive),
     min_viewers: z.number(),
   }),
+  z.object({
+    type: z.literal(EventTriggerType.HypeTrain),
+    phase: z.enum(HypeTrainPhase),
+    min_level: z.number().min(0).optional(),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.Poll),
+    phase: z.enum(PollPhase),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.Prediction),
+    phase: z.enum(PredictionPhase),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.StreamOnline),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.StreamOffline),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.ChatPattern),
+    pattern: z.string().min(1, "Pattern is required"),
+    match_type: z.enum(ChatPatternMatchType).optional(),
+    case_sensitive: z.boolean().optional(),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.FirstMessage),
+    scope: z.enum(FirstMessageScope).optional(),
+  }),
+  z.object({
+    type: z.literal(EventTriggerType.ReturningViewer),
+    days: z.number().min(1),
+  }),
 ]);
 
 export type EventTriggerSchema = z.infer<typeof eventTriggerSchema>;
@@ -74,6 +149,15 @@ export const throwableDataSchema = z.discriminatedUnion("type", [
 
 export type ThrowableDataSchema = z.infer<typeof throwableDataSchema>;
 
+// Outcomes picked by a random outcome, the nested outcome is typed using
+// the API type to avoid a recursive schema type
+const weightedEventOutcomeSchema = z.object({
+  weight: z.number().min(0),
+  get outcome(): z.ZodType<EventOutcome> {
+    return eventOutcomeSchema;
+  },
+});
+
 export const eventOutcomeSchema = z.discriminatedUnion("type", [
   z.object({
     type: z.literal(EventOutcomeType.ThrowBits),
@@ -105,11 +189,36 @@ export const eventOutcomeSchema = z.discriminatedUnion("type", [
   z.object({
     type: z.literal(EventOutcomeType.Script),
     script: z.string(),
+    permissions: scriptPermissionsSchema.nullish(),
+    limits: scriptLimitsSchema.optional(),
   }),
   z.object({
     type: z.literal(EventOutcomeType.ChannelEmotes),
     amount: throwableDataSchema,
   }),
+  z.object({
+    type: z.literal(EventOutcomeType.Random),
+    outcomes: z
+      .array(weightedEventOutcomeSchema)
+      .min(1, "At least one outcome is required"),
+  }),
+  z.object({
+    type: z.literal(EventOutcomeType.CreatePoll),
+    title: z.string().min(1, "Title is required").max(60),
+    choices: z.array(z.string().min(1).max(25)).min(2).max(5),
+    duration: z.number().min(15).max(1800),
+  }),
+  z.object({
+    type: z.literal(EventOutcomeType.CreatePrediction),
+    title: z.string().min(1, "Title is required").max(45),
+    outcomes: z.array(z.string().min(1).max(25)).min(2).max(10),
+    duration: z.number().min(30).max(1800),
+  }),
+  z.object({
+    type: z.literal(EventOutcomeType.ToggleReward),
+    reward_id: z.string().min(1, "Reward is required"),
+    action: z.enum(ToggleRewardAction),
+  }),
 ]);
 
 export type EventOutcomeSchema = z.infer<typeof eventOutcomeSchema>;
@@ -120,9 +229,19 @@ const cooldownSchema = z.object({
   per_user: z.boolean(),
 });
 
+const eventOutcomeStepSchema = z.object({
+  delay: z.number().min(0),
+  outcome: eventOutcomeSchema,
+});
+
+export type EventOutcomeStepSchema = z.infer<typeof eventOutcomeStepSchema>;
+
 const eventConfigSchema = z.object({
   trigger: eventTriggerSchema,
-  outcome: eventOutcomeSchema,
+  outcomes: z
+    .array(eventOutcomeStepSchema)
+    .min(1, "At least one outcome is required"),
+  outcome_mode: z.enum(EventOutcomeMode),
   require_role: z.enum(MINIMUM_REQUIRED_ROLE_VALUES),
   cooldown: cooldownSchema,
   outcome_delay: z.number(),
@@ -203,6 +322,16 @@ export function getEventOutcomeDefaults(): Record<
     [EventOutcomeType.ChannelEmotes]: getEventOutcomeDefault(
       EventOutcomeType.ChannelEmotes,
     ),
+    [EventOutcomeType.Random]: getEventOutcomeDefault(EventOutcomeType.Random),
+    [EventOutcomeType.CreatePoll]: getEventOutcomeDefault(
+      EventOutcomeType.CreatePoll,
+    ),
+    [EventOutcomeType.CreatePrediction]: getEventOutcomeDefault(
+      EventOutcomeType.CreatePrediction,
+    ),
+    [EventOutcomeType.ToggleReward]: getEventOutcomeDefault(
+      EventOutcomeType.ToggleReward,
+    ),
   };
 }
 
@@ -265,6 +394,7 @@ export function getEventOutcomeDefault(
       return {
         type: EventOutcomeType.Script,
         script: "",
+        limits: {},
       };
     case EventOutcomeType.ChannelEmotes:
       return {
@@ -281,6 +411,31 @@ export function getEventOutcomeDefault(
           },
         },
       };
+    case EventOutcomeType.Random:
+      return {
+        type: EventOutcomeType.Random,
+        outcomes: [],
+      };
+    case EventOutcomeType.CreatePoll:
+      return {
+        type: EventOutcomeType.CreatePoll,
+        title: "",
+        choices: ["", ""],
+        duration: 60,
+      };
+    case EventOutcomeType.CreatePrediction:
+      return {
+        type: EventOutcomeType.CreatePrediction,
+        title: "",
+        outcomes: ["", ""],
+        duration: 60,
+      };
+    case EventOutcomeType.ToggleReward:
+      return {
+        type: EventOutcomeType.ToggleReward,
+        reward_id: "",
+        action: ToggleRewardAction.Enable,
+      };
   }
 }
 
@@ -309,6 +464,28 @@ export function getEventTriggerDefaults(): Record<
     [EventTriggerType.ShoutoutReceive]: getEventTriggerDefault(
       EventTriggerType.ShoutoutReceive,
     ),
+    [EventTriggerType.HypeTrain]: getEventTriggerDefault(
+      EventTriggerType.HypeTrain,
+    ),
+    [EventTriggerType.Poll]: getEventTriggerDefault(EventTriggerType.Poll),
+    [EventTriggerType.Prediction]: getEventTriggerDefault(
+      EventTriggerType.Prediction,
+    ),
+    [EventTriggerType.StreamOnline]: getEventTriggerDefault(
+      EventTriggerType.StreamOnline,
+    ),
+    [EventTriggerType.StreamOffline]: getEventTriggerDefault(
+      EventTriggerType.StreamOffline,
+    ),
+    [EventTriggerType.ChatPattern]: getEventTriggerDefault(
+      EventTriggerType.ChatPattern,
+    ),
+    [EventTriggerType.FirstMessage]: getEventTriggerDefault(
+      EventTriggerType.FirstMessage,
+    ),
+    [EventTriggerType.ReturningViewer]: getEventTriggerDefault(
+      EventTriggerType.ReturningViewer,
+    ),
   };
 }
 
@@ -335,11 +512,43 @@ export function getEventTriggerDefault(
         type: EventTriggerType.Timer,
         interval: 60,
         min_chat_messages: 2,
+        schedule: null,
+        days: [],
+        jitter: 0,
+        missed_runs: MissedRunPolicy.Skip,
       };
     case EventTriggerType.AdBreakBegin:
       return { type: EventTriggerType.AdBreakBegin };
     case EventTriggerType.ShoutoutReceive:
       return { type: EventTriggerType.ShoutoutReceive, min_viewers: 1 };
+    case EventTriggerType.HypeTrain:
+      return {
+        type: EventTriggerType.HypeTrain,
+        phase: HypeTrainPhase.Begin,
+        min_level: 1,
+      };
+    case EventTriggerType.Poll:
+      return { type: EventTriggerType.Poll, phase: PollPhase.End };
+    case EventTriggerType.Prediction:
+      return { type: EventTriggerType.Prediction, phase: PredictionPhase.End };
+    case EventTriggerType.StreamOnline:
+      return { type: EventTriggerType.StreamOnline };
+    case EventTriggerType.StreamOffline:
+      return { type: EventTriggerType.StreamOffline };
+    case EventTriggerType.ChatPattern:
+      return {
+        type: EventTriggerType.ChatPattern,
+        pattern: "",
+        match_type: ChatPatternMatchType.Substring,
+        case_sensitive: false,
+      };
+    case EventTriggerType.FirstMessage:
+      return {
+        type: EventTriggerType.FirstMessage,
+        scope: FirstMessageScope.Session,
+      };
+    case EventTriggerType.ReturningViewer:
+      return { type: EventTriggerType.ReturningViewer, days: 30 };
   }
 }
 
@@ -349,7 +558,13 @@ export function getDefaultEvent(): EventSchema {
     enabled: true,
     config: {
       trigger: getEventTriggerDefault(EventTriggerType.Redeem),
-      outcome: getEventOutcomeDefault(EventOutcomeType.Throwable),
+      outcomes: [
+        {
+          delay: 0,
+          outcome: getEventOutcomeDefault(EventOutcomeType.Throwable),
+        },
+      ],
+      outcome_mode: EventOutcomeMode.Sequential,
       require_role: MinimumRequiredRole.None,
       cooldown: { enabled: false, duration: 0, per_user: false },
       outcome_delay: 0,
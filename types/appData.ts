@@ -37,8 +37,42 @@ export type MainConfig = {
   clean_chat_history_days: number;
   auto_updating: boolean;
   http_port: number;
+  pause_mode: PauseMode;
+  command_prefixes: string[];
+  mid_message_commands: boolean;
+  command_suggestions: boolean;
+  script_runtime: ScriptRuntimeConfig;
 };
 
+// How events that arrive while processing is paused are handled
+export enum PauseMode {
+  Drop = "Drop",
+  Buffer = "Buffer",
+}
+
+export const PAUSE_MODE_VALUES = [PauseMode.Drop, PauseMode.Buffer] as const;
+
+// Settings for the runtimes scripts are run on, applied on restart
+export type ScriptRuntimeConfig = {
+  pool_size: number;
+  isolation: ScriptIsolation;
+  memory_limit_mb: number;
+  wall_clock_limit: number;
+  cpu_time_limit: number;
+};
+
+export enum ScriptIsolation {
+  Shared = "Shared",
+  PerScript = "PerScript",
+  PerExecution = "PerExecution",
+}
+
+export const SCRIPT_ISOLATION_VALUES = [
+  ScriptIsolation.Shared,
+  ScriptIsolation.PerScript,
+  ScriptIsolation.PerExecution,
+] as const;
+
 export type ThrowablesConfig = {
   duration: number;
   spin_speed: MinMax;